@@ -0,0 +1,34 @@
+use rocket::data::{self, Data, FromData, ToByteUnit};
+use rocket::http::{ContentType, Status};
+use rocket::outcome::Outcome;
+use rocket::request::Request;
+
+/// CSV of the bulk candidate import
+pub struct CandidateImport(Vec<u8>);
+
+impl Into<Vec<u8>> for CandidateImport {
+    fn into(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromData<'r> for CandidateImport {
+    type Error = Option<String>;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        if req.content_type() != Some(&ContentType::CSV) && req.content_type() != Some(&ContentType::Plain) {
+            return Outcome::Failure((Status::BadRequest, None))
+        }
+
+        let data = data.open(2.megabytes());
+
+        let data_bytes = data.into_bytes().await.unwrap();
+
+        if !data_bytes.is_complete() {
+            return Outcome::Failure((Status::PayloadTooLarge, None))
+        }
+
+        Outcome::Success(CandidateImport(data_bytes.into_inner()))
+    }
+}
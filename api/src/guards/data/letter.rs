@@ -25,7 +25,7 @@ impl<'r> FromData<'r> for Letter {
         let data_bytes = data.into_bytes().await.unwrap();
 
         if !data_bytes.is_complete() {
-            return Outcome::Failure((Status::BadRequest, None))
+            return Outcome::Failure((Status::PayloadTooLarge, None))
         }
 
         let data_bytes = data_bytes.into_inner();
@@ -1,2 +1,3 @@
 pub mod portfolio;
-pub mod letter;
\ No newline at end of file
+pub mod letter;
+pub mod import;
\ No newline at end of file
@@ -1,13 +1,17 @@
+use portfolio_core::utils::filetype::{inspect_zip, ZipLimits, ZipManifest};
 use rocket::data::{self, Data, FromData, ToByteUnit};
 use rocket::http::{ContentType, Status};
 use rocket::outcome::Outcome;
 use rocket::request::Request;
 
-pub struct Portfolio(Vec<u8>);
+use crate::responses::guard_error;
 
-impl Into<Vec<u8>> for Portfolio {
-    fn into(self) -> Vec<u8> {
-        self.0
+pub struct Portfolio(Vec<u8>, ZipManifest);
+
+impl Portfolio {
+    /// Zip data and manifest of its content
+    pub fn into_parts(self) -> (Vec<u8>, ZipManifest) {
+        (self.0, self.1)
     }
 }
 
@@ -25,7 +29,7 @@ impl<'r> FromData<'r> for Portfolio {
         let data_bytes = data.into_bytes().await.unwrap();
 
         if !data_bytes.is_complete() {
-            return Outcome::Failure((Status::BadRequest, None))
+            return Outcome::Failure((Status::PayloadTooLarge, None))
         }
 
         let data_bytes = data_bytes.into_inner();
@@ -36,6 +40,15 @@ impl<'r> FromData<'r> for Portfolio {
             return Outcome::Failure((Status::BadRequest, None))
         }
 
-        Outcome::Success(Portfolio(data_bytes))
+        let manifest = match inspect_zip(&data_bytes, &ZipLimits::from_env()).await {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                info!("PORTFOLIO ZIP REJECTED: {}", e);
+                let message = e.to_string();
+                return Outcome::Failure((guard_error(req, e), Some(message)))
+            },
+        };
+
+        Outcome::Success(Portfolio(data_bytes, manifest))
     }
 }
@@ -1,6 +1,5 @@
 use entity::admin::Model as Admin;
 use log::info;
-use portfolio_core::models::auth::AuthenticableTrait;
 use portfolio_core::sea_orm::prelude::Uuid;
 use portfolio_core::services::admin_service::AdminService;
 use rocket::http::Status;
@@ -22,6 +21,10 @@ impl AdminAuth {
     pub fn get_private_key(&self) -> String {
         self.1.clone()
     }
+
+    pub fn admin(&self) -> &Admin {
+        &self.0
+    }
 }
 
 #[rocket::async_trait]
@@ -29,18 +32,19 @@ impl<'r> FromRequest<'r> for AdminAuth {
     type Error = Option<String>;
     async fn from_request(req: &'r Request<'_>) -> Outcome<AdminAuth, (Status, Self::Error), ()> {
         let cookie_id = req.cookies().get_private("id");
-        let cookie_private_key = req.cookies().get_private("key");
+        // secret of the session key slot, the private key itself never leaves the server
+        let cookie_key_secret = req.cookies().get_private("key");
 
         let Some(cookie_id) = cookie_id else {
             return Outcome::Failure((Status::Unauthorized, None));
         };
 
-        let Some(cookie_private_key) = cookie_private_key else {
+        let Some(cookie_key_secret) = cookie_key_secret else {
             return Outcome::Failure((Status::Unauthorized, None));
         };
 
         let session_id = cookie_id.value();
-        let private_key = cookie_private_key.value();
+        let key_secret = cookie_key_secret.value();
 
         let conn = &req.rocket().state::<Db>().unwrap().conn;
 
@@ -49,12 +53,12 @@ impl<'r> FromRequest<'r> for AdminAuth {
             Err(_) => return Outcome::Failure((Status::BadRequest, None)),
         };
 
-        let session = AdminService::auth(conn, uuid).await;
+        let session = AdminService::auth_with_private_key(conn, uuid, key_secret.to_string()).await;
 
         match session {
-            Ok(model) => {
+            Ok((model, private_key)) => {
                 warn!("{}: ADMIN {} AUTHENTICATED", format_request(req), model.id);
-                Outcome::Success(AdminAuth(model, private_key.to_string()))
+                Outcome::Success(AdminAuth(model, private_key))
             },
             Err(e) => {
                 info!("{}: ADMIN AUTHENTICATION FAILED: {}", format_request(req), e);
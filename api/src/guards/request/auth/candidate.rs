@@ -1,5 +1,4 @@
 use entity::application::Model as Application;
-use portfolio_core::models::auth::AuthenticableTrait;
 use portfolio_core::sea_orm::prelude::Uuid;
 use portfolio_core::services::application_service::ApplicationService;
 use rocket::http::Status;
@@ -23,44 +22,60 @@ impl ApplicationAuth {
     }
 }
 
-#[rocket::async_trait]
-impl<'r> FromRequest<'r> for ApplicationAuth {
-    type Error = Option<String>;
-    async fn from_request(
-        req: &'r Request<'_>,
-    ) -> Outcome<ApplicationAuth, (Status, Self::Error), ()> {
-        let cookie_id = req.cookies().get_private("id");
-        let cookie_private_key = req.cookies().get_private("key");
+/// Result of the authentication cached for the request, so that other guards
+/// can depend on `ApplicationAuth` without authenticating twice
+struct CachedApplicationAuth(Result<(Application, String), Status>);
+
+async fn authenticate(req: &Request<'_>) -> Result<(Application, String), Status> {
+    let cookie_id = req.cookies().get_private("id");
+    // secret of the session key slot, the private key itself never leaves the server
+    let cookie_key_secret = req.cookies().get_private("key");
 
-        let Some(cookie_id) = cookie_id else {
-            return Outcome::Failure((Status::Unauthorized, None));
-        };
+    let Some(cookie_id) = cookie_id else {
+        return Err(Status::Unauthorized);
+    };
 
-        let Some(cookie_private_key) = cookie_private_key else {
-            return Outcome::Failure((Status::Unauthorized, None));
-        };
+    let Some(cookie_key_secret) = cookie_key_secret else {
+        return Err(Status::Unauthorized);
+    };
 
-        let session_id = cookie_id.value();
-        let private_key = cookie_private_key.value();
+    let session_id = cookie_id.value();
+    let key_secret = cookie_key_secret.value();
 
-        let conn = &req.rocket().state::<Db>().unwrap().conn;
+    let conn = &req.rocket().state::<Db>().unwrap().conn;
 
-        let uuid = match Uuid::parse_str(&session_id) {
-            Ok(uuid) => uuid,
-            Err(_) => return Outcome::Failure((Status::BadRequest, None)),
-        };
+    let uuid = match Uuid::parse_str(&session_id) {
+        Ok(uuid) => uuid,
+        Err(_) => return Err(Status::BadRequest),
+    };
 
-        let session = ApplicationService::auth(conn, uuid).await;
+    let session = ApplicationService::auth_with_private_key(conn, uuid, key_secret.to_string()).await;
+
+    match session {
+        Ok((model, private_key)) => {
+            info!("{}: CANDIDATE {} AUTHENTICATED", format_request(req), model.id);
+            Ok((model, private_key))
+        },
+        Err(e) => {
+            info!("{}: CANDIDATE {} AUTHENTICATION FAILED", format_request(req), e);
+            Err(Status::Unauthorized)
+        },
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ApplicationAuth {
+    type Error = Option<String>;
+    async fn from_request(
+        req: &'r Request<'_>,
+    ) -> Outcome<ApplicationAuth, (Status, Self::Error), ()> {
+        let cached = req.local_cache_async(async {
+            CachedApplicationAuth(authenticate(req).await)
+        }).await;
 
-        match session {
-            Ok(model) => {
-                info!("{}: CANDIDATE {} AUTHENTICATED", format_request(req), model.id);
-                Outcome::Success(ApplicationAuth(model, private_key.to_string().to_string()))
-            },
-            Err(e) => {
-                info!("{}: CANDIDATE {} AUTHENTICATION FAILED", format_request(req), e);
-                Outcome::Failure((Status::Unauthorized, None))
-            },
+        match &cached.0 {
+            Ok((model, private_key)) => Outcome::Success(ApplicationAuth(model.clone(), private_key.clone())),
+            Err(status) => Outcome::Failure((*status, None)),
         }
     }
 }
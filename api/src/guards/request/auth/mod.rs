@@ -1,5 +1,7 @@
 pub mod admin;
 pub mod candidate;
+pub mod permission;
 
 pub use admin::*;
 pub use candidate::*;
+pub use permission::*;
@@ -0,0 +1,81 @@
+use std::marker::PhantomData;
+
+use entity::admin::Model as Admin;
+use portfolio_core::error::ServiceError;
+use portfolio_core::models::admin::{AdminRole, FieldScope, Permission};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{FromRequest, Request};
+
+use crate::logging::format_request;
+use crate::responses::guard_error;
+
+use super::AdminAuth;
+
+/// Permission a route requires, used as `Authorized<CreateCandidates>`
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+macro_rules! required_permissions {
+    ($($name:ident),* $(,)?) => {
+        $(
+            pub struct $name;
+
+            impl RequiredPermission for $name {
+                const PERMISSION: Permission = Permission::$name;
+            }
+        )*
+    };
+}
+
+required_permissions!(
+    ListCandidates,
+    ReadCandidates,
+    ReadPortfolios,
+    ExportData,
+    CreateCandidates,
+    EditCandidates,
+    DeleteCandidates,
+    ManageSettings,
+    ManageAdmins,
+);
+
+/// Logged in admin whose role grants the permission `P`, other admins get 403
+pub struct Authorized<P: RequiredPermission>(AdminAuth, PhantomData<P>);
+
+impl<P: RequiredPermission> Into<Admin> for Authorized<P> {
+    fn into(self) -> Admin {
+        self.0.into()
+    }
+}
+
+impl<P: RequiredPermission> Authorized<P> {
+    pub fn get_private_key(&self) -> String {
+        self.0.get_private_key()
+    }
+
+    /// Fields of study whose candidates the admin can see
+    pub fn scope(&self) -> FieldScope {
+        FieldScope::of(self.0.admin())
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, P: RequiredPermission + Send + Sync> FromRequest<'r> for Authorized<P> {
+    type Error = Option<String>;
+    async fn from_request(req: &'r Request<'_>) -> Outcome<Authorized<P>, (Status, Self::Error), ()> {
+        let session = match req.guard::<AdminAuth>().await {
+            Outcome::Success(session) => session,
+            Outcome::Failure(e) => return Outcome::Failure(e),
+            Outcome::Forward(f) => return Outcome::Forward(f),
+        };
+
+        if !AdminRole::allows(session.admin(), P::PERMISSION) {
+            warn!("{}: ADMIN {} LACKS PERMISSION {:?}", format_request(req), session.admin().id, P::PERMISSION);
+            return Outcome::Failure((guard_error(req, ServiceError::Forbidden), None));
+        }
+
+        Outcome::Success(Authorized(session, PhantomData))
+    }
+}
@@ -0,0 +1,151 @@
+use std::convert::Infallible;
+use std::net::{IpAddr, Ipv4Addr};
+
+use rocket::outcome::Outcome;
+use rocket::request::{FromRequest, Request};
+
+/// Proxies (Traefik) allowed to tell the client address in `X-Forwarded-For` or `X-Real-IP`,
+/// configured as comma separated CIDRs in `PORTFOLIO_TRUSTED_PROXIES`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrustedProxies(Vec<(IpAddr, u8)>);
+
+impl TrustedProxies {
+    pub fn parse(cidrs: &str) -> Self {
+        let networks = cidrs.split(',')
+            .map(str::trim)
+            .filter(|cidr| !cidr.is_empty())
+            .filter_map(|cidr| {
+                let network = Self::parse_cidr(cidr);
+                if network.is_none() {
+                    warn!("INVALID TRUSTED PROXY {}", cidr);
+                }
+                network
+            })
+            .collect();
+
+        Self(networks)
+    }
+
+    pub fn from_env() -> Self {
+        Self::parse(&std::env::var("PORTFOLIO_TRUSTED_PROXIES").unwrap_or_default())
+    }
+
+    /// `10.0.0.0/8` or a single address
+    fn parse_cidr(cidr: &str) -> Option<(IpAddr, u8)> {
+        let (ip, prefix) = cidr.split_once('/').unwrap_or((cidr, ""));
+        let ip: IpAddr = ip.parse().ok()?;
+        let max_prefix = if ip.is_ipv4() { 32 } else { 128 };
+        let prefix = if prefix.is_empty() { max_prefix } else { prefix.parse().ok()? };
+
+        (prefix <= max_prefix).then_some((ip, prefix))
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.0.iter().any(|(network, prefix)| match (network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+                u32::from(*network) & mask == u32::from(ip) & mask
+            },
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+                u128::from(*network) & mask == u128::from(ip) & mask
+            },
+            _ => false,
+        })
+    }
+
+    /// Address of the client, headers are used only when the request came from a trusted proxy.
+    /// `X-Forwarded-For` is read from the right, the first address not belonging to a trusted proxy is the client.
+    pub fn resolve(&self, peer: IpAddr, forwarded_for: Option<&str>, real_ip: Option<&str>) -> IpAddr {
+        if !self.contains(peer) {
+            return peer;
+        }
+
+        if let Some(forwarded_for) = forwarded_for {
+            let mut hops = forwarded_for.rsplit(',')
+                .map(|hop| hop.trim().parse::<IpAddr>());
+            let client = hops.find(|hop| !matches!(hop, Ok(ip) if self.contains(*ip)));
+
+            match client {
+                Some(Ok(ip)) => return ip,
+                // garbage in the header, the proxy itself is the best we know
+                Some(Err(_)) => return peer,
+                None => {},
+            }
+        }
+
+        real_ip
+            .and_then(|ip| ip.trim().parse().ok())
+            .unwrap_or(peer)
+    }
+}
+
+pub fn client_ip(req: &Request<'_>) -> IpAddr {
+    let peer = req.remote()
+        .map(|remote| remote.ip())
+        .unwrap_or(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)));
+
+    match req.rocket().state::<TrustedProxies>() {
+        Some(proxies) => proxies.resolve(
+            peer,
+            req.headers().get_one("X-Forwarded-For"),
+            req.headers().get_one("X-Real-IP"),
+        ),
+        None => peer,
+    }
+}
+
+/// Real address and user agent of the client
+pub struct ClientInfo {
+    pub ip: IpAddr,
+    pub user_agent: Option<String>,
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for ClientInfo {
+    type Error = Infallible;
+    async fn from_request(req: &'r Request<'_>) -> Outcome<ClientInfo, (rocket::http::Status, Infallible), ()> {
+        Outcome::Success(ClientInfo {
+            ip: client_ip(req),
+            user_agent: req.headers().get_one("User-Agent").map(|ua| ua.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use super::TrustedProxies;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_trusted_proxies() {
+        let proxies = TrustedProxies::parse("172.16.0.0/12, 10.0.0.1, fd00::/8, nonsense");
+
+        assert!(proxies.contains(ip("172.20.0.5")));
+        assert!(!proxies.contains(ip("172.32.0.1")));
+        assert!(proxies.contains(ip("10.0.0.1")));
+        assert!(!proxies.contains(ip("10.0.0.2")));
+        assert!(proxies.contains(ip("fd12::1")));
+        assert!(!TrustedProxies::parse("").contains(ip("127.0.0.1")));
+    }
+
+    #[test]
+    fn test_resolve_client_ip() {
+        let proxies = TrustedProxies::parse("172.16.0.0/12");
+        let traefik = ip("172.18.0.2");
+
+        // headers from untrusted peers are ignored
+        assert_eq!(proxies.resolve(ip("1.2.3.4"), Some("5.6.7.8"), Some("5.6.7.8")), ip("1.2.3.4"));
+        // spoofed leftmost address is skipped
+        assert_eq!(proxies.resolve(traefik, Some("6.6.6.6, 1.2.3.4"), None), ip("1.2.3.4"));
+        assert_eq!(proxies.resolve(traefik, Some("1.2.3.4, 172.18.0.3"), None), ip("1.2.3.4"));
+        assert_eq!(proxies.resolve(traefik, None, Some("1.2.3.4")), ip("1.2.3.4"));
+        assert_eq!(proxies.resolve(traefik, Some("garbage"), Some("1.2.3.4")), traefik);
+        assert_eq!(proxies.resolve(traefik, None, None), traefik);
+    }
+}
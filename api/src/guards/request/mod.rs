@@ -1 +1,3 @@
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod phase;
+pub mod client;
\ No newline at end of file
@@ -0,0 +1,58 @@
+use portfolio_core::models::calendar::Phase;
+use portfolio_core::services::application_service::ApplicationService;
+use portfolio_core::services::calendar_service::CalendarService;
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::request::{FromRequest, Request};
+
+use crate::logging::format_request;
+use crate::pool::Db;
+use crate::responses::guard_error;
+
+use super::auth::ApplicationAuth;
+
+/// Candidate can still fill in their details
+pub struct DetailsOpen;
+
+/// Candidate can still upload, submit and delete their portfolio
+pub struct PortfolioOpen;
+
+async fn check_phase(req: &Request<'_>, phase: Phase) -> Outcome<(), (Status, Option<String>), ()> {
+    let session = match req.guard::<ApplicationAuth>().await {
+        Outcome::Success(session) => session,
+        Outcome::Failure(e) => return Outcome::Failure(e),
+        Outcome::Forward(f) => return Outcome::Forward(f),
+    };
+    let application: entity::application::Model = session.into();
+
+    let conn = &req.rocket().state::<Db>().unwrap().conn;
+    let candidate = match ApplicationService::find_related_candidate(conn, &application).await {
+        Ok(candidate) => candidate,
+        Err(e) => return Outcome::Failure((guard_error(req, e), None)),
+    };
+
+    match CalendarService::check(&candidate, phase) {
+        Ok(_) => Outcome::Success(()),
+        Err(e) => {
+            info!("{}: CANDIDATE {} {:?} PHASE CLOSED", format_request(req), candidate.id, phase);
+            let message = e.to_string();
+            Outcome::Failure((guard_error(req, e), Some(message)))
+        },
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for DetailsOpen {
+    type Error = Option<String>;
+    async fn from_request(req: &'r Request<'_>) -> Outcome<DetailsOpen, (Status, Self::Error), ()> {
+        check_phase(req, Phase::Details).await.map(|_| DetailsOpen)
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for PortfolioOpen {
+    type Error = Option<String>;
+    async fn from_request(req: &'r Request<'_>) -> Outcome<PortfolioOpen, (Status, Self::Error), ()> {
+        check_phase(req, Phase::Portfolio).await.map(|_| PortfolioOpen)
+    }
+}
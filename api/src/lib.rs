@@ -8,16 +8,19 @@ use rocket::http::Header;
 use rocket::{Build, Request, Response, Rocket};
 
 use migration::MigratorTrait;
+use portfolio_core::services::candidate_service::CandidateService;
 use sea_orm_rocket::Database;
 
 mod guards;
 mod pool;
 mod requests;
+mod responses;
 mod routes;
 mod logging;
 pub mod test;
 
 use pool::Db;
+use guards::request::client::TrustedProxies;
 
 pub use entity::candidate;
 pub use entity::candidate::Entity as Candidate;
@@ -96,11 +99,17 @@ async fn run_migrations(rocket: Rocket<Build>) -> fairing::Result {
     println!("got conn");
     let _ = migration::Migrator::up(conn, None).await;
     println!("migrated");
+    // candidates created before blind indexes must be indexed by `cli blind_index` first
+    if let Err(e) = CandidateService::check_personal_id_index(conn).await {
+        error!("NOT STARTING: {}", e);
+        return Err(rocket);
+    }
     Ok(rocket)
 }
 
 pub fn rocket() -> Rocket<Build> {
     rocket::build()
+        .manage(TrustedProxies::from_env())
         .attach(Logging)
         .attach(CORS)
         .attach(Db::init())
@@ -111,7 +120,12 @@ pub fn rocket() -> Rocket<Build> {
             routes![
                 routes::candidate::login,
                 routes::candidate::logout,
+                routes::candidate::list_sessions,
+                routes::candidate::revoke_session,
+                routes::candidate::change_password,
                 routes::candidate::whoami,
+                routes::candidate::list_admission_rounds,
+                routes::candidate::deadlines,
                 routes::candidate::get_details,
                 routes::candidate::post_details,
             ],
@@ -146,13 +160,37 @@ pub fn rocket() -> Rocket<Build> {
             routes![
                 routes::admin::login,
                 routes::admin::logout,
+                routes::admin::list_sessions,
+                routes::admin::revoke_session,
                 routes::admin::whoami,
+                routes::admin::enrol_totp,
+                routes::admin::confirm_totp,
                 routes::admin::hello,
                 routes::admin::create_candidate,
+                routes::admin::import_candidates,
                 routes::admin::get_candidate,
                 routes::admin::reset_candidate_password,
+                routes::admin::get_credential_letter,
+                routes::admin::get_credential_letters,
+                routes::admin::get_import_letters,
+                routes::admin::extend_candidate_deadline,
                 routes::admin::get_candidate_portfolio,
                 routes::admin::delete_candidate,
+                routes::admin::get_audit_log,
+                routes::admin::list_lockouts,
+                routes::admin::clear_lockout,
+                routes::admin::list_fields_of_study,
+                routes::admin::save_field_of_study,
+                routes::admin::list_admission_rounds,
+                routes::admin::create_admission_round,
+                routes::admin::update_admission_round,
+                routes::admin::delete_admission_round,
+                routes::admin::list_admins,
+                routes::admin::create_admin,
+                routes::admin::disable_admin,
+                routes::admin::enable_admin,
+                routes::admin::set_admin_role,
+                routes::admin::delete_admin,
             ],
         )
         .mount(
@@ -163,7 +201,18 @@ pub fn rocket() -> Rocket<Build> {
                 routes::admin::list_admissions_csv
             ]
         )
-        .register("/", catchers![])
+        .register(
+            "/",
+            catchers![
+                routes::catchers::bad_request,
+                routes::catchers::unauthorized,
+                routes::catchers::not_found,
+                routes::catchers::payload_too_large,
+                routes::catchers::unprocessable_entity,
+                routes::catchers::internal_server_error,
+                routes::catchers::default,
+            ]
+        )
 }
 
 #[tokio::main]
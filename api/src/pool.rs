@@ -60,8 +60,6 @@ impl sea_orm_rocket::Pool for SeaOrmPool {
         // connect to general database
         let db: sea_orm::DbConn = sea_orm::Database::connect(options).await?;
 
-        use portfolio_core::crypto::{self, hash_password};
-        use portfolio_core::services::admin_service::admin_tests::create_admin;
         use sea_orm::{Schema, Database, Statement};
         use sea_orm::{sea_query::TableCreateStatement, ConnectionTrait, DbBackend};
 
@@ -116,19 +114,6 @@ impl sea_orm_rocket::Pool for SeaOrmPool {
         }
 
         if true {
-            // insert new admin account for our use
-            // id: should be 1 but may change
-            // password: hello
-            let password_plain_text = "hello".to_string();
-            let pwrd_hash = hash_password(password_plain_text.clone()).await.unwrap();
-            println!("got password hash {}", pwrd_hash.clone());
-            let (pub_key, priv_key) = crypto::create_identity();
-            let enc_priv_key = crypto::encrypt_password(priv_key, password_plain_text).await.unwrap();
-            db.execute(Statement::from_string(
-                db.get_database_backend(),
-                format!("INSERT INTO admin VALUES (0, \"alex3\", \"{pub_key}\", \"{enc_priv_key}\", \"{pwrd_hash}\", NOW(), NOW());"),
-            )).await?;
-
             // switch everything from varchars to text
             let query = "ALTER TABLE candidate MODIFY COLUMN name text; ALTER TABLE candidate MODIFY COLUMN surname text; ALTER TABLE candidate MODIFY COLUMN birth_surname text; ALTER TABLE candidate MODIFY COLUMN birthplace text; ALTER TABLE candidate MODIFY COLUMN address text; ALTER TABLE candidate MODIFY COLUMN letter_address text; ALTER TABLE candidate MODIFY COLUMN telephone text; ALTER TABLE candidate MODIFY COLUMN citizenship text; ALTER TABLE candidate MODIFY COLUMN email text; ALTER TABLE candidate MODIFY COLUMN sex text; ALTER TABLE candidate MODIFY COLUMN school_name text; ALTER TABLE candidate MODIFY COLUMN personal_identification_number text; ALTER TABLE candidate MODIFY COLUMN health_insurance text; ALTER TABLE candidate MODIFY COLUMN grades_json text; ALTER TABLE candidate MODIFY COLUMN first_school text; ALTER TABLE candidate MODIFY COLUMN second_school text; ALTER TABLE candidate MODIFY COLUMN test_language text; ALTER TABLE parent MODIFY COLUMN name text; ALTER TABLE parent MODIFY COLUMN surname text; ALTER TABLE parent MODIFY COLUMN telephone text; ALTER TABLE parent MODIFY COLUMN email text; ALTER TABLE application MODIFY COLUMN personal_id_number text; ALTER TABLE candidate MODIFY COLUMN birthdate text;".to_string();
             for a in query.split("; "){
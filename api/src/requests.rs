@@ -1,3 +1,4 @@
+use portfolio_core::models::admin::AdminRole;
 use rocket::serde::{Serialize, Deserialize};
 
 
@@ -8,17 +9,89 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub old_password: String,
+    pub new_password: String,
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde", rename_all = "camelCase")]
 pub struct RegisterRequest {
     pub application_id: i32,
     pub personal_id_number: String,
+    /// Candidate's email the login credentials are sent to
+    #[serde(default)]
+    pub email: Option<String>,
 }
 
+/// Password just returned by creating the candidate or resetting the password, printed on the letter
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct LetterRequest {
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct CredentialsRequest {
+    pub application_id: i32,
+    pub password: String,
+}
+
+/// `passwords` returned by the candidate import
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct ImportLettersRequest {
+    pub passwords: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct ExtensionRequest {
+    /// End of the individual extension, missing value revokes the extension
+    #[serde(default)]
+    pub until: Option<String>,
+}
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "rocket::serde", rename_all = "camelCase")]
 pub struct AdminLoginRequest {
     pub admin_id: i32,
     pub password: String,
-}
\ No newline at end of file
+    /// TOTP or recovery code, required only when the admin has a second factor enabled
+    #[serde(default)]
+    pub code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct TotpEnrolRequest {
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct TotpConfirmRequest {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct CreateAdminRequest {
+    pub name: String,
+    pub role: AdminRole,
+    /// Field of study codes, used only for reviewers
+    #[serde(default)]
+    pub fields_of_study: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", rename_all = "camelCase")]
+pub struct AdminRoleRequest {
+    pub role: AdminRole,
+    #[serde(default)]
+    pub fields_of_study: Vec<String>,
+}
@@ -0,0 +1,122 @@
+use std::pin::Pin;
+
+use portfolio_core::error::{Locale, ServiceError};
+use rocket::http::{ContentType, Status};
+use rocket::response::status::Custom;
+use rocket::response::{self, Responder};
+use rocket::serde::json::Json;
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::io::AsyncRead;
+use rocket::{Request, Response};
+
+/// Decrypted portfolio streamed to the client chunk by chunk, it is never buffered whole in memory
+pub struct PortfolioStream(pub Pin<Box<dyn AsyncRead + Send>>);
+
+impl<'r> Responder<'r, 'static> for PortfolioStream {
+    fn respond_to(self, _: &'r Request<'_>) -> response::Result<'static> {
+        Response::build()
+            .header(ContentType::Binary)
+            .streamed_body(self.0)
+            .ok()
+    }
+}
+
+/// JSON body of every error response, `code` is stable, `message` is localized
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ErrorResponse {
+    pub code: String,
+    pub status: u16,
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ErrorResponse {
+    pub fn from_service_error(e: &ServiceError, status: Status, locale: Locale) -> Self {
+        Self {
+            code: e.error_code().to_string(),
+            status: status.code,
+            message: e.message(locale),
+            details: e.details(),
+        }
+    }
+
+    /// Errors raised by Rocket itself, e.g. unknown route or too large body
+    pub fn from_status(status: Status, locale: Locale) -> Self {
+        let (code, en, cs) = match status.code {
+            400 => ("BAD_REQUEST", "Bad request", "Neplatný požadavek"),
+            401 => ("UNAUTHORIZED", "Unauthorized", "Nepřihlášený uživatel"),
+            403 => ("FORBIDDEN", "Forbidden", "Přístup zamítnut"),
+            404 => ("NOT_FOUND", "Not found", "Nenalezeno"),
+            413 => ("PAYLOAD_TOO_LARGE", "Request is too large", "Požadavek je příliš velký"),
+            422 => ("UNPROCESSABLE_ENTITY", "Invalid request body", "Neplatný obsah požadavku"),
+            500 => ("INTERNAL_SERVER_ERROR", "Internal server error", "Interní chyba serveru"),
+            _ => ("HTTP_ERROR", status.reason().unwrap_or("Error"), "Chyba požadavku"),
+        };
+
+        Self {
+            code: code.to_string(),
+            status: status.code,
+            message: match locale {
+                Locale::En => en.to_string(),
+                Locale::Cs => cs.to_string(),
+            },
+            details: None,
+        }
+    }
+}
+
+pub fn request_locale(req: &Request<'_>) -> Locale {
+    Locale::from_accept_language(req.headers().get_one("Accept-Language"))
+}
+
+/// Error of a failed request guard, the catcher of its status responds with it
+struct GuardError(Option<ErrorResponse>);
+
+/// Remembers the service error of a failing guard and returns its status
+pub fn guard_error(req: &Request<'_>, e: ServiceError) -> Status {
+    let status = Status::from_code(e.code()).unwrap_or_default();
+    req.local_cache(|| GuardError(Some(ErrorResponse::from_service_error(&e, status, request_locale(req)))));
+    status
+}
+
+pub fn catch_error(status: Status, req: &Request<'_>) -> Custom<Json<ErrorResponse>> {
+    let response = req.local_cache(|| GuardError(None)).0.to_owned()
+        .filter(|e| e.status == status.code)
+        .unwrap_or_else(|| ErrorResponse::from_status(status, request_locale(req)));
+
+    Custom(status, Json(response))
+}
+
+/// Error returned by routes, status is taken from `ServiceError::code` unless set explicitly
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Status,
+    pub error: ServiceError,
+}
+
+impl ApiError {
+    pub fn with_status(status: Status, error: ServiceError) -> Self {
+        Self { status, error }
+    }
+}
+
+impl From<ServiceError> for ApiError {
+    fn from(error: ServiceError) -> Self {
+        Self {
+            status: Status::from_code(error.code()).unwrap_or_default(),
+            error,
+        }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, req: &'r Request<'_>) -> response::Result<'static> {
+        if self.status.code == 500 {
+            warn!("Internal server error: {} ({})", self.error, self.error.inner_trace().unwrap_or("".to_string()));
+        }
+
+        let body = ErrorResponse::from_service_error(&self.error, self.status, request_locale(req));
+        Custom(self.status, Json(body)).respond_to(req)
+    }
+}
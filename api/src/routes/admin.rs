@@ -1,69 +1,66 @@
-use std::net::{SocketAddr, IpAddr, Ipv4Addr};
-
 use portfolio_core::{
     crypto::random_12_char_string,
-    services::{admin_service::AdminService, application_service::ApplicationService, portfolio_service::PortfolioService}, models::{candidate::{CreateCandidateResponse, ApplicationDetails}, auth::AuthenticableTrait, application::ApplicationResponse}, sea_orm::prelude::Uuid, Query, error::ServiceError,
+    error::ServiceError,
+    services::{admin_service::AdminService, application_service::ApplicationService, audit_service::AuditService, field_of_study_service::FieldOfStudyService, admission_round_service::AdmissionRoundService, login_throttle_service::LoginThrottleService, totp_service::TotpService, import_service::ImportService, letter_service::LetterService}, models::{admin::{AdminResponse, CreateAdminResponse}, candidate::{CreateCandidateResponse, ApplicationDetails, ImportResponse}, auth::{AuthenticableTrait, RecoveryCodesResponse, SessionResponse, TotpEnrolmentResponse}, application::ApplicationResponse, audit::{AuditAction, AuditLogFilter, AuditLogResponse}, field_of_study::FieldOfStudyResponse, admission_round::{AdmissionRoundRequest, AdmissionRoundResponse}, throttle::{LockoutResponse, ThrottleKey}}, sea_orm::prelude::Uuid, Query, utils::date::parse_naive_datetime,
 };
-use requests::{AdminLoginRequest, RegisterRequest};
-use rocket::http::{Cookie, Status, CookieJar};
-use rocket::response::status::Custom;
+use requests::{AdminLoginRequest, AdminRoleRequest, CreateAdminRequest, CredentialsRequest, ExtensionRequest, ImportLettersRequest, LetterRequest, RegisterRequest, TotpConfirmRequest, TotpEnrolRequest};
+use rocket::http::{ContentType, Cookie, Status, CookieJar};
 use rocket::serde::json::Json;
 
 use sea_orm_rocket::Connection;
-use portfolio_core::utils::csv::{ApplicationCsv, CandidateCsv, CsvExporter};
+use portfolio_core::utils::csv::{ApplicationCsv, CandidateCsv};
 
-use crate::{guards::request::{auth::AdminAuth}, pool::Db, requests};
+use crate::{guards::{data::import::CandidateImport, request::{auth::{AdminAuth, Authorized, CreateCandidates, DeleteCandidates, EditCandidates, ExportData, ListCandidates, ManageAdmins, ManageSettings, ReadCandidates, ReadPortfolios}, client::ClientInfo}}, pool::Db, requests, responses::{ApiError, PortfolioStream}};
 
-use super::to_custom_error;
 
 #[post("/login", data = "<login_form>")]
 pub async fn login(
     conn: Connection<'_, Db>,
     login_form: Json<AdminLoginRequest>,
-    // ip_addr: SocketAddr, // TODO uncomment in production
+    client: ClientInfo,
     cookies: &CookieJar<'_>,
-) -> Result<(), Custom<String>> {
-    let ip_addr: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0);
-    let db = conn.into_inner();
-    let session_token_key = AdminService::login(
-        db,
-        login_form.admin_id,
-        login_form.password.to_string(),
-        ip_addr.ip().to_string(),
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+    let throttle_keys = [ThrottleKey::Ip(client.ip.to_string()), ThrottleKey::Admin(login_form.admin_id)];
+    let session_token_key = LoginThrottleService::attempt(db, &throttle_keys,
+        AdminService::login_with_second_factor(
+            db,
+            login_form.admin_id,
+            login_form.password.to_string(),
+            login_form.code.to_owned(),
+            client.ip.to_string(),
+            client.user_agent,
+        )
     )
     .await;
 
     let Ok(session_token_key) = session_token_key else {
         let e = session_token_key.unwrap_err();
-        return Err(Custom(
-            Status::from_code(e.code()).unwrap_or(Status::InternalServerError),
-            e.to_string(),
-        ));
-    
+        return Err(e.into());
     };
 
     let session_token = session_token_key.0;
-    let private_key = session_token_key.1;
+    let key_secret = session_token_key.1;
 
     cookies.add_private(Cookie::new("id", session_token.clone()));
-    cookies.add_private(Cookie::new("key", private_key.clone()));
+    cookies.add_private(Cookie::new("key", key_secret.clone()));
 
     return Ok(());
 }
 
 #[post("/logout")]
-pub async fn logout(conn: Connection<'_, Db>, _session: AdminAuth, cookies: &CookieJar<'_>,) -> Result<(), Custom<String>> {
+pub async fn logout(conn: Connection<'_, Db>, _session: AdminAuth, cookies: &CookieJar<'_>,) -> Result<(), ApiError> {
     let db = conn.into_inner();
 
     let cookie = cookies.get_private("id") // unwrap would be safe here because of the auth guard
-        .ok_or(Custom(Status::Unauthorized, "No session cookie".to_string()))?;
+        .ok_or(ServiceError::Unauthorized)?;
     let session_id = Uuid::try_parse(cookie.value()) // unwrap would be safe here because of the auth guard
-        .map_err(|e| Custom(Status::BadRequest, e.to_string()))?;
+        .map_err(|_| ServiceError::InvalidId)?;
     let session = Query::find_admin_session_by_uuid(db, session_id).await.unwrap().unwrap();
     
     let _res = AdminService::logout(db, session)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     cookies.remove_private(Cookie::named("id"));
     cookies.remove_private(Cookie::named("key"));
@@ -71,41 +68,112 @@ pub async fn logout(conn: Connection<'_, Db>, _session: AdminAuth, cookies: &Coo
     Ok(())
 }
 
+#[get("/sessions")]
+pub async fn list_sessions(
+    conn: Connection<'_, Db>,
+    session: AdminAuth,
+    cookies: &CookieJar<'_>,
+) -> Result<Json<Vec<SessionResponse>>, ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+    let current = cookies.get_private("id")
+        .and_then(|c| Uuid::try_parse(c.value()).ok());
+
+    let sessions = AdminService::list_sessions(db, &admin, current)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(sessions))
+}
+
+#[delete("/sessions/<id>")]
+pub async fn revoke_session(
+    conn: Connection<'_, Db>,
+    session: AdminAuth,
+    id: String,
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+    let session_id = Uuid::try_parse(&id)
+        .map_err(|_| ServiceError::InvalidId)?;
+
+    AdminService::revoke_session(db, &admin, session_id)
+        .await
+        .map_err(ApiError::from)
+}
+
 
 #[get("/whoami")]
-pub async fn whoami(session: AdminAuth) -> Result<String, Custom<String>> {
+pub async fn whoami(session: AdminAuth) -> Result<String, ApiError> {
     let admin: entity::admin::Model = session.into();
     Ok(admin.id.to_string())
 }
 
+/// Starts enrolment of a TOTP second factor, returns the secret for an authenticator app
+#[post("/totp", data = "<request>")]
+pub async fn enrol_totp(
+    conn: Connection<'_, Db>,
+    session: AdminAuth,
+    request: Json<TotpEnrolRequest>,
+) -> Result<Json<TotpEnrolmentResponse>, ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    let response = TotpService::enrol(db, admin.id, request.password.to_owned())
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(response)
+    )
+}
+
+/// Enables the enrolled second factor after the first valid code, returns recovery codes
+#[post("/totp/confirm", data = "<request>")]
+pub async fn confirm_totp(
+    conn: Connection<'_, Db>,
+    session: AdminAuth,
+    request: Json<TotpConfirmRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    let response = TotpService::confirm(db, admin.id, request.password.to_owned(), request.code.to_owned())
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(response)
+    )
+}
+
 #[get("/hello")]
-pub async fn hello(_session: AdminAuth) -> Result<String, Custom<String>> {
+pub async fn hello(_session: AdminAuth) -> Result<String, ApiError> {
     Ok("Hello admin".to_string())
 }
 
 #[post("/create", data = "<request>")]
 pub async fn create_candidate(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
+    _session: Authorized<CreateCandidates>,
     request: Json<RegisterRequest>,
-) -> Result<Json<CreateCandidateResponse>, Custom<String>> {
+) -> Result<Json<CreateCandidateResponse>, ApiError> {
     let db = conn.into_inner();
     let form = request.into_inner();
-    let private_key = session.get_private_key();
 
     let plain_text_password = random_12_char_string();
 
     println!("trying to did the thing");
 
     let (application, applications, personal_id_number) = ApplicationService::create(
-        &private_key,
         &db,
         form.application_id,
         &plain_text_password,
-        form.personal_id_number.clone()
+        form.personal_id_number.clone(),
+        form.email.clone(),
     )
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     println!("did the thing");
     Ok(
@@ -123,25 +191,41 @@ pub async fn create_candidate(
     )
 }
 
+/// Creates candidates from a CSV, generated passwords are encrypted for the importing admin
+#[post("/import", data = "<import>")]
+pub async fn import_candidates(
+    conn: Connection<'_, Db>,
+    session: Authorized<CreateCandidates>,
+    import: CandidateImport,
+) -> Result<Json<ImportResponse>, ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    let report = ImportService::import_candidates(db, Some(admin.id), &Into::<Vec<u8>>::into(import), &vec![admin.public_key])
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(report.into())
+    )
+}
+
 #[allow(unused_variables)]
 #[get("/candidates?<field>&<page>&<sort>")]
 pub async fn list_candidates(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
+    session: Authorized<ListCandidates>,
     field: Option<String>,
     page: Option<u64>, 
     sort: Option<String>,
-) -> Result<Json<Vec<ApplicationResponse>>, Custom<String>> {
+) -> Result<Json<Vec<ApplicationResponse>>, ApiError> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
-    if let Some(field) = field.clone() {
-        if !(field == "KB".to_string() || field == "IT".to_string() || field == "G") {
-            return Err(Custom(Status::BadRequest, "Invalid field of study".to_string()));
-        }
-    }
+    let scope = session.scope();
+    let admin: entity::admin::Model = session.into();
 
-    let candidates = ApplicationService::list_applications(&private_key, db, field, page, sort)
-        .await.map_err(to_custom_error)?;
+    let candidates = AdminService::list_candidates(db, admin.id, &scope, &private_key, field, page, sort)
+        .await.map_err(ApiError::from)?;
 
     Ok(
         Json(candidates)
@@ -151,14 +235,16 @@ pub async fn list_candidates(
 #[get("/candidates_csv")]
 pub async fn list_candidates_csv(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
-) -> Result<Vec<u8>, Custom<String>> {
+    session: Authorized<ExportData>,
+) -> Result<Vec<u8>, ApiError> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
+    let scope = session.scope();
+    let admin: entity::admin::Model = session.into();
 
-    let candidates = ApplicationCsv::export(db, private_key)
+    let candidates = AdminService::export_csv::<ApplicationCsv>(db, Some(admin.id), &scope, private_key, AuditAction::ExportApplications)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(
         candidates
@@ -168,14 +254,16 @@ pub async fn list_candidates_csv(
 #[get("/admissions_csv")]
 pub async fn list_admissions_csv(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
-) -> Result<Vec<u8>, Custom<String>> {
+    session: Authorized<ExportData>,
+) -> Result<Vec<u8>, ApiError> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
+    let scope = session.scope();
+    let admin: entity::admin::Model = session.into();
 
-    let candidates = CandidateCsv::export(db, private_key)
+    let candidates = AdminService::export_csv::<CandidateCsv>(db, Some(admin.id), &scope, private_key, AuditAction::ExportCandidates)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(
         candidates
@@ -185,24 +273,17 @@ pub async fn list_admissions_csv(
 #[get("/candidate/<id>")]
 pub async fn get_candidate(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
+    session: Authorized<ReadCandidates>,
     id: i32,
-) -> Result<Json<ApplicationDetails>, Custom<String>> {
+) -> Result<Json<ApplicationDetails>, ApiError> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
+    let scope = session.scope();
+    let admin: entity::admin::Model = session.into();
 
-    let application = Query::find_application_by_id(db, id)
-        .await
-        .map_err(|e| to_custom_error(ServiceError::DbError(e)))?
-        .ok_or(to_custom_error(ServiceError::CandidateNotFound))?;
-    
-    let details = ApplicationService::decrypt_all_details(
-        private_key,
-        db,
-        &application
-    )
+    let details = AdminService::get_candidate_details(db, admin.id, &scope, private_key, id)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(
         Json(details)
@@ -212,69 +293,423 @@ pub async fn get_candidate(
 #[delete("/candidate/<id>")]
 pub async fn delete_candidate(
     conn: Connection<'_, Db>,
-    _session: AdminAuth,
+    session: Authorized<DeleteCandidates>,
     id: i32,
-) -> Result<(), Custom<String>> {
+) -> Result<(), ApiError> {
     let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
 
-    let application = Query::find_application_by_id(db, id)
-        .await
-        .map_err(|e| to_custom_error(ServiceError::DbError(e)))?
-        .ok_or(to_custom_error(ServiceError::CandidateNotFound))?;
-
-
-    ApplicationService::delete(db, application)
+    AdminService::delete_candidate(db, admin.id, id)
         .await
-        .map_err(to_custom_error)
+        .map_err(ApiError::from)
 
 }
 
 #[post("/candidate/<id>/reset_password")]
 pub async fn reset_candidate_password(
     conn: Connection<'_, Db>,
-    session: AdminAuth,
+    session: Authorized<EditCandidates>,
     id: i32,
-) -> Result<Json<CreateCandidateResponse>, Custom<String>> {
-    // TODO
+) -> Result<Json<CreateCandidateResponse>, ApiError> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
+    let admin: entity::admin::Model = session.into();
 
-    let response = ApplicationService::reset_password(private_key, db, id)
+    let response = AdminService::reset_candidate_password(db, admin.id, private_key, id)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
     
     Ok(
         Json(response)
     )
 }
 
+#[post("/candidate/<id>/letter", data = "<request>")]
+pub async fn get_credential_letter(
+    conn: Connection<'_, Db>,
+    session: Authorized<CreateCandidates>,
+    id: i32,
+    request: Json<LetterRequest>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    let letter = LetterService::credential_letter(db, Some(admin.id), id, request.into_inner().password)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        (ContentType::PDF, letter)
+    )
+}
+
+/// Credential letters of many applications in one PDF, e.g. after an import
+#[post("/letters", data = "<request>")]
+pub async fn get_credential_letters(
+    conn: Connection<'_, Db>,
+    session: Authorized<CreateCandidates>,
+    request: Json<Vec<CredentialsRequest>>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+    let credentials = request.into_inner()
+        .into_iter()
+        .map(|c| (c.application_id, c.password))
+        .collect();
+
+    let letters = LetterService::credential_letters(db, Some(admin.id), credentials)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        (ContentType::PDF, letters)
+    )
+}
+
+/// Credential letters of the applications created by an import, the passwords are decrypted
+/// with the private key of the admin's session
+#[post("/import/letters", data = "<request>")]
+pub async fn get_import_letters(
+    conn: Connection<'_, Db>,
+    session: Authorized<CreateCandidates>,
+    request: Json<ImportLettersRequest>,
+) -> Result<(ContentType, Vec<u8>), ApiError> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+    let admin: entity::admin::Model = session.into();
+
+    let letters = LetterService::import_letters(db, Some(admin.id), &private_key, &request.into_inner().passwords)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        (ContentType::PDF, letters)
+    )
+}
+
+#[post("/candidate/<id>/extension", data = "<request>")]
+pub async fn extend_candidate_deadline(
+    conn: Connection<'_, Db>,
+    session: Authorized<EditCandidates>,
+    id: i32,
+    request: Json<ExtensionRequest>,
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    let until = request.until
+        .as_deref()
+        .map(parse_naive_datetime)
+        .transpose()
+        .map_err(ApiError::from)?;
+
+    AdminService::extend_deadline(db, admin.id, id, until)
+        .await
+        .map_err(ApiError::from)
+}
+
 #[get("/candidate/<id>/portfolio")]
 pub async fn get_candidate_portfolio(
     conn: Connection<'_, Db>,
-    session: AdminAuth, 
+    session: Authorized<ReadPortfolios>, 
     id: i32,
-) -> Result<Vec<u8>, Custom<String>> {
+) -> Result<PortfolioStream, ApiError> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
+    let scope = session.scope();
+    let admin: entity::admin::Model = session.into();
 
-    let application = Query::find_application_by_id(db, id)
+    let portfolio = AdminService::get_candidate_portfolio(db, admin.id, &scope, private_key, id)
         .await
-        .map_err(|e| to_custom_error(ServiceError::DbError(e)))?
-        .ok_or(to_custom_error(ServiceError::CandidateNotFound))?;
+        .map_err(ApiError::from)?;
+
+    Ok(PortfolioStream(portfolio))
+}
 
-    let portfolio = PortfolioService::get_portfolio(application.candidate_id, private_key)
+#[get("/fields_of_study")]
+pub async fn list_fields_of_study(
+    conn: Connection<'_, Db>,
+    _session: AdminAuth,
+) -> Result<Json<Vec<FieldOfStudyResponse>>, ApiError> {
+    let db = conn.into_inner();
+
+    let fields = FieldOfStudyService::list(db)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
-    Ok(portfolio)
+    Ok(
+        Json(fields)
+    )
+}
+
+#[put("/fields_of_study", data = "<request>")]
+pub async fn save_field_of_study(
+    conn: Connection<'_, Db>,
+    _session: Authorized<ManageSettings>,
+    request: Json<FieldOfStudyResponse>,
+) -> Result<Json<FieldOfStudyResponse>, ApiError> {
+    let db = conn.into_inner();
+
+    let field = FieldOfStudyService::save(db, request.into_inner())
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(field)
+    )
+}
+
+#[get("/admission_rounds")]
+pub async fn list_admission_rounds(
+    conn: Connection<'_, Db>,
+    _session: AdminAuth,
+) -> Result<Json<Vec<AdmissionRoundResponse>>, ApiError> {
+    let db = conn.into_inner();
+
+    let rounds = AdmissionRoundService::list(db)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(rounds)
+    )
+}
+
+#[post("/admission_rounds", data = "<request>")]
+pub async fn create_admission_round(
+    conn: Connection<'_, Db>,
+    _session: Authorized<ManageSettings>,
+    request: Json<AdmissionRoundRequest>,
+) -> Result<Json<AdmissionRoundResponse>, ApiError> {
+    let db = conn.into_inner();
+
+    let round = AdmissionRoundService::create(db, request.into_inner())
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(round)
+    )
+}
+
+#[put("/admission_rounds/<id>", data = "<request>")]
+pub async fn update_admission_round(
+    conn: Connection<'_, Db>,
+    _session: Authorized<ManageSettings>,
+    id: i32,
+    request: Json<AdmissionRoundRequest>,
+) -> Result<Json<AdmissionRoundResponse>, ApiError> {
+    let db = conn.into_inner();
+
+    let round = AdmissionRoundService::update(db, id, request.into_inner())
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(round)
+    )
+}
+
+#[delete("/admission_rounds/<id>")]
+pub async fn delete_admission_round(
+    conn: Connection<'_, Db>,
+    _session: Authorized<ManageSettings>,
+    id: i32,
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+
+    AdmissionRoundService::delete(db, id)
+        .await
+        .map_err(ApiError::from)
+}
+
+#[get("/audit?<candidate>&<admin>&<from>&<to>")]
+pub async fn get_audit_log(
+    conn: Connection<'_, Db>,
+    _session: Authorized<ManageSettings>,
+    candidate: Option<i32>,
+    admin: Option<i32>,
+    from: Option<String>,
+    to: Option<String>,
+) -> Result<Json<Vec<AuditLogResponse>>, ApiError> {
+    let db = conn.into_inner();
+
+    let parse = |datetime: Option<String>| datetime
+        .map(|d| parse_naive_datetime(&d))
+        .transpose()
+        .map_err(|e| ApiError::with_status(Status::BadRequest, e));
+
+    let filter = AuditLogFilter {
+        candidate_id: candidate,
+        admin_id: admin,
+        from: parse(from)?,
+        to: parse(to)?,
+    };
+
+    let entries = AuditService::list(db, &filter)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(entries)
+    )
+}
+
+/// IP addresses and accounts locked out after too many failed logins
+#[get("/lockouts")]
+pub async fn list_lockouts(
+    conn: Connection<'_, Db>,
+    _session: Authorized<ManageSettings>,
+) -> Result<Json<Vec<LockoutResponse>>, ApiError> {
+    let db = conn.into_inner();
+
+    let lockouts = LoginThrottleService::list_lockouts(db)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(lockouts)
+    )
+}
+
+#[delete("/lockouts?<key>")]
+pub async fn clear_lockout(
+    conn: Connection<'_, Db>,
+    session: Authorized<ManageSettings>,
+    key: String,
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    LoginThrottleService::clear_lockout(db, admin.id, key)
+        .await
+        .map_err(ApiError::from)
+}
+
+#[get("/admins")]
+pub async fn list_admins(
+    conn: Connection<'_, Db>,
+    _session: Authorized<ManageAdmins>,
+) -> Result<Json<Vec<AdminResponse>>, ApiError> {
+    let db = conn.into_inner();
+
+    let admins = AdminService::list_admins(db)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(admins)
+    )
+}
+
+/// New admin gets a generated password, they can log in once the existing candidates
+/// are re-encrypted to their key in the background
+#[post("/admins", data = "<request>")]
+pub async fn create_admin(
+    conn: Connection<'_, Db>,
+    session: Authorized<ManageAdmins>,
+    request: Json<CreateAdminRequest>,
+) -> Result<Json<CreateAdminResponse>, ApiError> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+    let admin: entity::admin::Model = session.into();
+    let request = request.into_inner();
+
+    let response = AdminService::add_admin(db, admin.id, request.name, request.role, request.fields_of_study)
+        .await
+        .map_err(ApiError::from)?;
+
+    // re-encrypting every candidate takes too long for the request, `cli admin activate` finishes a failed run
+    let db = db.clone();
+    rocket::tokio::spawn(async move {
+        if let Err(e) = AdminService::activate_pending_admins(&db, &private_key).await {
+            warn!("PENDING ADMINS NOT ACTIVATED: {}", e);
+        }
+    });
+
+    Ok(
+        Json(response)
+    )
+}
+
+#[post("/admins/<id>/disable")]
+pub async fn disable_admin(
+    conn: Connection<'_, Db>,
+    session: Authorized<ManageAdmins>,
+    id: i32,
+) -> Result<Json<AdminResponse>, ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    let response = AdminService::set_admin_disabled(db, admin.id, id, true)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(response)
+    )
+}
+
+#[post("/admins/<id>/enable")]
+pub async fn enable_admin(
+    conn: Connection<'_, Db>,
+    session: Authorized<ManageAdmins>,
+    id: i32,
+) -> Result<Json<AdminResponse>, ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+
+    let response = AdminService::set_admin_disabled(db, admin.id, id, false)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(response)
+    )
+}
+
+/// Role of another admin, `fieldsOfStudy` limits reviewers to the listed field codes
+#[put("/admins/<id>/role", data = "<request>")]
+pub async fn set_admin_role(
+    conn: Connection<'_, Db>,
+    session: Authorized<ManageAdmins>,
+    id: i32,
+    request: Json<AdminRoleRequest>,
+) -> Result<Json<AdminResponse>, ApiError> {
+    let db = conn.into_inner();
+    let admin: entity::admin::Model = session.into();
+    let request = request.into_inner();
+
+    let response = AdminService::set_admin_role(db, admin.id, id, request.role, request.fields_of_study)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(
+        Json(response)
+    )
+}
+
+#[delete("/admins/<id>")]
+pub async fn delete_admin(
+    conn: Connection<'_, Db>,
+    session: Authorized<ManageAdmins>,
+    id: i32,
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+    let private_key = session.get_private_key();
+    let admin: entity::admin::Model = session.into();
+
+    AdminService::delete_admin(db, admin.id, &private_key, id)
+        .await
+        .map_err(ApiError::from)
 }
 
 #[cfg(test)]
 pub mod tests {
-    use portfolio_core::models::candidate::CreateCandidateResponse;
-    use rocket::{local::blocking::Client, http::{Cookie, Status}};
+    use portfolio_core::models::{admin::{AdminResponse, CreateAdminResponse}, application::ApplicationResponse, candidate::{CreateCandidateResponse, ImportResponse, ImportStatus}, throttle::LockoutResponse};
+    use rocket::{local::blocking::Client, http::{ContentType, Cookie, Header, Status}};
 
-    use crate::test::tests::{test_client, ADMIN_PASSWORD, ADMIN_ID};
+    use crate::{responses::ErrorResponse, test::tests::{test_client, ADMIN_PASSWORD, ADMIN_ID}};
 
     pub fn admin_login(client: &Client) -> (Cookie, Cookie) {
         let response = client
@@ -318,6 +753,222 @@ pub mod tests {
         response.into_json::<CreateCandidateResponse>().unwrap()
     }
 
+    #[test]
+    fn test_admin_decrypts_created_candidate() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+        let created = create_candidate(&client, cookies.clone(), 1021511, "0101010001".to_string());
+
+        let response = client
+            .get("/admin/list/candidates?field=IT")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let candidates = response.into_json::<Vec<ApplicationResponse>>().unwrap();
+        let candidate = candidates.iter()
+            .find(|c| c.application_id == created.application_id)
+            .unwrap();
+        assert_eq!(candidate.personal_id_number, "0101010001");
+
+        let response = client
+            .get("/admin/list/candidates?field=XX")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    #[test]
+    fn test_login_lockout() {
+        let client = test_client().lock().unwrap();
+        let login = || client
+            .post("/admin/login")
+            .body("{\"adminId\": 999, \"password\": \"wrong\"}")
+            .dispatch()
+            .status();
+
+        for _ in 0..5 {
+            assert_eq!(login(), Status::Unauthorized);
+        }
+        assert_eq!(login(), Status::TooManyRequests);
+
+        let cookies = admin_login(&client);
+        let response = client
+            .get("/admin/lockouts")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let lockouts = response.into_json::<Vec<LockoutResponse>>().unwrap();
+        assert!(lockouts.iter().any(|l| l.key == "admin:999"));
+
+        let response = client
+            .delete("/admin/lockouts?key=admin%3A999")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(login(), Status::Unauthorized);
+    }
+
+    #[test]
+    fn test_json_error_response() {
+        let client = test_client().lock().unwrap();
+        let login = |language: &'static str| client
+            .post("/admin/login")
+            .header(Header::new("Accept-Language", language))
+            .body("{\"adminId\": 998, \"password\": \"wrong\"}")
+            .dispatch();
+
+        let response = login("en-US,en;q=0.9");
+        assert_eq!(response.status(), Status::Unauthorized);
+        let error = response.into_json::<ErrorResponse>().unwrap();
+        assert_eq!(error.code, "INVALID_CREDENTIALS");
+        assert_eq!(error.status, 401);
+        assert_eq!(error.message, "Invalid credentials");
+
+        let error = login("cs").into_json::<ErrorResponse>().unwrap();
+        assert_eq!(error.message, "Neplatné přihlašovací údaje");
+
+        // errors of Rocket itself go through the catchers
+        let response = client.post("/admin/login").body("{}").dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity);
+        assert_eq!(response.into_json::<ErrorResponse>().unwrap().code, "UNPROCESSABLE_ENTITY");
+
+        let response = client.get("/admin/whoami").dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+        assert_eq!(response.into_json::<ErrorResponse>().unwrap().code, "UNAUTHORIZED");
+
+        let response = client.get("/nonexistent").dispatch();
+        assert_eq!(response.into_json::<ErrorResponse>().unwrap().code, "NOT_FOUND");
+    }
+
+    #[test]
+    fn test_manage_admins() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+
+        let response = client
+            .get("/admin/admins")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let admins = response.into_json::<Vec<AdminResponse>>().unwrap();
+        assert!(admins.iter().any(|a| a.id == ADMIN_ID && a.disabled_at.is_none()));
+
+        // admins can't lock themselves out
+        let response = client
+            .post(format!("/admin/admins/{}/disable", ADMIN_ID))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .delete(format!("/admin/admins/{}", ADMIN_ID))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .post("/admin/admins/999999/disable")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+        assert_eq!(response.into_json::<ErrorResponse>().unwrap().code, "ADMIN_NOT_FOUND");
+    }
+
+    #[test]
+    fn test_reviewer_permissions() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+        create_candidate(&client, cookies.clone(), 1011512, "0101010012".to_string());
+        create_candidate(&client, cookies.clone(), 1021512, "0101010023".to_string());
+
+        let response = client
+            .post("/admin/admins")
+            .body("{\"name\": \"reviewer\", \"role\": \"reviewer\", \"fieldsOfStudy\": [\"G\"]}")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let reviewer = response.into_json::<CreateAdminResponse>().unwrap();
+
+        // reviewer can log in once the candidates are re-encrypted in the background
+        let pending = || client
+            .get("/admin/admins")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch()
+            .into_json::<Vec<AdminResponse>>()
+            .unwrap()
+            .iter()
+            .any(|a| a.id == reviewer.id && a.key_pending_at.is_some());
+        for _ in 0..50 {
+            if !pending() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        assert!(!pending());
+
+        let response = client
+            .post("/admin/login")
+            .body(format!("{{\"adminId\": {}, \"password\": \"{}\"}}", reviewer.id, reviewer.password))
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let cookies = (
+            response.cookies().get("id").unwrap().to_owned(),
+            response.cookies().get("key").unwrap().to_owned(),
+        );
+
+        // only candidates of the assigned field are listed
+        let response = client
+            .get("/admin/list/candidates")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let candidates = response.into_json::<Vec<ApplicationResponse>>().unwrap();
+        assert!(candidates.iter().any(|c| c.application_id == 1011512));
+        assert!(candidates.iter().all(|c| c.field_of_study.as_deref() == Some("G")));
+
+        let response = client
+            .get("/admin/list/candidates?field=IT")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .get("/admin/candidate/1011512")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+        assert_eq!(response.into_json::<ErrorResponse>().unwrap().code, "FORBIDDEN");
+
+        let response = client
+            .post("/admin/create")
+            .body("{\"applicationId\": 1011513, \"personalIdNumber\": \"0101010034\"}")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+
+        let response = client
+            .get("/admin/list/candidates_csv")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Forbidden);
+    }
+
     #[test]
     fn test_create_candidate() {
         let client = test_client().lock().unwrap();
@@ -326,4 +977,80 @@ pub mod tests {
     
         assert_eq!(response.password.len(), 12);
     }
+
+    #[test]
+    fn test_import_candidates() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+        let import = "application_id,personal_id_number\n1011611,0101010611\n1021611,0101010611\n4041611,0101010612\n";
+
+        let dispatch = || client
+            .post("/admin/import")
+            .header(ContentType::CSV)
+            .body(import)
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+
+        let response = dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let response = response.into_json::<ImportResponse>().unwrap();
+        let statuses: Vec<ImportStatus> = response.results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![ImportStatus::Created, ImportStatus::Linked, ImportStatus::Rejected]);
+        assert!(!response.passwords.is_empty());
+
+        let letters = client
+            .post("/admin/import/letters")
+            .body(format!("{{\"passwords\": \"{}\"}}", response.passwords))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(letters.status(), Status::Ok);
+        assert_eq!(letters.content_type(), Some(ContentType::PDF));
+
+        let response = dispatch().into_json::<ImportResponse>().unwrap();
+        let statuses: Vec<ImportStatus> = response.results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![ImportStatus::Existing, ImportStatus::Existing, ImportStatus::Rejected]);
+    }
+
+    #[test]
+    fn test_credential_letters() {
+        let client = test_client().lock().unwrap();
+        let cookies = admin_login(&client);
+        let created = create_candidate(&client, cookies.clone(), 1031711, "0101010711".to_string());
+
+        let response = client
+            .post(format!("/admin/candidate/{}/letter", created.application_id))
+            .body(format!("{{\"password\": \"{}\"}}", created.password))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.content_type(), Some(ContentType::PDF));
+        assert!(response.into_bytes().unwrap().starts_with(b"%PDF"));
+
+        let response = client
+            .post("/admin/letters")
+            .body(format!("[{{\"applicationId\": {}, \"password\": \"{}\"}}, {{\"applicationId\": 1011799, \"password\": \"x\"}}]", created.application_id, created.password))
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+
+        let response = client
+            .post("/admin/letters")
+            .body(format!("[{{\"applicationId\": {}, \"password\": \"wrong password\"}}]", created.application_id))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+
+        let response = client
+            .post("/admin/letters")
+            .body("[]")
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
 }
\ No newline at end of file
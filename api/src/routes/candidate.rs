@@ -1,46 +1,54 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
-
 use entity::application;
 use portfolio_core::Query;
 use portfolio_core::error::ServiceError;
-use portfolio_core::models::auth::AuthenticableTrait;
+use portfolio_core::models::auth::{AuthenticableTrait, SessionResponse};
 use portfolio_core::models::candidate::{ApplicationDetails, NewCandidateResponse};
 use portfolio_core::sea_orm::prelude::Uuid;
+use portfolio_core::models::admission_round::AdmissionRoundResponse;
+use portfolio_core::models::calendar::DeadlinesResponse;
+use portfolio_core::models::throttle::ThrottleKey;
+use portfolio_core::services::admission_round_service::AdmissionRoundService;
 use portfolio_core::services::application_service::ApplicationService;
+use portfolio_core::services::calendar_service::CalendarService;
+use portfolio_core::services::login_throttle_service::LoginThrottleService;
 use portfolio_core::services::portfolio_service::{PortfolioService, SubmissionProgress};
-use requests::LoginRequest;
-use rocket::http::{Cookie, CookieJar, Status};
-use rocket::response::status::Custom;
+use requests::{ChangePasswordRequest, LoginRequest};
+use rocket::http::{Cookie, CookieJar};
 use rocket::serde::json::Json;
 
 use sea_orm_rocket::Connection;
 
 use crate::guards::data::letter::Letter;
 use crate::guards::data::portfolio::Portfolio;
+use crate::responses::{ApiError, PortfolioStream};
+use crate::guards::request::client::ClientInfo;
+use crate::guards::request::phase::{DetailsOpen, PortfolioOpen};
 use crate::{guards::request::auth::ApplicationAuth, pool::Db, requests};
 
-use super::to_custom_error;
 
 #[post("/login", data = "<login_form>")]
 pub async fn login(
     conn: Connection<'_, Db>,
     login_form: Json<LoginRequest>,
-    // ip_addr: SocketAddr, // TODO uncomment in production
+    client: ClientInfo,
     cookies: &CookieJar<'_>,
-) -> Result<(), Custom<String>> {
-    let ip_addr: SocketAddr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0);
+) -> Result<(), ApiError> {
     let db = conn.into_inner();
-    let (session_token, private_key) = ApplicationService::login(
-        db,
-        login_form.application_id,
-        login_form.password.to_string(),
-        ip_addr.ip().to_string(),
+    let throttle_keys = [ThrottleKey::Ip(client.ip.to_string()), ThrottleKey::Candidate(login_form.application_id)];
+    let (session_token, key_secret) = LoginThrottleService::attempt(db, &throttle_keys,
+        ApplicationService::login(
+            db,
+            login_form.application_id,
+            login_form.password.to_string(),
+            client.ip.to_string(),
+            client.user_agent,
+        )
     )
     .await
-    .map_err(to_custom_error)?;
+    .map_err(ApiError::from)?;
 
     cookies.add_private(Cookie::new("id", session_token.clone()));
-    cookies.add_private(Cookie::new("key", private_key.clone()));
+    cookies.add_private(Cookie::new("key", key_secret.clone()));
 
     return Ok(());
 }
@@ -50,21 +58,18 @@ pub async fn logout(
     conn: Connection<'_, Db>,
     _session: ApplicationAuth,
     cookies: &CookieJar<'_>,
-) -> Result<(), Custom<String>> {
+) -> Result<(), ApiError> {
     let db = conn.into_inner();
 
     let cookie = cookies
         .get_private("id") // unwrap would be safe here because of the auth guard
-        .ok_or(Custom(
-            Status::Unauthorized,
-            "No session cookie".to_string(),
-        ))?;
+        .ok_or(ServiceError::Unauthorized)?;
     let session_id = Uuid::try_parse(cookie.value()) // unwrap would be safe here because of the auth guard
-        .map_err(|e| Custom(Status::BadRequest, e.to_string()))?;
+        .map_err(|_| ServiceError::InvalidId)?;
     let session = Query::find_session_by_uuid(db, session_id).await.unwrap().unwrap(); // TODO
     ApplicationService::logout(db, session)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     cookies.remove_private(Cookie::named("id"));
     cookies.remove_private(Cookie::named("key"));
@@ -72,43 +77,138 @@ pub async fn logout(
     Ok(())
 }
 
+#[get("/sessions")]
+pub async fn list_sessions(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    cookies: &CookieJar<'_>,
+) -> Result<Json<Vec<SessionResponse>>, ApiError> {
+    let db = conn.into_inner();
+    let application: entity::application::Model = session.into();
+    let current = cookies.get_private("id")
+        .and_then(|c| Uuid::try_parse(c.value()).ok());
+
+    let sessions = ApplicationService::list_sessions(db, &application, current)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(sessions))
+}
+
+#[delete("/sessions/<id>")]
+pub async fn revoke_session(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    id: String,
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+    let application: entity::application::Model = session.into();
+    let session_id = Uuid::try_parse(&id)
+        .map_err(|_| ServiceError::InvalidId)?;
+
+    ApplicationService::revoke_session(db, &application, session_id)
+        .await
+        .map_err(ApiError::from)
+}
+
+#[put("/password", data = "<password_form>")]
+pub async fn change_password(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+    password_form: Json<ChangePasswordRequest>,
+    cookies: &CookieJar<'_>,
+) -> Result<(), ApiError> {
+    let db = conn.into_inner();
+
+    let cookie = cookies
+        .get_private("id") // unwrap would be safe here because of the auth guard
+        .ok_or(ServiceError::Unauthorized)?;
+    let session_id = Uuid::try_parse(cookie.value())
+        .map_err(|_| ServiceError::InvalidId)?;
+
+    ApplicationService::change_password(
+        db,
+        session.into(),
+        session_id,
+        password_form.old_password.to_string(),
+        password_form.new_password.to_string(),
+    )
+    .await
+    .map_err(ApiError::from)?;
+
+    Ok(())
+}
+
 #[get("/whoami")]
-pub async fn whoami(conn: Connection<'_, Db>, session: ApplicationAuth) -> Result<Json<NewCandidateResponse>, Custom<String>> {
+pub async fn whoami(conn: Connection<'_, Db>, session: ApplicationAuth) -> Result<Json<NewCandidateResponse>, ApiError> {
     let db = conn.into_inner();
 
     let private_key = session.get_private_key();
     let application: entity::application::Model = session.into();
     let candidate = ApplicationService::find_related_candidate(&db, &application)
-        .await.map_err(to_custom_error)?; // TODO more compact
+        .await.map_err(ApiError::from)?; // TODO more compact
     let applications = Query::find_applications_by_candidate_id(&db, candidate.id)
-        .await.map_err(|e| to_custom_error(ServiceError::DbError(e)))?; 
+        .await.map_err(|e| ApiError::from(ServiceError::DbError(e)))?; 
     let response = NewCandidateResponse::from_encrypted(
         application.id,
         applications,
         &private_key,
         candidate
     ).await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(Json(response))
 }
 
+/// Admission rounds the candidate can link their schools to
+#[get("/admission_rounds")]
+pub async fn list_admission_rounds(
+    conn: Connection<'_, Db>,
+    _session: ApplicationAuth,
+) -> Result<Json<Vec<AdmissionRoundResponse>>, ApiError> {
+    let db = conn.into_inner();
+
+    let rounds = AdmissionRoundService::list(db)
+        .await
+        .map_err(ApiError::from)?;
+
+    Ok(Json(rounds))
+}
+
+/// Deadlines of the candidate including their individual extension
+#[get("/deadlines")]
+pub async fn deadlines(
+    conn: Connection<'_, Db>,
+    session: ApplicationAuth,
+) -> Result<Json<DeadlinesResponse>, ApiError> {
+    let db = conn.into_inner();
+    let application: application::Model = session.into();
+    let candidate = ApplicationService::find_related_candidate(&db, &application)
+        .await
+        .map_err(ApiError::from)?;
+
+    CalendarService::deadlines(&candidate)
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
 // TODO: use put instead of post???
 #[post("/details", data = "<details>")]
 pub async fn post_details(
     conn: Connection<'_, Db>,
     details: Json<ApplicationDetails>,
     session: ApplicationAuth,
-) -> Result<Json<ApplicationDetails>, Custom<String>> {
+    _open: DetailsOpen,
+) -> Result<Json<ApplicationDetails>, ApiError> {
     let db = conn.into_inner();
     let form = details.into_inner();
-    form.candidate.validate_self().map_err(to_custom_error)?;
+    form.candidate.validate_self().map_err(ApiError::from)?;
     let application: application::Model = session.into();
-    let candidate = ApplicationService::find_related_candidate(&db, &application).await.map_err(to_custom_error)?; // TODO
+    let candidate = ApplicationService::find_related_candidate(&db, &application).await.map_err(ApiError::from)?; // TODO
 
     let _candidate_parent = ApplicationService::add_all_details(db, &application, candidate, &form)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(Json(form))
 }
@@ -117,7 +217,7 @@ pub async fn post_details(
 pub async fn get_details(
     conn: Connection<'_, Db>,
     session: ApplicationAuth,
-) -> Result<Json<ApplicationDetails>, Custom<String>> {
+) -> Result<Json<ApplicationDetails>, ApiError> {
     let db = conn.into_inner();
     let private_key = session.get_private_key();
     let application: entity::application::Model = session.into();
@@ -129,31 +229,32 @@ pub async fn get_details(
     )
         .await
         .map(|x| Json(x))
-        .map_err(to_custom_error);
+        .map_err(ApiError::from);
 
     details
 }
 #[post("/cover_letter", data = "<letter>")]
 pub async fn upload_cover_letter(
     session: ApplicationAuth,
+    _open: PortfolioOpen,
     letter: Letter,
-) -> Result<(), Custom<String>> {
+) -> Result<(), ApiError> {
     let application: entity::application::Model = session.into();
 
     PortfolioService::add_cover_letter_to_cache(application.candidate_id, letter.into())
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(())
 }
 
 #[delete("/cover_letter")]
-pub async fn delete_cover_letter(session: ApplicationAuth) -> Result<(), Custom<String>> {
+pub async fn delete_cover_letter(session: ApplicationAuth, _open: PortfolioOpen) -> Result<(), ApiError> {
     let application: entity::application::Model = session.into();
 
     PortfolioService::delete_cover_letter_from_cache(application.candidate_id)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(())
 }
@@ -161,24 +262,25 @@ pub async fn delete_cover_letter(session: ApplicationAuth) -> Result<(), Custom<
 #[post("/portfolio_letter", data = "<letter>")]
 pub async fn upload_portfolio_letter(
     session: ApplicationAuth,
+    _open: PortfolioOpen,
     letter: Letter,
-) -> Result<(), Custom<String>> {
+) -> Result<(), ApiError> {
     let application: entity::application::Model = session.into();
 
     PortfolioService::add_portfolio_letter_to_cache(application.candidate_id, letter.into())
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(())
 }
 
 #[delete("/portfolio_letter")]
-pub async fn delete_portfolio_letter(session: ApplicationAuth) -> Result<(), Custom<String>> {
+pub async fn delete_portfolio_letter(session: ApplicationAuth, _open: PortfolioOpen) -> Result<(), ApiError> {
     let candidate: entity::application::Model = session.into();
 
     PortfolioService::delete_portfolio_letter_from_cache(candidate.candidate_id)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(())
 }
@@ -186,24 +288,27 @@ pub async fn delete_portfolio_letter(session: ApplicationAuth) -> Result<(), Cus
 #[post("/portfolio_zip", data = "<portfolio>")]
 pub async fn upload_portfolio_zip(
     session: ApplicationAuth,
+    _open: PortfolioOpen,
     portfolio: Portfolio,
-) -> Result<(), Custom<String>> {
+) -> Result<(), ApiError> {
     let application: entity::application::Model = session.into();
 
-    PortfolioService::add_portfolio_zip_to_cache(application.candidate_id, portfolio.into())
+    let (zip, manifest) = portfolio.into_parts();
+
+    PortfolioService::add_portfolio_zip_to_cache(application.candidate_id, zip, &manifest)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(())
 }
 
 #[delete("/portfolio_zip")]
-pub async fn delete_portfolio_zip(session: ApplicationAuth) -> Result<(), Custom<String>> {
+pub async fn delete_portfolio_zip(session: ApplicationAuth, _open: PortfolioOpen) -> Result<(), ApiError> {
     let application: entity::application::Model = session.into();
 
     PortfolioService::delete_portfolio_zip_from_cache(application.candidate_id)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(())
 }
@@ -211,13 +316,13 @@ pub async fn delete_portfolio_zip(session: ApplicationAuth) -> Result<(), Custom
 #[get("/submission_progress")]
 pub async fn submission_progress(
     session: ApplicationAuth,
-) -> Result<Json<SubmissionProgress>, Custom<String>> {
+) -> Result<Json<SubmissionProgress>, ApiError> {
     let application: entity::application::Model = session.into();
 
     let progress = PortfolioService::get_submission_progress(application.candidate_id)
         .await
         .map(|x| Json(x))
-        .map_err(to_custom_error);
+        .map_err(ApiError::from);
 
     progress
 }
@@ -226,13 +331,15 @@ pub async fn submission_progress(
 pub async fn submit_portfolio(
     conn: Connection<'_, Db>,
     session: ApplicationAuth,
-) -> Result<(), Custom<String>> {
+    _open: PortfolioOpen,
+) -> Result<(), ApiError> {
     let db = conn.into_inner();
 
+    let private_key = session.get_private_key();
     let application: entity::application::Model = session.into();
-    let candidate = ApplicationService::find_related_candidate(&db, &application).await.map_err(to_custom_error)?; // TODO
+    let candidate = ApplicationService::find_related_candidate(&db, &application).await.map_err(ApiError::from)?; // TODO
 
-    let submit = PortfolioService::submit(&candidate, &db).await;
+    let submit = PortfolioService::submit(&candidate, &db, &private_key).await;
 
     if submit.is_err() {
         let e = submit.err().unwrap();
@@ -243,7 +350,7 @@ pub async fn submit_portfolio(
                 .await
                 .unwrap();
         }
-        return Err(to_custom_error(e));
+        return Err(e.into());
     }
 
     Ok(())
@@ -252,33 +359,34 @@ pub async fn submit_portfolio(
 #[post("/delete")]
 pub async fn delete_portfolio(
     session: ApplicationAuth,
-) -> Result<(), Custom<String>> {
+    _open: PortfolioOpen,
+) -> Result<(), ApiError> {
     let application: entity::application::Model = session.into();
 
     PortfolioService::delete_portfolio(application.candidate_id)
         .await
-        .map_err(to_custom_error)?;
+        .map_err(ApiError::from)?;
 
     Ok(())
 }
 
 #[get("/download")]
-pub async fn download_portfolio(session: ApplicationAuth) -> Result<Vec<u8>, Custom<String>> {
+pub async fn download_portfolio(session: ApplicationAuth) -> Result<PortfolioStream, ApiError> {
     let private_key = session.get_private_key();
     let application: entity::application::Model = session.into();
 
     let file = PortfolioService::get_portfolio(application.candidate_id, private_key)
         .await
-        .map_err(to_custom_error);
+        .map_err(ApiError::from)?;
 
-    file
+    Ok(PortfolioStream(file))
 }
 
 #[cfg(test)]
 mod tests {
-    use portfolio_core::{crypto, models::candidate::{ApplicationDetails, NewCandidateResponse}, sea_orm::prelude::Uuid};
+    use portfolio_core::{crypto, models::{auth::SessionResponse, candidate::{ApplicationDetails, NewCandidateResponse}}, sea_orm::prelude::Uuid};
     use rocket::{
-        http::{Cookie, Status},
+        http::{Cookie, Header, Status},
         local::blocking::Client,
     };
 
@@ -445,4 +553,64 @@ mod tests {
             .dispatch();
         assert_eq!(response.status(), Status::Unauthorized);
     }
+
+    #[test]
+    fn test_list_and_revoke_sessions() {
+        let client = test_client().lock().unwrap();
+        let login = |user_agent: &'static str| {
+            let response = client
+                .post("/candidate/login")
+                .header(Header::new("User-Agent", user_agent))
+                .body(format!(
+                    "{{\"applicationId\": {}, \"password\": \"{}\"}}",
+                    APPLICATION_ID, CANDIDATE_PASSWORD
+                ))
+                .dispatch();
+            assert_eq!(response.status(), Status::Ok);
+            (
+                response.cookies().get("id").unwrap().to_owned(),
+                response.cookies().get("key").unwrap().to_owned(),
+            )
+        };
+        let lost_device = login("lost device");
+        let cookies = login("current device");
+
+        let response = client
+            .get("/candidate/sessions")
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+        let sessions = response.into_json::<Vec<SessionResponse>>().unwrap();
+
+        let current = sessions.iter().find(|s| s.current).unwrap();
+        assert_eq!(current.user_agent.as_deref(), Some("current device"));
+        assert!(current.last_seen_at.is_some());
+        let lost = sessions.iter()
+            .find(|s| s.user_agent.as_deref() == Some("lost device"))
+            .unwrap();
+        assert!(!lost.current);
+
+        let response = client
+            .delete(format!("/candidate/sessions/{}", lost.id))
+            .cookie(cookies.0.clone())
+            .cookie(cookies.1.clone())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+
+        let response = client
+            .get("/candidate/whoami")
+            .cookie(lost_device.0)
+            .cookie(lost_device.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::Unauthorized);
+
+        // sessions of other users can't be revoked
+        let response = client
+            .delete(format!("/candidate/sessions/{}", Uuid::new_v4()))
+            .cookie(cookies.0)
+            .cookie(cookies.1)
+            .dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
 }
@@ -0,0 +1,42 @@
+use rocket::http::Status;
+use rocket::response::status::Custom;
+use rocket::serde::json::Json;
+use rocket::Request;
+
+use crate::responses::{catch_error, ErrorResponse};
+
+#[catch(400)]
+pub fn bad_request(req: &Request) -> Custom<Json<ErrorResponse>> {
+    catch_error(Status::BadRequest, req)
+}
+
+#[catch(401)]
+pub fn unauthorized(req: &Request) -> Custom<Json<ErrorResponse>> {
+    catch_error(Status::Unauthorized, req)
+}
+
+#[catch(404)]
+pub fn not_found(req: &Request) -> Custom<Json<ErrorResponse>> {
+    catch_error(Status::NotFound, req)
+}
+
+#[catch(413)]
+pub fn payload_too_large(req: &Request) -> Custom<Json<ErrorResponse>> {
+    catch_error(Status::PayloadTooLarge, req)
+}
+
+#[catch(422)]
+pub fn unprocessable_entity(req: &Request) -> Custom<Json<ErrorResponse>> {
+    catch_error(Status::UnprocessableEntity, req)
+}
+
+#[catch(500)]
+pub fn internal_server_error(req: &Request) -> Custom<Json<ErrorResponse>> {
+    catch_error(Status::InternalServerError, req)
+}
+
+/// Other statuses of failed guards, e.g. 403 after a deadline
+#[catch(default)]
+pub fn default(status: Status, req: &Request) -> Custom<Json<ErrorResponse>> {
+    catch_error(status, req)
+}
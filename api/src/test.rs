@@ -35,19 +35,21 @@ pub mod tests {
             public_key: Set(pubkey),
             private_key: Set(priv_key),
             password: Set(password_hash),
+            role: Set("superadmin".to_string()),
             created_at: Set(chrono::Utc::now().naive_utc()),
             updated_at: Set(chrono::Utc::now().naive_utc()),
+            ..Default::default()
         }
         .insert(db)
         .await
         .unwrap();
 
         ApplicationService::create(
-            &"".to_string(),
             db,
             APPLICATION_ID,
             &CANDIDATE_PASSWORD.to_string(),
-            PERSONAL_ID_NUMBER.to_string())
+            PERSONAL_ID_NUMBER.to_string(),
+            None)
             .await.unwrap();
     }
 
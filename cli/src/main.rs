@@ -2,11 +2,26 @@ use std::path::PathBuf;
 
 use clap::{arg, ArgAction, ArgMatches, command, Command, value_parser};
 use sea_orm::{Database, DatabaseConnection, DbConn};
+use tokio::io::AsyncWriteExt;
 use url::Url;
 
-use portfolio_core::{crypto, Query};
-use portfolio_core::services::portfolio_service::{FileType};
-use portfolio_core::utils::csv::{ApplicationCsv, CsvExporter};
+use portfolio_core::{crypto, error::ServiceError, Query};
+use portfolio_core::models::admin::FieldScope;
+use portfolio_core::models::audit::{AuditAction, AuditLogFilter};
+use portfolio_core::models::candidate::ImportStatus;
+use portfolio_core::models::package::{FolderNameTemplate, DEFAULT_FOLDER_NAME_TEMPLATE};
+use portfolio_core::services::admin_service::AdminService;
+use portfolio_core::services::audit_service::AuditService;
+use portfolio_core::services::backup_service::BackupService;
+use portfolio_core::services::candidate_service::CandidateService;
+use portfolio_core::services::import_service::ImportService;
+use portfolio_core::services::letter_service::LetterService;
+use portfolio_core::services::notification_service::NotificationService;
+use portfolio_core::services::package_service::PackageService;
+use portfolio_core::services::totp_service::TotpService;
+use portfolio_core::storage::get_store;
+use portfolio_core::utils::csv::{parse_credentials, ApplicationCsv};
+use portfolio_core::utils::date::parse_naive_datetime;
 
 async fn get_admin_private_key(db: &DbConn, sub_matches: &ArgMatches) -> Result<String, Box<dyn std::error::Error>> {
     Ok(match (sub_matches.get_one::<String>("key"), sub_matches.get_one::<String>("password")) {
@@ -133,7 +148,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .subcommand(
             Command::new("package")
-                .about("Package all data into one zip")
+                .about("Export personal data and decrypted portfolios into folders named after candidates")
                 .arg(
                     arg!(
                         -s --pg_dump "Backup SQL database with pg_dump (PostgreSQL only)"
@@ -141,11 +156,55 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 )
                 .arg(
                     arg!(
-                        -r --root_dir <PATH> "Portfolio root directory"
+                        -t --name_template <TEMPLATE> "Candidate folder name, placeholders {name}, {surname}, {applications}, {fields} and {candidate_id}"
+                    )
+                        .required(false)
+                        .default_value(DEFAULT_FOLDER_NAME_TEMPLATE),
+                )
+                .arg(
+                    arg!(
+                        -r --root_dir <PATH> "Portfolio root directory of the fs store, PORTFOLIO_STORE_PATH by default"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -o --output <PATH> "Output file path"
                     )
                         .required(true)
                         .value_parser(value_parser!(PathBuf)),
                 )
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("backup")
+                .about("Back up the database and all candidate files into one archive encrypted to all admins")
                 .arg(
                     arg!(
                         -o --output <PATH> "Output file path"
@@ -153,6 +212,298 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .required(true)
                         .value_parser(value_parser!(PathBuf)),
                 )
+                .arg(
+                    arg!(
+                        --root_dir <PATH> "Portfolio root directory of the fs store, PORTFOLIO_STORE_PATH by default"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -r --recipient <PUBLIC_KEY> "Additional public key the backup is encrypted to (can be used multiple times)"
+                    )
+                        .required(false)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin making the backup, recorded in the audit log"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("restore")
+                .about("Verify a backup and restore it into an empty database and portfolio root directory")
+                .arg(
+                    arg!(
+                        -f --file <PATH> "Backup file path"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        --verify ... "Only check that the backup can be decrypted and matches its manifest"
+                    )
+                        .action(ArgAction::SetTrue)
+                        .required(false)
+                )
+                .arg(
+                    arg!(
+                        --root_dir <PATH> "Portfolio root directory of the fs store, PORTFOLIO_STORE_PATH by default"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("rotate")
+                .about("Re-encrypt all candidate data and portfolios to a new set of admin public keys")
+                .arg(
+                    arg!(
+                        -r --recipient <PUBLIC_KEY> "New admin public key (can be used multiple times)"
+                    )
+                        .required(true)
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    arg!(
+                        -s --state <PATH> "Progress file, rotation resumes from it when interrupted"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "Current AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Current password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("remind")
+                .about("Email a reminder to all candidates who have not submitted their portfolio yet")
+                .arg(
+                    arg!(
+                        --deadline <DATETIME> "Submission deadline shown in the reminder (YYYY-MM-DD HH:MM:SS)"
+                    )
+                        .required(true),
+                )
+                .arg(
+                    arg!(
+                        --root_dir <PATH> "Portfolio root directory"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("List audit log entries and verify the audit log hash chain")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -c --candidate <CANDIDATE_ID> "Only entries touching this candidate"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Only entries made by this admin"
+                    )
+                        .required(false)
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    arg!(
+                        --from <DATETIME> "Entries created at or after (YYYY-MM-DD HH:MM:SS)"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        --to <DATETIME> "Entries created at or before (YYYY-MM-DD HH:MM:SS)"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        --verify ... "Verify integrity of the whole hash chain"
+                    )
+                        .action(ArgAction::SetTrue)
+                        .required(false)
+                )
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Create candidates from a CSV with application_id, personal_id_number and optional email columns")
+                .arg(
+                    arg!(
+                        -i --input <PATH> "CSV file path"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -o --output <PATH> "Output path of the generated passwords, encrypted for all admins"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("letter")
+                .about("Print the credential letter of an application to a PDF")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        --application <ID> "Application ID"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(i32)),
+                )
+                .arg(
+                    arg!(
+                        --candidate_password <PASSWORD> "Password of the application printed on the letter"
+                    )
+                        .required(true),
+                )
+                .arg(
+                    arg!(
+                        -o --output <PATH> "Output PDF path"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID recorded in the audit log"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("letters")
+                .about("Print credential letters of all applications in the passwords file of an import to one PDF")
                 .arg(
                     arg!(
                         -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
@@ -161,6 +512,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .required(true)
                         .value_parser(value_parser!(Url)),
                 )
+                .arg(
+                    arg!(
+                        -i --input <PATH> "CSV with application_id and password columns, age encrypted or plain"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
+                .arg(
+                    arg!(
+                        -o --output <PATH> "Output PDF path"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(PathBuf)),
+                )
                 .arg(
                     arg!(
                         -k --key <KEY> "AGE private key for decryption"
@@ -180,6 +545,62 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                         .required(false),
                 )
         )
+        .subcommand(
+            Command::new("blind_index")
+                .about("Compute blind indexes of personal identification numbers of existing candidates")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        --all ... "Recompute indexes of all candidates, e.g. after changing PORTFOLIO_BLIND_INDEX_KEY"
+                    )
+                        .action(ArgAction::SetTrue)
+                        .required(false)
+                )
+                .arg(
+                    arg!(
+                        -k --key <KEY> "AGE private key for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -p --password <PASSWORD> "Password for decryption"
+                    )
+                        .required(false),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin ID"
+                    )
+                        .required(false),
+                )
+        )
+        .subcommand(
+            Command::new("reset_totp")
+                .about("Remove the second factor of an admin who lost access to their authenticator")
+                .arg(
+                    arg!(
+                        -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                    )
+                        .alias("url")
+                        .required(true)
+                        .value_parser(value_parser!(Url)),
+                )
+                .arg(
+                    arg!(
+                        -a --admin_id <ADMIN_ID> "Admin whose second factor is removed"
+                    )
+                        .required(true)
+                        .value_parser(value_parser!(i32)),
+                )
+        )
         .subcommand(
             Command::new("hash")
                 .about("Hash operations")
@@ -238,12 +659,94 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         )
         .subcommand(
             Command::new("admin")
-                .about("Create admin")
-                .arg(
-                    arg!(
-                        -p --password <PASWORD> "Password"
-                    )
-                    .required(true)
+                .about("Admin accounts")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("create")
+                        .about("Create an admin directly in the database, meant for the first run")
+                        .arg(
+                            arg!(
+                                -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                            )
+                                .alias("url")
+                                .required(true)
+                                .value_parser(value_parser!(Url)),
+                        )
+                        .arg(
+                            arg!(
+                                -n --name <NAME> "Admin name"
+                            )
+                                .required(true)
+                        )
+                        .arg(
+                            arg!(
+                                -p --password <PASSWORD> "Password, generated when missing"
+                            )
+                                .required(false)
+                        )
+                        .arg(
+                            arg!(
+                                --force ... "Create the admin even when other admins exist"
+                            )
+                                .action(ArgAction::SetTrue)
+                                .required(false)
+                        )
+                        .arg(
+                            arg!(
+                                -k --key <KEY> "AGE private key of an existing admin, existing candidates are re-encrypted for the new admin"
+                            )
+                                .required(false)
+                        )
+                        .arg(
+                            arg!(
+                                -a --admin_id <ADMIN_ID> "Existing admin whose key re-encrypts the candidates, used with --admin_password"
+                            )
+                                .required(false)
+                                .value_parser(value_parser!(i32))
+                        )
+                        .arg(
+                            arg!(
+                                --admin_password <PASSWORD> "Password of the existing admin"
+                            )
+                                .required(false)
+                        )
+                )
+                .subcommand(
+                    Command::new("activate")
+                        .about("Re-encrypt all candidates to the keys of admins created through the API, so they can log in")
+                        .arg(
+                            arg!(
+                                -d --database <URL> "URL to the database or sql file with postgres:// or sqlite://"
+                            )
+                                .alias("url")
+                                .required(true)
+                                .value_parser(value_parser!(Url)),
+                        )
+                        .arg(
+                            arg!(
+                                -k --key <KEY> "AGE private key of an active admin"
+                            )
+                                .required(false),
+                        )
+                        .arg(
+                            arg!(
+                                -p --password <PASSWORD> "Password of the active admin"
+                            )
+                                .required(false),
+                        )
+                        .arg(
+                            arg!(
+                                -a --admin_id <ADMIN_ID> "Admin ID"
+                            )
+                                .required(false),
+                        )
+                        .arg(
+                            arg!(
+                                --root_dir <PATH> "Portfolio root directory of the fs store, PORTFOLIO_STORE_PATH by default"
+                            )
+                                .required(false)
+                                .value_parser(value_parser!(PathBuf)),
+                        )
                 ),
         )
         .get_matches();
@@ -253,8 +756,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let db = get_db_conn(sub_matches).await?;
             let key = get_admin_private_key(&db, sub_matches).await?;
 
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
             let output = sub_matches.get_one::<PathBuf>("output").unwrap();
-            let csv = ApplicationCsv::export(&db, key).await?;
+            let csv = AdminService::export_csv::<ApplicationCsv>(&db, admin_id, &FieldScope::All, key, AuditAction::ExportApplications).await?;
             tokio::fs::write(output, csv).await?;
         },
         Some(("portfolio", sub_matches)) => {
@@ -263,51 +770,300 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             let age_file_path = sub_matches.get_one::<PathBuf>("file").unwrap();
 
-            let decrypted = crypto::decrypt_file_with_private_key_as_buffer(age_file_path, &key).await?;
-
             let output = sub_matches.get_one::<PathBuf>("output").unwrap();
-            tokio::fs::write(output, decrypted).await?;
+
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
+            crypto::decrypt_file_with_private_key(age_file_path, output, &key).await?;
+            AuditService::log(&db, admin_id, None, None, AuditAction::DecryptPortfolio, Some(age_file_path.display().to_string())).await?;
         },
         Some(("package", sub_matches)) => {
             let db_url = sub_matches.get_one::<Url>("database").unwrap();
             let db = get_db_conn(sub_matches).await?;
             let key = get_admin_private_key(&db, sub_matches).await?;
 
-            let portfolio_root_dir = sub_matches.get_one::<PathBuf>("root_dir").unwrap();
+            if let Some(portfolio_root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", portfolio_root_dir);
+            }
             let output = sub_matches.get_one::<PathBuf>("output").unwrap();
             tokio::fs::create_dir_all(&output).await?;
 
-            let csv = ApplicationCsv::export(&db, key.to_string()).await?;
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
+            let csv = AdminService::export_csv::<ApplicationCsv>(&db, admin_id, &FieldScope::All, key.to_string(), AuditAction::ExportApplications).await?;
             tokio::fs::write(output.join("personal_data.csv"), csv).await?;
             println!("Exported personal data to personal_data.csv");
 
-            let ids: Vec<i32> = Query::list_all_candidate_ids(&db)
-                .await?
-                .iter()
-                .map(|application_id| application_id.to_i32())
-                .collect();
-            for id in ids {
-                let file_path = portfolio_root_dir.join(&id.to_string()).join(FileType::Age.as_str());
-                println!("{}", file_path.display());
-                let output_path = output.join(&id.to_string());
-                if let Ok(portfolio) = crypto::decrypt_file_with_private_key_as_buffer(file_path, &key).await {
-                    tokio::fs::create_dir_all(&output_path).await?;
-                    tokio::fs::write(&output_path.join(FileType::PortfolioZip.as_str()), portfolio).await?;
-                };
-            }
-            println!("Exported all portfolios");
+            let template = FolderNameTemplate::new(sub_matches.get_one::<String>("name_template").unwrap())?;
+            let report = PackageService::package_all(&db, &key, output, &template).await?;
+            AuditService::log(&db, admin_id, None, None, AuditAction::PackagePortfolios, Some(format!(
+                "packaged={} not_submitted={} failed={}",
+                report.packaged,
+                report.not_submitted.len(),
+                report.failures.len(),
+            ))).await?;
+            tokio::fs::write(output.join("package_report.txt"), report.to_string()).await?;
+            print!("{}", report.to_string());
+            println!("Exported all portfolios, see package_report.txt");
 
             if *sub_matches.get_one::<bool>("pg_dump").unwrap_or(&false) {
                 let file = std::fs::File::create(&output.join("pg_dump.sql"))?;
-                tokio::process::Command::new("pg_dump")
+                let status = tokio::process::Command::new("pg_dump")
                     .args(&[db_url.as_str()])
                     .stdout(file)
-                    .spawn()
-                    .expect("failed to start pg_dump");
+                    .status()
+                    .await?;
+                if !status.success() {
+                    return Err(format!("pg_dump failed: {}", status))?;
+                }
+                println!("Exported database");
+            }
+
+        }
+        Some(("backup", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+
+            let recipients: Vec<String> = sub_matches.get_many::<String>("recipient")
+                .map(|r| r.map(|r| r.to_string()).collect())
+                .unwrap_or_default();
+            if let Some(invalid) = recipients.iter().find(|r| !crypto::is_valid_public_key(r)) {
+                return Err(format!("Invalid public key {}", invalid))?;
+            }
+
+            if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+            }
+            let output = sub_matches.get_one::<PathBuf>("output").unwrap();
+
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
+            let manifest = BackupService::create(&db, get_store()?.as_ref(), output, &recipients).await?;
+            AuditService::log(&db, admin_id, None, None, AuditAction::CreateBackup, Some(format!("files={}", manifest.entries.len()))).await?;
+            for table in manifest.tables.iter() {
+                println!("{}: {} rows", table.name, table.rows);
+            }
+            println!("Backup of {} files ({} bytes) written to {}", manifest.entries.len(), manifest.total_size(), output.display());
+        },
+        Some(("restore", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+
+            let backup = sub_matches.get_one::<PathBuf>("file").unwrap();
+            if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+            }
+
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
+            // restore needs an empty database, so the entry is written after it
+            let (manifest, action) = if *sub_matches.get_one::<bool>("verify").unwrap_or(&false) {
+                (BackupService::verify(backup, &key).await?, AuditAction::VerifyBackup)
+            } else {
+                (BackupService::restore(&db, backup, &key, get_store()?.as_ref()).await?, AuditAction::RestoreBackup)
+            };
+            AuditService::log(&db, admin_id, None, None, action, Some(format!("created_at={}", manifest.created_at))).await?;
+            println!("Backup from {} with {} files is valid", manifest.created_at, manifest.entries.len());
+        },
+        Some(("rotate", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+
+            let recipients: Vec<String> = sub_matches.get_many::<String>("recipient")
+                .unwrap()
+                .map(|r| r.to_string())
+                .collect();
+            if let Some(invalid) = recipients.iter().find(|r| !crypto::is_valid_public_key(r)) {
+                return Err(format!("Invalid public key {}", invalid))?;
+            }
+
+            let portfolio_root_dir = sub_matches.get_one::<PathBuf>("root_dir").unwrap();
+            std::env::set_var("PORTFOLIO_STORE_PATH", portfolio_root_dir);
+
+            // dropped keys are retired first, so candidates written during the rotation don't get them back
+            let retired = AdminService::retire_admin_keys(&db, &recipients)
+                .await
+                .map_err(|e| match e {
+                    ServiceError::InvalidPublicKey => "None of the recipients is a key of an admin, create the new admins first".to_string(),
+                    e => e.to_string(),
+                })?;
+            for admin in retired {
+                println!("Key of admin {} ({}) retired", admin.id, admin.name);
+            }
+
+            // progress of a previous (interrupted) run, `<id> data` once the database rows of the candidate
+            // are rotated and `<id>` once the portfolio is too, rotated rows can't be read with the old key anymore
+            let state_path = sub_matches.get_one::<PathBuf>("state").unwrap();
+            let state_lines: Vec<String> = match tokio::fs::read_to_string(state_path).await {
+                Ok(state) => state.lines().map(|l| l.trim().to_string()).collect(),
+                Err(_) => vec![],
+            };
+            let rotated: Vec<i32> = state_lines.iter().filter_map(|l| l.parse().ok()).collect();
+            let data_rotated: Vec<i32> = state_lines.iter()
+                .filter_map(|l| l.strip_suffix(" data"))
+                .filter_map(|id| id.parse().ok())
+                .collect();
+            let mut state = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(state_path)
+                .await?;
+
+            let ids: Vec<i32> = Query::list_all_candidate_ids(&db)
+                .await?
+                .iter()
+                .map(|candidate_id| candidate_id.to_i32())
+                .filter(|id| !rotated.contains(id))
+                .collect();
+            let total = ids.len();
+            println!("Rotating keys of {} candidates ({} already done)", total, rotated.len());
+
+            for (index, id) in ids.into_iter().enumerate() {
+                if !data_rotated.contains(&id) {
+                    AdminService::rotate_candidate_data_keys(&db, &key, id, &recipients)
+                        .await
+                        .map_err(|e| format!("Candidate {} failed: {}, run again to resume", id, e))?;
+                    state.write_all(format!("{} data\n", id).as_bytes()).await?;
+                    state.flush().await?;
+                }
+                AdminService::rotate_candidate_portfolio_keys(&db, &key, id, &recipients)
+                    .await
+                    .map_err(|e| format!("Portfolio of candidate {} failed: {}, run again to resume", id, e))?;
+                state.write_all(format!("{}\n", id).as_bytes()).await?;
+                state.flush().await?;
+                println!("[{}/{}] Candidate {} rotated", index + 1, total, id);
             }
-            println!("Exported database");
 
+            println!("Key rotation finished");
         }
+        Some(("remind", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+
+            let root_dir = sub_matches.get_one::<PathBuf>("root_dir").unwrap();
+            std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+
+            let deadline = parse_naive_datetime(sub_matches.get_one::<String>("deadline").unwrap())?;
+
+            let reminded = NotificationService::send_deadline_reminders(&db, &key, deadline).await?;
+            println!("Reminder sent to {} candidates", reminded);
+        },
+        Some(("audit", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+
+            if *sub_matches.get_one::<bool>("verify").unwrap_or(&false) {
+                match AuditService::verify_chain(&db).await? {
+                    Some(id) => return Err(format!("Audit log chain broken at entry {}", id))?,
+                    None => println!("Audit log chain is intact"),
+                }
+            }
+
+            let parse = |arg: &str| sub_matches.get_one::<String>(arg)
+                .map(|d| parse_naive_datetime(d))
+                .transpose();
+
+            let filter = AuditLogFilter {
+                candidate_id: sub_matches.get_one::<i32>("candidate").copied(),
+                admin_id: sub_matches.get_one::<i32>("admin_id").copied(),
+                from: parse("from")?,
+                to: parse("to")?,
+            };
+
+            for entry in AuditService::list(&db, &filter).await? {
+                println!(
+                    "{}\t{}\t{}\tadmin={}\tcandidate={}\tapplication={}\t{}",
+                    entry.id,
+                    entry.created_at,
+                    entry.action,
+                    entry.admin_id.map(|id| id.to_string()).unwrap_or_default(),
+                    entry.candidate_id.map(|id| id.to_string()).unwrap_or_default(),
+                    entry.application_id.map(|id| id.to_string()).unwrap_or_default(),
+                    entry.detail.unwrap_or_default(),
+                );
+            }
+        },
+        Some(("import", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
+            let input = tokio::fs::read(sub_matches.get_one::<PathBuf>("input").unwrap()).await?;
+            let output = sub_matches.get_one::<PathBuf>("output").unwrap();
+            let recipients = Query::get_all_admin_public_keys(&db).await?;
+
+            let report = ImportService::import_candidates(&db, admin_id, &input, &recipients).await?;
+            tokio::fs::write(output, &report.passwords).await?;
+
+            for result in report.results.iter().filter(|r| r.status == ImportStatus::Rejected) {
+                println!(
+                    "Line {} ({}) rejected: {}",
+                    result.line,
+                    result.application_id.map(|id| id.to_string()).unwrap_or_default(),
+                    result.reason.to_owned().unwrap_or_default(),
+                );
+            }
+            println!("Import finished: {}", report.summary());
+            println!("Passwords written to {}, decrypt them with the portfolio subcommand", output.display());
+        },
+        Some(("letter", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let application_id = *sub_matches.get_one::<i32>("application").unwrap();
+            let password = sub_matches.get_one::<String>("candidate_password").unwrap();
+            let output = sub_matches.get_one::<PathBuf>("output").unwrap();
+
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
+            let letter = LetterService::credential_letter(&db, admin_id, application_id, password.to_string()).await?;
+            tokio::fs::write(output, letter).await?;
+            println!("Letter written to {}", output.display());
+        },
+        Some(("letters", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let input = sub_matches.get_one::<PathBuf>("input").unwrap();
+            let output = sub_matches.get_one::<PathBuf>("output").unwrap();
+
+            let mut data = tokio::fs::read(input).await?;
+            if data.starts_with(b"age-encryption.org/") {
+                let key = get_admin_private_key(&db, sub_matches).await?;
+                data = crypto::decrypt_file_with_private_key_as_buffer(input, &key).await?;
+            }
+            let credentials = parse_credentials(&data)?;
+            let count = credentials.len();
+
+            let admin_id = sub_matches.get_one::<String>("admin_id")
+                .map(|id| id.parse::<i32>())
+                .transpose()?;
+
+            let letters = LetterService::credential_letters(&db, admin_id, credentials).await?;
+            tokio::fs::write(output, letters).await?;
+            println!("{} letters written to {}", count, output.display());
+        },
+        Some(("blind_index", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let key = get_admin_private_key(&db, sub_matches).await?;
+            let all = *sub_matches.get_one::<bool>("all").unwrap();
+
+            let count = CandidateService::backfill_personal_id_index(&db, &key, all).await?;
+            println!("Blind index computed for {} candidates", count);
+        },
+        Some(("reset_totp", sub_matches)) => {
+            let db = get_db_conn(sub_matches).await?;
+            let admin_id = *sub_matches.get_one::<i32>("admin_id").unwrap();
+
+            TotpService::reset(&db, admin_id).await?;
+            println!("Second factor of admin {} removed", admin_id);
+        },
         Some(("hash", sub_matches)) => {
             let input = sub_matches.get_one::<String>("input").unwrap();
 
@@ -341,24 +1097,60 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
             println!("{}", result);
         }
-        Some(("admin", sub_matches)) => {
-            let input = sub_matches.get_one::<String>("password").unwrap();
+        Some(("admin", sub_matches)) => match sub_matches.subcommand() {
+            Some(("create", sub_matches)) => {
+                let db = get_db_conn(sub_matches).await?;
+                let name = sub_matches.get_one::<String>("name").unwrap();
+                let force = *sub_matches.get_one::<bool>("force").unwrap();
+                let password = sub_matches.get_one::<String>("password")
+                    .map(|p| p.to_string())
+                    .unwrap_or_else(crypto::random_12_char_string);
 
-            let (pubkey, priv_key) = crypto::create_identity();
-            
-            let priv_key = crypto::encrypt_password(priv_key, input.to_string())
-                .await
-                .unwrap();
+                let admin_private_key = match (
+                    sub_matches.get_one::<String>("key"),
+                    sub_matches.get_one::<i32>("admin_id"),
+                    sub_matches.get_one::<String>("admin_password"),
+                ) {
+                    (Some(key), _, _) => Some(key.to_string()),
+                    (_, Some(admin_id), Some(admin_password)) => {
+                        let existing = Query::find_admin_by_id(&db, *admin_id)
+                            .await?
+                            .ok_or("Admin not found")?;
+                        Some(crypto::decrypt_password(existing.private_key, admin_password.to_string()).await?)
+                    },
+                    _ => None,
+                };
+                if force && admin_private_key.is_none() && !Query::list_all_candidate_ids(&db).await?.is_empty() {
+                    return Err("Existing candidates have to be re-encrypted for the new admin, pass the private key of an existing admin with -k or -a and --admin_password")?;
+                }
 
-            let password_hash = crypto::hash_password(input.to_string())
-                .await
-                .unwrap();
+                let admin = AdminService::bootstrap_admin(&db, name.to_string(), password.to_owned(), force, admin_private_key.as_ref())
+                    .await
+                    .map_err(|e| match e {
+                        ServiceError::UserAlreadyExists => "Admins already exist, use --force to create another one".to_string(),
+                        e => e.to_string(),
+                    })?;
 
-        
-            println!("{}", pubkey);
-            println!("{}", priv_key);
-            println!("{}", password_hash);
-        }
+                println!("Admin {} created", admin.id);
+                if sub_matches.get_one::<String>("password").is_none() {
+                    println!("Password: {}", password);
+                }
+            },
+            Some(("activate", sub_matches)) => {
+                let db = get_db_conn(sub_matches).await?;
+                let key = get_admin_private_key(&db, sub_matches).await?;
+                if let Some(root_dir) = sub_matches.get_one::<PathBuf>("root_dir") {
+                    std::env::set_var("PORTFOLIO_STORE_PATH", root_dir);
+                }
+
+                let activated = AdminService::activate_pending_admins(&db, &key).await?;
+                for admin in &activated {
+                    println!("Admin {} ({}) activated", admin.id, admin.name);
+                }
+                println!("{} pending admins activated", activated.len());
+            },
+            _ => unreachable!(),
+        },
         _ => unreachable!("Exhausted list of subcommands and subcommand_required prevents `None`"),
     }
 
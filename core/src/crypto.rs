@@ -3,14 +3,17 @@ use aes_gcm_siv::KeyInit;
 use argon2::{
     Argon2, PasswordHasher as ArgonPasswordHasher, PasswordVerifier as ArgonPasswordVerifier,
 };
-use async_compat::CompatExt;
+use async_compat::{Compat, CompatExt};
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as base64;
 use futures::io::{AsyncReadExt, AsyncWriteExt};
+use hmac::{Hmac, Mac};
 use rand::Rng;
 use secrecy::ExposeSecret;
+use sha2::Sha256;
 use std::iter;
 use std::path::Path;
+use std::pin::Pin;
 use std::str::FromStr;
 
 use crate::error::ServiceError;
@@ -32,6 +35,13 @@ pub fn random_12_char_string() -> String {
         .join("")
 }
 
+/// Random secret for symmetric encryption, 32 bytes base64 encoded
+pub fn random_secret() -> String {
+    let mut secret = [0u8; 32];
+    rand::thread_rng().fill(&mut secret);
+    base64.encode(secret)
+}
+
 /// Exclude O and 0, lowercase letters
 fn is_usable_char(c: &char) -> bool {
     ('1'..='9').contains(c) ||
@@ -40,12 +50,108 @@ fn is_usable_char(c: &char) -> bool {
     ['@', '#', '$', '%'].contains(c)
 }
 
+/// Argon2 parameters of new password hashes, configured by `PORTFOLIO_ARGON2_ALGORITHM` (default `argon2id`),
+/// `PORTFOLIO_ARGON2_MEMORY` (KiB), `PORTFOLIO_ARGON2_ITERATIONS` and `PORTFOLIO_ARGON2_PARALLELISM`.
+/// Existing hashes are verified with the parameters encoded in them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PasswordHashConfig {
+    pub algorithm: argon2::Algorithm,
+    pub memory: u32,
+    pub iterations: u32,
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: argon2::Algorithm::Argon2id,
+            memory: 19456,
+            iterations: 2,
+            parallelism: 1,
+        }
+    }
+}
+
+impl PasswordHashConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<u32>().ok());
+
+        Self {
+            algorithm: std::env::var("PORTFOLIO_ARGON2_ALGORITHM").ok()
+                .and_then(|a| argon2::Algorithm::from_str(&a.to_lowercase()).ok())
+                .unwrap_or(default.algorithm),
+            memory: var("PORTFOLIO_ARGON2_MEMORY").unwrap_or(default.memory),
+            iterations: var("PORTFOLIO_ARGON2_ITERATIONS").unwrap_or(default.iterations),
+            parallelism: var("PORTFOLIO_ARGON2_PARALLELISM").unwrap_or(default.parallelism),
+        }
+    }
+
+    fn argon2(&self) -> Result<Argon2<'static>, ServiceError> {
+        Ok(Argon2::new(
+            self.algorithm,
+            argon2::Version::V0x13,
+            argon2::Params::new(self.memory, self.iterations, self.parallelism, None)?,
+        ))
+    }
+
+    /// Hash was created with other parameters than the configured ones and should be replaced
+    pub fn is_outdated(&self, hash: &str) -> bool {
+        let Ok(parsed) = argon2::PasswordHash::new(hash) else {
+            return false;
+        };
+        let Ok(params) = argon2::Params::try_from(&parsed) else {
+            return true;
+        };
+
+        parsed.algorithm.as_str() != self.algorithm.as_str()
+            || parsed.version != Some(argon2::Version::V0x13.into())
+            || params.m_cost() != self.memory
+            || params.t_cost() != self.iterations
+            || params.p_cost() != self.parallelism
+    }
+}
+
+/// Used only by debug builds without `PORTFOLIO_BLIND_INDEX_KEY`
+const DEV_BLIND_INDEX_KEY: &[u8] = b"portfolio development blind index key";
+
+/// Key of the blind indexes (HMAC-SHA256), configured by `PORTFOLIO_BLIND_INDEX_KEY`.
+/// Changing the key requires recomputing all indexes.
+#[derive(Clone)]
+pub struct BlindIndexKey(Vec<u8>);
+
+impl BlindIndexKey {
+    pub fn new(key: &[u8]) -> Self {
+        Self(key.to_vec())
+    }
+
+    pub fn from_env() -> Result<Self, ServiceError> {
+        match std::env::var("PORTFOLIO_BLIND_INDEX_KEY") {
+            Ok(key) if !key.is_empty() => Ok(Self::new(key.as_bytes())),
+            _ if cfg!(debug_assertions) => Ok(Self::new(DEV_BLIND_INDEX_KEY)),
+            _ => Err(ServiceError::MissingBlindIndexKey),
+        }
+    }
+
+    /// Equal values have equal indexes, the value can't be recovered from the index without the key
+    pub fn hash(&self, value: &str) -> String {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.0).expect("HMAC accepts keys of any length");
+        mac.update(value.as_bytes());
+        format!("{:x}", mac.finalize().into_bytes())
+    }
+
+    /// `736028/5163` and `7360285163` have the same index
+    pub fn personal_id_number(&self, personal_id_number: &str) -> String {
+        let normalized: String = personal_id_number.chars()
+            .filter(|c| !c.is_whitespace() && *c != '/')
+            .flat_map(char::to_uppercase)
+            .collect();
+        self.hash(&normalized)
+    }
+}
+
 pub async fn hash_password(password_plain_text: String) -> Result<String, ServiceError> {
-    let argon_config = Argon2::new(
-        argon2::Algorithm::Argon2i,
-        argon2::Version::V0x13,
-        argon2::Params::new(6000, 3, 10, None)?,
-    );
+    let argon_config = PasswordHashConfig::from_env().argon2()?;
 
     let hash = tokio::task::spawn_blocking(move || {
         let password = password_plain_text.as_bytes();
@@ -63,22 +169,18 @@ pub async fn hash_password(password_plain_text: String) -> Result<String, Servic
     Ok(hash_string)
 }
 
+/// Algorithm, version and parameters are taken from the hash, so hashes created with
+/// older configuration keep working
 pub async fn verify_password(
     password_plaint_text: String,
     hash: String,
 ) -> Result<bool, ServiceError> {
-    let argon_config = Argon2::new(
-        argon2::Algorithm::Argon2i,
-        argon2::Version::V0x13,
-        argon2::Params::new(6000, 3, 10, None)?,
-    );
-
     let result: Result<bool, argon2::password_hash::Error> =
         tokio::task::spawn_blocking(move || {
             let parsed_hash = argon2::PasswordHash::new(&hash);
             match parsed_hash {
                 Ok(parsed) => {
-                    return Ok(argon_config
+                    return Ok(Argon2::default()
                         .verify_password(password_plaint_text.as_bytes(), &parsed)
                         .is_ok())
                 }
@@ -90,6 +192,11 @@ pub async fn verify_password(
     Ok(result?)
 }
 
+/// Verified password should be hashed again with the current configuration
+pub fn password_needs_rehash(hash: &str) -> bool {
+    PasswordHashConfig::from_env().is_outdated(hash)
+}
+
 fn convert_key_aes256(key: &str) -> Vec<u8> {
     const REQUIRED_KEY_BYTES: usize = 32;
     //const REQUIRED_NONCE_BYTES: usize = 12;
@@ -107,30 +214,117 @@ fn convert_key_aes256(key: &str) -> Vec<u8> {
     key
 }
 
+/// Prefix of the versioned format `v2$` + base64(salt | nonce | ciphertext),
+/// the AES key is derived from the password with Argon2id and a random salt
+const ENVELOPE_V2_PREFIX: &str = "v2$";
+const ENVELOPE_SALT_LEN: usize = 16;
+const ENVELOPE_NONCE_LEN: usize = 12;
+
+/// Key derivation parameters are part of the `v2` format, changing them needs a new version
+fn envelope_v2_key(password: &str, salt: &[u8]) -> Result<[u8; 32], ServiceError> {
+    let argon = Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        argon2::Params::new(19456, 2, 1, Some(32))?,
+    );
+    let mut key = [0u8; 32];
+    argon.hash_password_into(password.as_bytes(), salt, &mut key)?;
+
+    Ok(key)
+}
+
+/// Ciphertext was created by the old format (key and nonce repeated from the password)
+/// and should be encrypted again
+pub fn is_legacy_ciphertext(password_cipher_text: &str) -> bool {
+    !password_cipher_text.starts_with(ENVELOPE_V2_PREFIX)
+}
+
 pub async fn encrypt_password(
     password_plain_text: String,
     key: String,
 ) -> Result<String, ServiceError> {
-    let hash = tokio::task::spawn_blocking(move || {
-        let aes_key_nonce = convert_key_aes256(&key);
+    let envelope = tokio::task::spawn_blocking(move || -> Result<Vec<u8>, ServiceError> {
+        let mut salt = [0u8; ENVELOPE_SALT_LEN];
+        let mut nonce = [0u8; ENVELOPE_NONCE_LEN];
+        rand::thread_rng().fill(&mut salt);
+        rand::thread_rng().fill(&mut nonce);
 
-        // Nonce should be always unique, but for our use case it's fine
-        // Also aes-gcm-siv is not vulnerable to nonce reuse
-        let nonce = aes_gcm_siv::Nonce::from_slice(&aes_key_nonce[..12]);
-
-        let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&aes_key_nonce[..32]).unwrap();
+        let aes_key = envelope_v2_key(&key, &salt)?;
+        let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&aes_key).unwrap();
+        let ciphertext = cipher.encrypt(aes_gcm_siv::Nonce::from_slice(&nonce), password_plain_text.as_bytes())?;
 
-        let res = cipher.encrypt(nonce, password_plain_text.as_bytes());
-        res
+        Ok([&salt[..], &nonce[..], &ciphertext[..]].concat())
     })
     .await??;
 
-    Ok(base64.encode(hash))
+    Ok(format!("{}{}", ENVELOPE_V2_PREFIX, base64.encode(envelope)))
 }
 
+/// Accepts both the `v2` envelope and the legacy format
 pub async fn decrypt_password(
     password_cipher_text: String,
     key: String,
+) -> Result<String, ServiceError> {
+    let Some(envelope) = password_cipher_text.strip_prefix(ENVELOPE_V2_PREFIX) else {
+        return decrypt_password_legacy(password_cipher_text, key).await;
+    };
+    let input = base64.decode(envelope)?;
+    if input.len() < ENVELOPE_SALT_LEN + ENVELOPE_NONCE_LEN {
+        return Err(ServiceError::CryptoDecryptFailed);
+    }
+
+    let plain = tokio::task::spawn_blocking(move || -> Result<Vec<u8>, ServiceError> {
+        let (salt, rest) = input.split_at(ENVELOPE_SALT_LEN);
+        let (nonce, ciphertext) = rest.split_at(ENVELOPE_NONCE_LEN);
+
+        let aes_key = envelope_v2_key(&key, salt)?;
+        let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&aes_key).unwrap();
+
+        Ok(cipher.decrypt(aes_gcm_siv::Nonce::from_slice(nonce), ciphertext)?)
+    })
+    .await??;
+
+    Ok(String::from_utf8(plain)?)
+}
+
+/// Encrypts with a secret from `random_secret`, which is already a full AES key,
+/// so the slow key derivation of `encrypt_password` is skipped
+pub async fn encrypt_with_secret(
+    plain_text: String,
+    secret: String,
+) -> Result<String, ServiceError> {
+    let aes_key = base64.decode(secret)?;
+    let mut nonce = [0u8; ENVELOPE_NONCE_LEN];
+    rand::thread_rng().fill(&mut nonce);
+
+    let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&aes_key)
+        .map_err(|_| ServiceError::CryptoEncryptFailed)?;
+    let ciphertext = cipher.encrypt(aes_gcm_siv::Nonce::from_slice(&nonce), plain_text.as_bytes())?;
+
+    Ok(base64.encode([&nonce[..], &ciphertext[..]].concat()))
+}
+
+pub async fn decrypt_with_secret(
+    cipher_text: String,
+    secret: String,
+) -> Result<String, ServiceError> {
+    let aes_key = base64.decode(secret)?;
+    let input = base64.decode(cipher_text)?;
+    if input.len() < ENVELOPE_NONCE_LEN {
+        return Err(ServiceError::CryptoDecryptFailed);
+    }
+    let (nonce, ciphertext) = input.split_at(ENVELOPE_NONCE_LEN);
+
+    let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&aes_key)
+        .map_err(|_| ServiceError::CryptoDecryptFailed)?;
+    let plain = cipher.decrypt(aes_gcm_siv::Nonce::from_slice(nonce), ciphertext)?;
+
+    Ok(String::from_utf8(plain)?)
+}
+
+async fn decrypt_password_legacy(
+    password_cipher_text: String,
+    key: String,
 ) -> Result<String, ServiceError> {
     let input = base64.decode(password_cipher_text)?;
     let plain = tokio::task::spawn_blocking(move || {
@@ -200,6 +394,11 @@ pub fn create_identity() -> (String, String) {
     )
 }
 
+/// Returns true if the string is a valid age x25519 public key
+pub fn is_valid_public_key(public_key: &str) -> bool {
+    age::x25519::Recipient::from_str(public_key).is_ok()
+}
+
 pub async fn encrypt_buffer_with_recipients(
     input_buffer: &[u8],
     recipients: &Vec<String>,
@@ -213,37 +412,59 @@ pub async fn encrypt_buffer_with_recipients(
     Ok(output_buffer)
 }
 
-async fn age_encrypt_with_recipients<W: tokio::io::AsyncWrite + Unpin>(
-    input_buffer: &[u8],
-    output_buffer: &mut W,
+/// Wraps the output into an age encryptor, everything written to the returned writer
+/// is encrypted for the recipients. The writer has to be closed to finish the stream.
+pub async fn encrypt_writer_with_recipients<W: tokio::io::AsyncWrite + Unpin>(
+    output: W,
     recipients: &Vec<&str>,
-) -> Result<(), ServiceError> {
+) -> Result<age::stream::StreamWriter<Compat<W>>, ServiceError> {
     let public_keys = recipients
         .into_iter()
         .map(|recipient| {
-            //TODO: No unwrap
-            println!("recipient is {:?}", recipient.clone());
-            Box::new(age::x25519::Recipient::from_str(recipient).unwrap()) as _
+            age::x25519::Recipient::from_str(recipient)
+                .map(|r| Box::new(r) as Box<dyn age::Recipient + Send>)
+                .map_err(|e| ServiceError::AgeKeyError(e.to_string()))
         })
-        .collect();
+        .collect::<Result<Vec<_>, ServiceError>>()?;
 
-    let encryptor_option = age::Encryptor::with_recipients(public_keys);
+    let encryptor = age::Encryptor::with_recipients(public_keys)
+        .ok_or(ServiceError::AgeNoRecipientsError)?;
 
-    if let Some(encryptor) = encryptor_option {
-        let mut encrypt_writer = encryptor
-            .wrap_async_output(output_buffer.compat_mut())
-            .await?;
+    Ok(encryptor.wrap_async_output(output.compat()).await?)
+}
 
-        encrypt_writer.write_all(input_buffer).await?;
+/// Wraps the input into an age decryptor, plain text is read from the returned reader in chunks
+pub async fn decrypt_reader_with_private_key<R: tokio::io::AsyncRead + Unpin>(
+    input: R,
+    key: &str,
+) -> Result<age::stream::StreamReader<Compat<R>>, ServiceError> {
+    let decryptor = match age::Decryptor::new_async(input.compat()).await? {
+        age::Decryptor::Recipients(d) => d,
+        _ => return Err(ServiceError::CryptoDecryptFailed),
+    };
 
-        encrypt_writer.flush().await?;
+    let decrypt_reader = decryptor.decrypt_async(iter::once(
+        &age::x25519::Identity::from_str(key)
+            .map_err(|e| ServiceError::AgeKeyError(e.to_string()))? as &dyn age::Identity,
+    ))?;
 
-        encrypt_writer.close().await?;
+    Ok(decrypt_reader)
+}
 
-        return Ok(());
-    } else {
-        return Err(ServiceError::AgeNoRecipientsError);
-    }
+async fn age_encrypt_with_recipients<W: tokio::io::AsyncWrite + Unpin>(
+    input_buffer: &[u8],
+    output_buffer: &mut W,
+    recipients: &Vec<&str>,
+) -> Result<(), ServiceError> {
+    let mut encrypt_writer = encrypt_writer_with_recipients(output_buffer, recipients).await?;
+
+    encrypt_writer.write_all(input_buffer).await?;
+
+    encrypt_writer.flush().await?;
+
+    encrypt_writer.close().await?;
+
+    Ok(())
 }
 
 async fn age_decrypt_with_private_key<R: tokio::io::AsyncRead + Unpin>(
@@ -251,17 +472,9 @@ async fn age_decrypt_with_private_key<R: tokio::io::AsyncRead + Unpin>(
     output_buffer: &mut Vec<u8>,
     key: &str,
 ) -> Result<(), ServiceError> {
-    let decryptor = match age::Decryptor::new_async(input_buffer.compat()).await? {
-        age::Decryptor::Recipients(d) => d,
-        _ => unreachable!(),
-    };
+    let mut decrypt_reader = decrypt_reader_with_private_key(input_buffer, key).await?;
 
-    let mut decrypt_writer = decryptor.decrypt_async(iter::once(
-        &age::x25519::Identity::from_str(key)
-            .map_err(|e| ServiceError::AgeKeyError(e.to_string()))? as &dyn age::Identity,
-    ))?;
-
-    decrypt_writer.read_to_end(output_buffer).await?;
+    decrypt_reader.read_to_end(output_buffer).await?;
 
     Ok(())
 }
@@ -302,20 +515,13 @@ pub async fn encrypt_file_with_recipients<P: AsRef<Path>>(
     recipients: Vec<&str>,
 ) -> Result<(), ServiceError> {
     let mut cipher_file = tokio::fs::File::create(cipher_file_path).await?;
-    let mut plain_file = tokio::fs::File::open(plain_file_path).await?;
-
-    let mut plain_file_contents = Vec::new();
+    let plain_file = tokio::fs::File::open(plain_file_path).await?;
 
-    tokio::io::AsyncReadExt::read_to_end(&mut plain_file, &mut plain_file_contents).await?;
+    let mut encrypt_writer = encrypt_writer_with_recipients(&mut cipher_file, &recipients).await?;
 
-    drop(plain_file);
+    futures::io::copy(plain_file.compat(), &mut encrypt_writer).await?;
 
-    age_encrypt_with_recipients(
-        plain_file_contents.as_slice(),
-        &mut cipher_file,
-        &recipients,
-    )
-    .await?;
+    encrypt_writer.close().await?;
 
     tokio::io::AsyncWriteExt::shutdown(&mut cipher_file).await?;
 
@@ -330,15 +536,58 @@ pub async fn decrypt_file_with_private_key<P: AsRef<Path>>(
     let cipher_file = tokio::fs::File::open(cipher_file_path).await?;
     let mut plain_file = tokio::fs::File::create(plain_file_path).await?;
 
-    let mut plain_file_contents = Vec::new();
+    let decrypt_reader = decrypt_reader_with_private_key(cipher_file, key).await?;
 
-    age_decrypt_with_private_key(cipher_file, &mut plain_file_contents, key).await?;
+    futures::io::copy(decrypt_reader, &mut plain_file.compat_mut()).await?;
 
-    tokio::io::AsyncWriteExt::write_all(&mut plain_file, plain_file_contents.as_slice()).await?;
+    tokio::io::AsyncWriteExt::shutdown(&mut plain_file).await?;
 
     Ok(())
 }
 
+/// Returns a reader which decrypts the file on the fly, so the whole file is never kept in memory
+pub async fn decrypt_file_with_private_key_as_reader<P: AsRef<Path>>(
+    cipher_file_path: P,
+    key: &str,
+) -> Result<Pin<Box<dyn tokio::io::AsyncRead + Send>>, ServiceError> {
+    let cipher_file = tokio::fs::File::open(cipher_file_path).await?;
+
+    let decrypt_reader = decrypt_reader_with_private_key(cipher_file, key).await?;
+
+    Ok(Box::pin(decrypt_reader.compat()))
+}
+
+/// Decrypts the input and encrypts it again for new recipients chunk by chunk
+pub async fn reencrypt_with_private_key<R: tokio::io::AsyncRead + Unpin, W: tokio::io::AsyncWrite + Unpin>(
+    input: R,
+    output: &mut W,
+    key: &str,
+    recipients: Vec<&str>,
+) -> Result<(), ServiceError> {
+    let decrypt_reader = decrypt_reader_with_private_key(input, key).await?;
+    let mut encrypt_writer = encrypt_writer_with_recipients(&mut *output, &recipients).await?;
+
+    futures::io::copy(decrypt_reader, &mut encrypt_writer).await?;
+
+    encrypt_writer.close().await?;
+
+    tokio::io::AsyncWriteExt::shutdown(output).await?;
+
+    Ok(())
+}
+
+pub async fn reencrypt_file_with_private_key<P: AsRef<Path>>(
+    cipher_file_path: P,
+    new_cipher_file_path: P,
+    key: &str,
+    recipients: Vec<&str>,
+) -> Result<(), ServiceError> {
+    let cipher_file = tokio::fs::File::open(cipher_file_path).await?;
+    let mut new_cipher_file = tokio::fs::File::create(new_cipher_file_path).await?;
+
+    reencrypt_with_private_key(cipher_file, &mut new_cipher_file, key, recipients).await
+}
+
 pub async fn decrypt_file_with_private_key_as_buffer<P: AsRef<Path>>(
     cipher_file_path: P,
     key: &str,
@@ -369,6 +618,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_blind_index() {
+        let key = super::BlindIndexKey::new(b"key");
+
+        assert_eq!(key.hash("7360285163").len(), 64);
+        assert_eq!(key.personal_id_number("736028/5163"), key.personal_id_number(" 7360285163"));
+        assert_ne!(key.personal_id_number("7360285163"), key.personal_id_number("7360285164"));
+        // indexes made with another key don't match
+        assert_ne!(key.hash("7360285163"), super::BlindIndexKey::new(b"other").hash("7360285163"));
+    }
+
     #[tokio::test]
     async fn test_hash_password() {
         const PASSWORD: &str = "test";
@@ -389,6 +649,22 @@ mod tests {
         assert!(result);
     }
 
+    #[tokio::test]
+    async fn test_rehash_outdated_password() {
+        const LEGACY_HASH: &str = "$argon2i$v=19$m=6000,t=3,p=10$WE9xCQmmWdBK82R4SEjoqA$TZSc6PuLd4aWK2x2WAb+Lm9sLySqjK3KLbNyqyQmzPQ";
+        let config = super::PasswordHashConfig::default();
+
+        assert!(config.is_outdated(LEGACY_HASH));
+        assert!(super::verify_password("test".to_string(), LEGACY_HASH.to_string()).await.unwrap());
+
+        let hash = super::hash_password("test".to_string()).await.unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=19456,t=2,p=1$"));
+        assert!(!config.is_outdated(&hash));
+
+        let stronger = super::PasswordHashConfig { iterations: 3, ..config };
+        assert!(stronger.is_outdated(&hash));
+    }
+
     #[tokio::test]
     async fn test_hash_and_verify_password() {
         const PASSWORD: &str = "test";
@@ -425,7 +701,51 @@ mod tests {
             .await
             .unwrap();
 
-        assert!(base64.decode(encrypted).is_ok());
+        let envelope = encrypted.strip_prefix("v2$").unwrap();
+        assert!(base64.decode(envelope).is_ok());
+        assert!(!super::is_legacy_ciphertext(&encrypted));
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_password_uses_random_salt_and_nonce() {
+        const PASSWORD: &str = "test";
+        const KEY: &str = "test";
+
+        let encrypted_1 = super::encrypt_password(PASSWORD.to_string(), KEY.to_string()).await.unwrap();
+        let encrypted_2 = super::encrypt_password(PASSWORD.to_string(), KEY.to_string()).await.unwrap();
+        assert_ne!(encrypted_1, encrypted_2);
+
+        // keys longer than 32 bytes are not truncated
+        let long_key = "a".repeat(40);
+        let encrypted = super::encrypt_password(PASSWORD.to_string(), long_key).await.unwrap();
+        assert!(super::decrypt_password(encrypted, "a".repeat(32)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_encrypt_decrypt_with_secret() {
+        const PLAIN: &str = "AGE-SECRET-KEY-1";
+        let secret = super::random_secret();
+
+        let encrypted = super::encrypt_with_secret(PLAIN.to_string(), secret.to_owned()).await.unwrap();
+        assert_eq!(super::decrypt_with_secret(encrypted.to_owned(), secret).await.unwrap(), PLAIN);
+        assert!(super::decrypt_with_secret(encrypted, super::random_secret()).await.is_err());
+        assert!(super::encrypt_with_secret(PLAIN.to_string(), base64.encode("short")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decrypt_legacy_password() {
+        use aes_gcm_siv::{aead::Aead, KeyInit};
+        const PASSWORD: &str = "test";
+        const KEY: &str = "test";
+
+        let aes_key_nonce = super::convert_key_aes256(KEY);
+        let cipher = aes_gcm_siv::Aes256GcmSiv::new_from_slice(&aes_key_nonce[..32]).unwrap();
+        let legacy = base64.encode(
+            cipher.encrypt(aes_gcm_siv::Nonce::from_slice(&aes_key_nonce[..12]), PASSWORD.as_bytes()).unwrap()
+        );
+
+        assert!(super::is_legacy_ciphertext(&legacy));
+        assert_eq!(super::decrypt_password(legacy, KEY.to_string()).await.unwrap(), PASSWORD);
     }
 
     #[tokio::test]
@@ -640,4 +960,88 @@ mod tests {
             PASSWORD
         );
     }
+
+    #[tokio::test]
+    async fn test_decrypt_file_with_private_key_as_reader() {
+        const PUBLIC_KEY: &str = "age1t220v5c8ye0pjx99kw8nr57y7a5qlw4ke0wchjuxnr2gcvfzt3hq7fufz0";
+        const PRIVATE_KEY: &str =
+            "AGE-SECRET-KEY-1WPDHL2FLJ23T6RK5KCX8KS8DNLX0CGXMNZG0XNUAH4QP5C8ZZ46QGD3STV";
+
+        // larger than a single age chunk (64 KiB)
+        let plain_buffer: Vec<u8> = (0..200_000).map(|i| (i % 251) as u8).collect();
+
+        let mut plain_file = async_tempfile::TempFile::new().await.unwrap();
+        let encrypted_file = async_tempfile::TempFile::new().await.unwrap();
+
+        tokio::io::AsyncWriteExt::write_all(&mut plain_file, &plain_buffer)
+            .await
+            .unwrap();
+
+        super::encrypt_file_with_recipients(
+            &plain_file.file_path(),
+            &encrypted_file.file_path(),
+            vec![PUBLIC_KEY],
+        )
+        .await
+        .unwrap();
+
+        let mut reader = super::decrypt_file_with_private_key_as_reader(encrypted_file.file_path(), PRIVATE_KEY)
+            .await
+            .unwrap();
+
+        let mut decrypted_buffer = vec![];
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut decrypted_buffer)
+            .await
+            .unwrap();
+
+        assert_eq!(plain_buffer, decrypted_buffer);
+    }
+
+    #[tokio::test]
+    async fn test_reencrypt_file_with_private_key() {
+        const PUBLIC_KEY: &str = "age1t220v5c8ye0pjx99kw8nr57y7a5qlw4ke0wchjuxnr2gcvfzt3hq7fufz0";
+        const PRIVATE_KEY: &str =
+            "AGE-SECRET-KEY-1WPDHL2FLJ23T6RK5KCX8KS8DNLX0CGXMNZG0XNUAH4QP5C8ZZ46QGD3STV";
+
+        const PASSWORD: &str = "test";
+
+        let (new_public_key, new_private_key) = super::create_identity();
+
+        let mut plain_file = async_tempfile::TempFile::new().await.unwrap();
+        let encrypted_file = async_tempfile::TempFile::new().await.unwrap();
+        let reencrypted_file = async_tempfile::TempFile::new().await.unwrap();
+
+        tokio::io::AsyncWriteExt::write_all(&mut plain_file, PASSWORD.as_bytes())
+            .await
+            .unwrap();
+
+        super::encrypt_file_with_recipients(
+            &plain_file.file_path(),
+            &encrypted_file.file_path(),
+            vec![PUBLIC_KEY],
+        )
+        .await
+        .unwrap();
+
+        super::reencrypt_file_with_private_key(
+            &encrypted_file.file_path(),
+            &reencrypted_file.file_path(),
+            PRIVATE_KEY,
+            vec![new_public_key.as_str()],
+        )
+        .await
+        .unwrap();
+
+        let decrypted_buffer =
+            super::decrypt_file_with_private_key_as_buffer(reencrypted_file.file_path(), &new_private_key)
+                .await
+                .unwrap();
+
+        assert_eq!(String::from_utf8(decrypted_buffer).unwrap(), PASSWORD);
+        assert!(
+            super::decrypt_file_with_private_key_as_buffer(reencrypted_file.file_path(), PRIVATE_KEY)
+                .await
+                .is_err()
+        );
+    }
 }
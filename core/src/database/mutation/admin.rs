@@ -0,0 +1,213 @@
+use ::entity::admin;
+use chrono::NaiveDateTime;
+use log::{info, warn};
+use sea_orm::*;
+
+use crate::{Mutation, models::admin::AdminRole};
+
+impl Mutation {
+    pub async fn insert_admin(
+        db: &DbConn,
+        name: String,
+        public_key: String,
+        priv_key_enc: String,
+        password_hash: String,
+        role: AdminRole,
+        key_pending_at: Option<NaiveDateTime>,
+    ) -> Result<admin::Model, DbErr> {
+        let admin = admin::ActiveModel {
+            name: Set(name),
+            public_key: Set(public_key),
+            private_key: Set(priv_key_enc),
+            password: Set(password_hash),
+            role: Set(role.as_str().to_string()),
+            key_pending_at: Set(key_pending_at),
+            created_at: Set(chrono::offset::Local::now().naive_local()),
+            updated_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await?;
+
+        warn!("ADMIN {} CREATED", admin.id);
+        Ok(admin)
+    }
+
+    pub async fn update_admin_disabled_at(
+        db: &DbConn,
+        admin: admin::Model,
+        disabled_at: Option<NaiveDateTime>,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.disabled_at = Set(disabled_at);
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        warn!("ADMIN {} {}", admin_id, if disabled_at.is_some() { "DISABLED" } else { "ENABLED" });
+        Ok(update)
+    }
+
+    pub async fn update_admin_key_retired_at(
+        db: &DbConn,
+        admin: admin::Model,
+        key_retired_at: NaiveDateTime,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.key_retired_at = Set(Some(key_retired_at));
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        warn!("ADMIN {} KEY RETIRED", admin_id);
+        Ok(update)
+    }
+
+    pub async fn update_admin_key_pending_at(
+        db: &DbConn,
+        admin: admin::Model,
+        key_pending_at: Option<NaiveDateTime>,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.key_pending_at = Set(key_pending_at);
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        warn!("ADMIN {} KEY ACTIVATED", admin_id);
+        Ok(update)
+    }
+
+    /// `fields_of_study` is a JSON list of field codes, used only by reviewers
+    pub async fn update_admin_role(
+        db: &DbConn,
+        admin: admin::Model,
+        role: AdminRole,
+        fields_of_study: Option<String>,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.role = Set(role.as_str().to_string());
+        admin.fields_of_study = Set(fields_of_study);
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        warn!("ADMIN {} ROLE SET TO {}", admin_id, role.as_str());
+        Ok(update)
+    }
+
+    pub async fn delete_admin(db: &DbConn, admin: admin::Model) -> Result<DeleteResult, DbErr> {
+        let admin_id = admin.id;
+        let delete = admin.delete(db).await?;
+
+        warn!("ADMIN {} DELETED", admin_id);
+        Ok(delete)
+    }
+
+    /// Same password hashed with the current Argon2 parameters, the private key stays as it is
+    pub async fn update_admin_password_hash(
+        db: &DbConn,
+        admin: admin::Model,
+        password_hash: String,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.password = Set(password_hash);
+
+        let update = admin.update(db).await?;
+
+        info!("ADMIN {} PASSWORD REHASHED", admin_id);
+        Ok(update)
+    }
+
+    /// Same private key encrypted again with the current format
+    pub async fn update_admin_private_key(
+        db: &DbConn,
+        admin: admin::Model,
+        priv_key_enc: String,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.private_key = Set(priv_key_enc);
+
+        let update = admin.update(db).await?;
+
+        info!("ADMIN {} PRIVATE KEY UPGRADED", admin_id);
+        Ok(update)
+    }
+
+    /// Stores a new, not yet confirmed second factor secret
+    pub async fn update_admin_totp_secret(
+        db: &DbConn,
+        admin: admin::Model,
+        enc_secret: String,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.totp_secret = Set(Some(enc_secret));
+        admin.totp_recovery_codes = Set(None);
+        admin.totp_enabled_at = Set(None);
+        admin.totp_last_step = Set(None);
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        info!("ADMIN {} SECOND FACTOR ENROLMENT STARTED", admin_id);
+        Ok(update)
+    }
+
+    pub async fn enable_admin_totp(
+        db: &DbConn,
+        admin: admin::Model,
+        recovery_codes: String,
+        step: i64,
+        enabled_at: NaiveDateTime,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.totp_recovery_codes = Set(Some(recovery_codes));
+        admin.totp_enabled_at = Set(Some(enabled_at));
+        admin.totp_last_step = Set(Some(step));
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        info!("ADMIN {} SECOND FACTOR ENABLED", admin_id);
+        Ok(update)
+    }
+
+    pub async fn update_admin_totp_usage(
+        db: &DbConn,
+        admin: admin::Model,
+        recovery_codes: Option<String>,
+        last_step: Option<i64>,
+    ) -> Result<admin::Model, DbErr> {
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.totp_recovery_codes = Set(recovery_codes);
+        admin.totp_last_step = Set(last_step);
+
+        admin.update(db).await
+    }
+
+    pub async fn reset_admin_totp(
+        db: &DbConn,
+        admin: admin::Model,
+    ) -> Result<admin::Model, DbErr> {
+        let admin_id = admin.id;
+        let mut admin: admin::ActiveModel = admin.into();
+        admin.totp_secret = Set(None);
+        admin.totp_recovery_codes = Set(None);
+        admin.totp_enabled_at = Set(None);
+        admin.totp_last_step = Set(None);
+        admin.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        let update = admin.update(db).await?;
+
+        warn!("ADMIN {} SECOND FACTOR RESET", admin_id);
+        Ok(update)
+    }
+}
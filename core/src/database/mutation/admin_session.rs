@@ -1,6 +1,6 @@
 use chrono::{Utc, Duration};
 use entity::{admin_session};
-use sea_orm::{DbConn, prelude::Uuid, DbErr, Set, ActiveModelTrait};
+use sea_orm::{DbConn, prelude::Uuid, DbErr, Set, ActiveModelTrait, IntoActiveModel};
 
 use crate::Mutation;
 
@@ -10,12 +10,17 @@ impl Mutation {
         admin_id: i32,
         random_uuid: Uuid,
         ip_addr: String,
+        user_agent: Option<String>,
+        key_slot: String,
     ) -> Result<admin_session::Model, DbErr> {
         admin_session::ActiveModel {
             id: Set(random_uuid),
             admin_id: Set(admin_id),
             ip_address: Set(ip_addr),
+            user_agent: Set(user_agent),
+            key_slot: Set(Some(key_slot)),
             created_at: Set(Utc::now().naive_local()),
+            last_seen_at: Set(Some(Utc::now().naive_local())),
             expires_at: Set(Utc::now()
                 .naive_local()
                 .checked_add_signed(Duration::days(1))
@@ -26,6 +31,16 @@ impl Mutation {
         .await
     }
 
+    pub async fn update_admin_session_last_seen(
+        db: &DbConn,
+        session: admin_session::Model,
+    ) -> Result<admin_session::Model, DbErr> {
+        let mut session = session.into_active_model();
+        session.last_seen_at = Set(Some(Utc::now().naive_local()));
+
+        session.update(db).await
+    }
+
     /* pub async fn update_session_expiration(db: &DbConn, 
         session: session::Model, 
         expires_at: NaiveDateTime,
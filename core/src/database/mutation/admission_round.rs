@@ -0,0 +1,38 @@
+use entity::admission_round;
+use log::warn;
+use sea_orm::{DbConn, DbErr, ActiveModelTrait, DeleteResult, ModelTrait};
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn insert_admission_round(
+        db: &DbConn,
+        round: admission_round::ActiveModel,
+    ) -> Result<admission_round::Model, DbErr> {
+        let insert = round.insert(db).await?;
+
+        warn!("ADMISSION ROUND {} ({}) CREATED", insert.id, insert.date);
+        Ok(insert)
+    }
+
+    pub async fn update_admission_round(
+        db: &DbConn,
+        round: admission_round::ActiveModel,
+    ) -> Result<admission_round::Model, DbErr> {
+        let update = round.update(db).await?;
+
+        warn!("ADMISSION ROUND {} ({}) UPDATED", update.id, update.date);
+        Ok(update)
+    }
+
+    pub async fn delete_admission_round(
+        db: &DbConn,
+        round: admission_round::Model,
+    ) -> Result<DeleteResult, DbErr> {
+        let round_id = round.id;
+        let delete = round.delete(db).await?;
+
+        warn!("ADMISSION ROUND {} DELETED", round_id);
+        Ok(delete)
+    }
+}
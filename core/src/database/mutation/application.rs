@@ -1,8 +1,8 @@
 use ::entity::application;
 use log::{info, warn};
-use sea_orm::{DbConn, DbErr, Set, ActiveModelTrait, IntoActiveModel, DeleteResult, ModelTrait};
+use sea_orm::{ConnectionTrait, DbConn, DbErr, Set, ActiveModelTrait, IntoActiveModel, DeleteResult, ModelTrait};
 
-use crate::{Mutation, models::candidate::FieldOfStudy};
+use crate::{Mutation, Query};
 
 impl Mutation {
     pub async fn create_application(
@@ -14,10 +14,12 @@ impl Mutation {
         pubkey: String,
         encrypted_priv_key: String,
     ) -> Result<application::Model, DbErr> {
-        let field_of_study = FieldOfStudy::from(application_id);
+        let field_of_study = Query::find_field_of_study_by_application_id(db, application_id)
+            .await?
+            .ok_or(DbErr::RecordNotFound(format!("Field of study for application {}", application_id)))?;
         let insert = application::ActiveModel {
             id: Set(application_id),
-            field_of_study: Set(field_of_study.into()),
+            field_of_study: Set(field_of_study.code),
             personal_id_number: Set(enc_personal_id_number),
             password: Set(hashed_password),
             candidate_id: Set(candidate_id),
@@ -62,4 +64,65 @@ impl Mutation {
         warn!("CANDIDATE {} PASSWORD CHANGED", application_id);
         Ok(update)
     }
-}
\ No newline at end of file
+
+    pub async fn update_application_password(
+        db: &DbConn,
+        application: application::Model,
+        new_password_hash: String,
+        priv_key_enc: String,
+    ) -> Result<application::Model, DbErr> {
+        let application_id = application.id;
+        let mut application = application.into_active_model();
+        application.password = Set(new_password_hash);
+        application.private_key = Set(priv_key_enc);
+
+        let update = application.update(db).await?;
+
+        warn!("CANDIDATE {} PASSWORD CHANGED BY CANDIDATE", application_id);
+        Ok(update)
+    }
+
+    /// Same password hashed with the current Argon2 parameters, the private key stays as it is
+    pub async fn update_application_password_hash(
+        db: &DbConn,
+        application: application::Model,
+        password_hash: String,
+    ) -> Result<application::Model, DbErr> {
+        let application_id = application.id;
+        let mut application = application.into_active_model();
+        application.password = Set(password_hash);
+
+        let update = application.update(db).await?;
+
+        info!("CANDIDATE {} PASSWORD REHASHED", application_id);
+        Ok(update)
+    }
+
+    /// Same private key encrypted again with the current format
+    pub async fn update_application_private_key(
+        db: &DbConn,
+        application: application::Model,
+        priv_key_enc: String,
+    ) -> Result<application::Model, DbErr> {
+        let application_id = application.id;
+        let mut application = application.into_active_model();
+        application.private_key = Set(priv_key_enc);
+
+        let update = application.update(db).await?;
+
+        info!("CANDIDATE {} PRIVATE KEY UPGRADED", application_id);
+        Ok(update)
+    }
+
+    pub async fn update_application_personal_id<C: ConnectionTrait>(
+        db: &C,
+        application: application::Model,
+        enc_personal_id_number: String,
+    ) -> Result<application::Model, DbErr> {
+        let mut application = application.into_active_model();
+        application.personal_id_number = Set(enc_personal_id_number);
+        application.updated_at = Set(chrono::offset::Local::now().naive_local());
+
+        application.update(db).await
+    }
+}
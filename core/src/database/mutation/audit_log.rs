@@ -0,0 +1,14 @@
+use entity::audit_log;
+use sea_orm::{ConnectionTrait, DbErr, ActiveModelTrait};
+
+use crate::Mutation;
+
+impl Mutation {
+    /// Audit log is append only, there are no update or delete mutations
+    pub async fn insert_audit_log<C: ConnectionTrait>(
+        db: &C,
+        entry: audit_log::ActiveModel,
+    ) -> Result<audit_log::Model, DbErr> {
+        entry.insert(db).await
+    }
+}
@@ -8,9 +8,11 @@ impl Mutation {
     pub async fn create_candidate(
         db: &DbConn,
         enc_personal_id_number: String,
+        personal_id_index: Option<String>,
     ) -> Result<candidate::Model, DbErr> {
         let candidate = candidate::ActiveModel {
             personal_identification_number: Set(enc_personal_id_number),
+            personal_id_index: Set(personal_id_index),
             created_at: Set(chrono::offset::Local::now().naive_local()),
             updated_at: Set(chrono::offset::Local::now().naive_local()),
             ..Default::default()
@@ -33,8 +35,8 @@ impl Mutation {
         Ok(delete)
     }
 
-    pub async fn update_candidate_opt_details(
-        db: &DbConn,
+    pub async fn update_candidate_opt_details<C: ConnectionTrait>(
+        db: &C,
         candidate: candidate::Model,
         enc_candidate: EncryptedCandidateDetails,
         encrypted_by_id: i32,
@@ -70,9 +72,24 @@ impl Mutation {
         Ok(update)
     }
 
-    pub async fn update_personal_id(
+    pub async fn update_candidate_deadline_extension(
         db: &DbConn,
         candidate: candidate::Model,
+        until: Option<chrono::NaiveDateTime>,
+    ) -> Result<candidate::Model, DbErr> {
+        let candidate_id = candidate.id;
+        let mut candidate: candidate::ActiveModel = candidate.into();
+        candidate.deadline_extension = Set(until);
+
+        let update = candidate.update(db).await?;
+
+        warn!("CANDIDATE {} DEADLINE EXTENDED UNTIL {:?}", candidate_id, until);
+        Ok(update)
+    }
+
+    pub async fn update_personal_id<C: ConnectionTrait>(
+        db: &C,
+        candidate: candidate::Model,
         personal_id: &str,
     ) -> Result<candidate::Model, DbErr> {
         let mut candidate = candidate.into_active_model();
@@ -83,6 +100,19 @@ impl Mutation {
             .await
 
     }
+
+    pub async fn update_personal_id_index(
+        db: &DbConn,
+        candidate: candidate::Model,
+        personal_id_index: String,
+    ) -> Result<candidate::Model, DbErr> {
+        let mut candidate = candidate.into_active_model();
+        candidate.personal_id_index = Set(Some(personal_id_index));
+
+        candidate
+            .update(db)
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -99,6 +129,7 @@ mod tests {
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
         )
         .await
         .unwrap();
@@ -116,6 +147,7 @@ mod tests {
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
         )
         .await
         .unwrap();
@@ -0,0 +1,27 @@
+use entity::field_of_study;
+use log::warn;
+use sea_orm::{DbConn, DbErr, ActiveModelTrait};
+
+use crate::Mutation;
+
+impl Mutation {
+    pub async fn insert_field_of_study(
+        db: &DbConn,
+        field: field_of_study::ActiveModel,
+    ) -> Result<field_of_study::Model, DbErr> {
+        let insert = field.insert(db).await?;
+
+        warn!("FIELD OF STUDY {} ({}) CREATED", insert.prefix, insert.code);
+        Ok(insert)
+    }
+
+    pub async fn update_field_of_study(
+        db: &DbConn,
+        field: field_of_study::ActiveModel,
+    ) -> Result<field_of_study::Model, DbErr> {
+        let update = field.update(db).await?;
+
+        warn!("FIELD OF STUDY {} ({}) UPDATED", update.prefix, update.code);
+        Ok(update)
+    }
+}
@@ -0,0 +1,70 @@
+use chrono::NaiveDateTime;
+use entity::login_throttle;
+use log::warn;
+use sea_orm::*;
+use sea_orm::sea_query::{Expr, OnConflict};
+
+use crate::Mutation;
+
+impl Mutation {
+    /// Counts one failure in a single statement, so concurrent failures are never lost.
+    /// Failures of a window started before `window_start_limit` are forgotten first.
+    pub async fn increment_login_throttle(
+        db: &DbConn,
+        key: String,
+        now: NaiveDateTime,
+        window_start_limit: NaiveDateTime,
+    ) -> Result<login_throttle::Model, DbErr> {
+        login_throttle::Entity::update_many()
+            .col_expr(login_throttle::Column::Failures, Expr::value(0))
+            .col_expr(login_throttle::Column::WindowStart, Expr::value(now))
+            .filter(login_throttle::Column::Key.eq(key.to_owned()))
+            .filter(login_throttle::Column::WindowStart.lt(window_start_limit))
+            .exec(db)
+            .await?;
+
+        login_throttle::Entity::insert(login_throttle::ActiveModel {
+            key: Set(key.to_owned()),
+            failures: Set(1),
+            window_start: Set(now),
+            locked_until: Set(None),
+            updated_at: Set(now),
+        })
+            .on_conflict(
+                OnConflict::column(login_throttle::Column::Key)
+                    .value(
+                        login_throttle::Column::Failures,
+                        Expr::col((login_throttle::Entity, login_throttle::Column::Failures)).add(1),
+                    )
+                    .update_column(login_throttle::Column::UpdatedAt)
+                    .to_owned()
+            )
+            .exec(db)
+            .await?;
+
+        login_throttle::Entity::find_by_id(key)
+            .one(db)
+            .await?
+            .ok_or(DbErr::RecordNotFound("login_throttle".to_string()))
+    }
+
+    pub async fn lock_login_throttle(
+        db: &DbConn,
+        throttle: login_throttle::Model,
+        locked_until: NaiveDateTime,
+    ) -> Result<login_throttle::Model, DbErr> {
+        let mut throttle: login_throttle::ActiveModel = throttle.into();
+        throttle.locked_until = Set(Some(locked_until));
+        let throttle = throttle.update(db).await?;
+
+        warn!("LOGIN {} LOCKED OUT UNTIL {} AFTER {} FAILURES", throttle.key, locked_until, throttle.failures);
+        Ok(throttle)
+    }
+
+    pub async fn delete_login_throttle(
+        db: &DbConn,
+        throttle: login_throttle::Model,
+    ) -> Result<DeleteResult, DbErr> {
+        throttle.delete(db).await
+    }
+}
@@ -4,4 +4,9 @@ pub mod application;
 pub mod session;
 pub mod candidate;
 pub mod parent;
-pub mod admin_session;
\ No newline at end of file
+pub mod admin_session;
+pub mod audit_log;
+pub mod field_of_study;
+pub mod admission_round;
+pub mod admin;
+pub mod login_throttle;
\ No newline at end of file
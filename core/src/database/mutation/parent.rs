@@ -21,8 +21,8 @@ impl Mutation {
             .await
     }
 
-    pub async fn add_parent_details(
-        db: &DbConn,
+    pub async fn add_parent_details<C: ConnectionTrait>(
+        db: &C,
         parent: Model,
         enc_parent: EncryptedParentDetails,
     ) -> Result<Model, sea_orm::DbErr> {
@@ -52,6 +52,7 @@ mod tests {
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
         )
         .await
         .unwrap();
@@ -69,6 +70,7 @@ mod tests {
         let candidate = Mutation::create_candidate(
             &db,
             "".to_string(),
+            None,
         )
         .await
         .unwrap();
@@ -11,12 +11,17 @@ impl Mutation {
         random_uuid: Uuid,
         candidate_id: i32,
         ip_addr: String,
+        user_agent: Option<String>,
+        key_slot: String,
     ) -> Result<session::Model, DbErr> {
         session::ActiveModel {
             id: Set(random_uuid),
             candidate_id: Set(candidate_id),
             ip_address: Set(ip_addr),
+            user_agent: Set(user_agent),
+            key_slot: Set(Some(key_slot)),
             created_at: Set(Utc::now().naive_local()),
+            last_seen_at: Set(Some(Utc::now().naive_local())),
             expires_at: Set(Utc::now()
                 .naive_local()
                 .checked_add_signed(Duration::days(14))
@@ -27,6 +32,16 @@ impl Mutation {
         .await
     }
 
+    pub async fn update_session_last_seen(
+        db: &DbConn,
+        session: session::Model,
+    ) -> Result<session::Model, DbErr> {
+        let mut session = session.into_active_model();
+        session.last_seen_at = Set(Some(Utc::now().naive_local()));
+
+        session.update(db).await
+    }
+
     pub async fn update_session_expiration(db: &DbConn, 
         session: session::Model, 
         expires_at: NaiveDateTime,
@@ -61,7 +76,7 @@ mod tests {
         let session_id = Uuid::new_v4();
         let (candidate, _) = put_user_data(&db).await;
 
-        let session = Mutation::insert_candidate_session(&db, session_id, candidate.application, "127.0.0.1".to_string()).await.unwrap();
+        let session = Mutation::insert_candidate_session(&db, session_id, candidate.application, "127.0.0.1".to_string(), None, "".to_string()).await.unwrap();
 
         assert_eq!(session.id, session_id);
 
@@ -8,8 +8,19 @@ impl Query {
         Admin::find_by_id(id).one(db).await
     }
 
+    pub async fn list_admins(db: &DbConn) -> Result<Vec<admin::Model>, DbErr> {
+        Admin::find()
+            .order_by_asc(admin::Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// Retired keys are left out, disabled admins keep receiving new data
     pub async fn get_all_admin_public_keys(db: &DbConn) -> Result<Vec<String>, DbErr> {
-        let admins = Admin::find().all(db).await?;
+        let admins = Admin::find()
+            .filter(admin::Column::KeyRetiredAt.is_null())
+            .all(db)
+            .await?;
 
         let public_keys = admins
             .iter()
@@ -37,6 +48,7 @@ mod tests {
             public_key: Set("valid_public_key_1".to_string()),
             private_key: Set("test".to_string()),
             password: Set("test".to_string().to_string()),
+            role: Set("superadmin".to_string()),
             created_at: Set(chrono::offset::Local::now().naive_local()),
             updated_at: Set(chrono::offset::Local::now().naive_local()),
             ..Default::default()
@@ -59,6 +71,7 @@ mod tests {
                 public_key: Set(format!("valid_public_key_{}", index)),
                 private_key: Set("test".to_string()),
                 password: Set("test".to_string().to_string()),
+                role: Set("superadmin".to_string()),
                 created_at: Set(chrono::offset::Local::now().naive_local()),
                 updated_at: Set(chrono::offset::Local::now().naive_local()),
                 ..Default::default()
@@ -0,0 +1,21 @@
+use crate::Query;
+
+use ::entity::{admission_round, admission_round::Entity as AdmissionRound};
+use sea_orm::*;
+
+impl Query {
+    /// Admission rounds in chronological order
+    pub async fn list_admission_rounds(db: &DbConn) -> Result<Vec<admission_round::Model>, DbErr> {
+        AdmissionRound::find()
+            .order_by_asc(admission_round::Column::Date)
+            .order_by_asc(admission_round::Column::Id)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_admission_round_by_id(db: &DbConn, id: i32) -> Result<Option<admission_round::Model>, DbErr> {
+        AdmissionRound::find_by_id(id)
+            .one(db)
+            .await
+    }
+}
@@ -63,10 +63,14 @@ impl Query {
     pub async fn list_applications(
         db: &DbConn,
         field_of_study: Option<String>,
+        allowed_fields: Option<Vec<String>>,
         page: Option<u64>,
         sort: Option<String>,
     ) -> Result<Vec<ApplicationCandidateJoin>, DbErr> {
-        let select = application::Entity::find();
+        let select = match allowed_fields {
+            Some(fields) => application::Entity::find().filter(application::Column::FieldOfStudy.is_in(fields)),
+            None => application::Entity::find(),
+        };
         let (column, order) = if let Some(sort) = sort {
             get_ordering(sort)
         } else {
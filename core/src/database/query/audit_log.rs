@@ -0,0 +1,44 @@
+use crate::Query;
+use crate::models::audit::AuditLogFilter;
+
+use ::entity::{audit_log, audit_log::Entity as AuditLog};
+use sea_orm::*;
+
+impl Query {
+    /// Last entry of the hash chain
+    pub async fn find_last_audit_log<C: ConnectionTrait>(db: &C) -> Result<Option<audit_log::Model>, DbErr> {
+        AuditLog::find()
+            .order_by_desc(audit_log::Column::Id)
+            .one(db)
+            .await
+    }
+
+    pub async fn find_audit_log_by_prev_hash<C: ConnectionTrait>(db: &C, prev_hash: &str) -> Result<Option<audit_log::Model>, DbErr> {
+        AuditLog::find()
+            .filter(audit_log::Column::PrevHash.eq(prev_hash))
+            .one(db)
+            .await
+    }
+
+    pub async fn list_audit_log(db: &DbConn, filter: &AuditLogFilter) -> Result<Vec<audit_log::Model>, DbErr> {
+        let mut select = AuditLog::find();
+
+        if let Some(candidate_id) = filter.candidate_id {
+            select = select.filter(audit_log::Column::CandidateId.eq(candidate_id));
+        }
+        if let Some(admin_id) = filter.admin_id {
+            select = select.filter(audit_log::Column::AdminId.eq(admin_id));
+        }
+        if let Some(from) = filter.from {
+            select = select.filter(audit_log::Column::CreatedAt.gte(from));
+        }
+        if let Some(to) = filter.to {
+            select = select.filter(audit_log::Column::CreatedAt.lte(to));
+        }
+
+        select
+            .order_by_asc(audit_log::Column::Id)
+            .all(db)
+            .await
+    }
+}
@@ -44,6 +44,35 @@ impl Query {
             .await
     }
 
+    /// Candidate with the blind index of the personal identification number, see `BlindIndexKey`
+    pub async fn find_candidate_by_personal_id_index(
+        db: &DbConn,
+        personal_id_index: &str,
+    ) -> Result<Option<candidate::Model>, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::PersonalIdIndex.eq(personal_id_index))
+            .one(db)
+            .await
+    }
+
+    /// Candidates created before blind indexes were introduced
+    pub async fn list_candidates_without_personal_id_index(
+        db: &DbConn,
+    ) -> Result<Vec<candidate::Model>, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::PersonalIdIndex.is_null())
+            .order_by(candidate::Column::Id, Order::Asc)
+            .all(db)
+            .await
+    }
+
+    pub async fn count_candidates_without_personal_id_index(db: &DbConn) -> Result<u64, DbErr> {
+        Candidate::find()
+            .filter(candidate::Column::PersonalIdIndex.is_null())
+            .count(db)
+            .await
+    }
+
     pub async fn list_candidates_full(
         db: &DbConn
     ) -> Result<Vec<candidate::Model>, DbErr> {
@@ -0,0 +1,50 @@
+use crate::Query;
+use crate::models::field_of_study::FieldsOfStudy;
+
+use ::entity::{field_of_study, field_of_study::Entity as FieldOfStudy};
+use sea_orm::*;
+
+impl Query {
+    pub async fn list_fields_of_study(db: &DbConn) -> Result<Vec<field_of_study::Model>, DbErr> {
+        FieldOfStudy::find()
+            .order_by_asc(field_of_study::Column::Prefix)
+            .all(db)
+            .await
+    }
+
+    pub async fn find_field_of_study_by_id(db: &DbConn, id: i32) -> Result<Option<field_of_study::Model>, DbErr> {
+        FieldOfStudy::find_by_id(id)
+            .one(db)
+            .await
+    }
+
+    pub async fn find_field_of_study_by_prefix(db: &DbConn, prefix: &str) -> Result<Option<field_of_study::Model>, DbErr> {
+        FieldOfStudy::find()
+            .filter(field_of_study::Column::Prefix.eq(prefix))
+            .one(db)
+            .await
+    }
+
+    pub async fn find_field_of_study_by_application_id(db: &DbConn, application_id: i32) -> Result<Option<field_of_study::Model>, DbErr> {
+        let fields = FieldsOfStudy::from(Self::list_fields_of_study(db).await?);
+
+        Ok(fields.by_application_id(application_id).cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Query;
+    use crate::utils::db::get_memory_sqlite_connection;
+
+    #[tokio::test]
+    async fn test_find_field_of_study_by_application_id() {
+        let db = get_memory_sqlite_connection().await;
+
+        let field = Query::find_field_of_study_by_application_id(&db, 102151).await.unwrap().unwrap();
+        assert_eq!(field.code, "IT");
+
+        assert!(Query::find_field_of_study_by_application_id(&db, 104151).await.unwrap().is_none());
+        assert!(Query::find_field_of_study_by_application_id(&db, 101).await.unwrap().is_none());
+    }
+}
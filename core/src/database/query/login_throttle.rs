@@ -0,0 +1,22 @@
+use crate::Query;
+
+use ::entity::{login_throttle, login_throttle::Entity as LoginThrottle};
+use chrono::NaiveDateTime;
+use sea_orm::*;
+
+impl Query {
+    pub async fn find_login_throttle(db: &DbConn, key: &str) -> Result<Option<login_throttle::Model>, DbErr> {
+        LoginThrottle::find_by_id(key.to_string())
+            .one(db)
+            .await
+    }
+
+    /// Keys locked out at the given time, latest lockouts first
+    pub async fn list_active_lockouts(db: &DbConn, now: NaiveDateTime) -> Result<Vec<login_throttle::Model>, DbErr> {
+        LoginThrottle::find()
+            .filter(login_throttle::Column::LockedUntil.gt(now))
+            .order_by_desc(login_throttle::Column::LockedUntil)
+            .all(db)
+            .await
+    }
+}
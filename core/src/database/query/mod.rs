@@ -4,4 +4,8 @@ pub mod application;
 pub mod candidate;
 pub mod admin;
 pub mod session;
-pub mod parent;
\ No newline at end of file
+pub mod parent;
+pub mod audit_log;
+pub mod field_of_study;
+pub mod admission_round;
+pub mod login_throttle;
\ No newline at end of file
@@ -3,13 +3,13 @@ use entity::candidate;
 use entity::parent;
 use entity::parent::Model;
 use sea_orm::{EntityTrait, ModelTrait};
-use sea_orm::{DbConn, DbErr};
+use sea_orm::{ConnectionTrait, DbConn, DbErr};
 
 use crate::Query;
 
 impl Query {
-    pub async fn find_candidate_parents(
-        db: &DbConn,
+    pub async fn find_candidate_parents<C: ConnectionTrait>(
+        db: &C,
         candidate: &candidate::Model,
     ) -> Result<Vec<Model>, DbErr> {
 
@@ -56,7 +56,8 @@ mod tests {
             ip_address: Set("10.10.10.10".to_string()),
             created_at: Set(chrono::offset::Local::now().naive_local()),
             expires_at: Set(chrono::offset::Local::now().naive_local()),
-            updated_at: Set(chrono::offset::Local::now().naive_local())
+            updated_at: Set(chrono::offset::Local::now().naive_local()),
+            ..Default::default()
         }
         .insert(&db)
         .await
@@ -95,6 +96,7 @@ mod tests {
             public_key: Set("test".to_string()),
             private_key: Set("test".to_string()),
             password: Set("test".to_string().to_string()),
+            role: Set("superadmin".to_string()),
             created_at: Set(chrono::offset::Local::now().naive_local()),
             updated_at: Set(chrono::offset::Local::now().naive_local()),
             ..Default::default()
@@ -1,5 +1,30 @@
+use std::collections::BTreeMap;
+
 use log::error;
 use thiserror::Error;
+use validator::{ValidationErrors, ValidationErrorsKind};
+
+/// Language of error messages, chosen by the `Accept-Language` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale {
+    Cs,
+    En,
+}
+
+impl Locale {
+    /// First supported language of the header, Czech when none matches
+    pub fn from_accept_language(header: Option<&str>) -> Self {
+        header.unwrap_or_default()
+            .split(',')
+            .map(|lang| lang.split(';').next().unwrap_or_default().trim().to_lowercase())
+            .find_map(|lang| match lang.split('-').next() {
+                Some("cs") | Some("sk") => Some(Locale::Cs),
+                Some("en") => Some(Locale::En),
+                _ => None,
+            })
+            .unwrap_or(Locale::Cs)
+    }
+}
 
 #[derive(Error, Debug)]
 
@@ -9,6 +34,16 @@ pub enum ServiceError {
     InvalidApplicationId,
     #[error("Invalid credentials")]
     InvalidCredentials,
+    #[error("Invalid row of the candidate import")]
+    InvalidImportRow,
+    #[error("No credentials to print")]
+    NoCredentials,
+    #[error("Password doesn't match the application")]
+    InvalidLetterPassword,
+    #[error("Too many letters in one request")]
+    TooManyLetters,
+    #[error("Candidates without personal id index, run cli blind_index")]
+    MissingPersonalIdIndex,
     #[error("Unauthorized")]
     Unauthorized,
     #[error("Forbidden")]
@@ -75,14 +110,50 @@ pub enum ServiceError {
     PortfolioWriteError,
     #[error("Zip error")]
     ZipError(#[from] async_zip::error::ZipError),
+    #[error("Invalid portfolio zip: {0}")]
+    InvalidPortfolioZip(String),
     #[error("Csv error")]
     CsvError(#[from] csv::Error),
     #[error("Csv into inner error")]
     CsvIntoInnerError,
+    #[error("PDF error")]
+    PdfError(String),
     #[error("Format error")]
     FormatError,
     #[error("Invalid field of study")]
     InvalidFieldOfStudy,
+    #[error("Admission round not found")]
+    AdmissionRoundNotFound,
+    #[error("Deadline has passed")]
+    DeadlinePassed,
+    #[error("Invalid public key")]
+    InvalidPublicKey,
+    #[error("Storage error")]
+    StorageError(String),
+    #[error("Notification error")]
+    NotificationError(String),
+    #[error("Backup error: {0}")]
+    BackupError(String),
+    #[error("Second factor required")]
+    SecondFactorRequired,
+    #[error("Invalid second factor code")]
+    InvalidSecondFactor,
+    #[error("Second factor not enrolled")]
+    SecondFactorNotEnrolled,
+    #[error("Too many failed login attempts, try again later")]
+    TooManyLoginAttempts,
+    #[error("Lockout not found")]
+    LockoutNotFound,
+    #[error("Session not found")]
+    SessionNotFound,
+    #[error("Invalid id")]
+    InvalidId,
+    #[error("Admin not found")]
+    AdminNotFound,
+    #[error("Invalid admin role")]
+    InvalidRole,
+    #[error("Blind index key is not configured")]
+    MissingBlindIndexKey,
 }
 
 impl ServiceError {
@@ -90,21 +161,40 @@ impl ServiceError {
         match self {
             // 4XX
             ServiceError::InvalidApplicationId => 400,
+            ServiceError::InvalidFieldOfStudy => 400,
+            ServiceError::InvalidImportRow => 400,
+            ServiceError::NoCredentials => 400,
+            ServiceError::InvalidLetterPassword => 400,
+            ServiceError::TooManyLetters => 400,
+            ServiceError::MissingPersonalIdIndex => 500,
             ServiceError::ParentOverflow => 400,
             ServiceError::MissingDetails => 400,
             ServiceError::ValidationError(_) => 400,
             ServiceError::Unauthorized => 401,
             ServiceError::InvalidCredentials => 401,
             ServiceError::ExpiredSession => 401,
+            ServiceError::SecondFactorRequired => 401,
+            ServiceError::InvalidSecondFactor => 401,
+            ServiceError::SecondFactorNotEnrolled => 409,
+            ServiceError::TooManyLoginAttempts => 429,
             ServiceError::Forbidden => 403,
             ServiceError::CandidateNotFound => 404,
+            ServiceError::AdmissionRoundNotFound => 404,
+            ServiceError::LockoutNotFound => 404,
+            ServiceError::SessionNotFound => 404,
+            ServiceError::InvalidId => 400,
+            ServiceError::AdminNotFound => 404,
+            ServiceError::InvalidRole => 400,
+            ServiceError::DeadlinePassed => 403,
             ServiceError::IncompletePortfolio => 406,
             ServiceError::UserAlreadyExists => 409,
             ServiceError::Locked => 423,
             ServiceError::TooManyFieldsForOnePerson => 409,
             ServiceError::TooManyApplications => 409,
+            ServiceError::InvalidPublicKey => 400,
             // 500
             ServiceError::InternalServerError => 500,
+            ServiceError::MissingBlindIndexKey => 500,
             ServiceError::InvalidDate => 500,
             ServiceError::ParentNotFound => 500,
             ServiceError::DbError(_) => 500,
@@ -125,10 +215,143 @@ impl ServiceError {
             ServiceError::ArgonConfigError(_) => 500,
             ServiceError::PortfolioWriteError => 500,
             ServiceError::ZipError(_) => 500,
+            ServiceError::InvalidPortfolioZip(_) => 400,
             ServiceError::CsvError(_) => 500,
             ServiceError::CsvIntoInnerError => 500,
+            ServiceError::PdfError(_) => 500,
             ServiceError::FormatError => 500,
-            ServiceError::InvalidFieldOfStudy => 500,
+            ServiceError::StorageError(_) => 500,
+            ServiceError::NotificationError(_) => 500,
+            ServiceError::BackupError(_) => 500,
+        }
+    }
+
+    /// Stable machine readable code, the frontend matches on it instead of the message
+    pub fn error_code(&self) -> &'static str {
+        match self {
+            ServiceError::InvalidApplicationId => "INVALID_APPLICATION_ID",
+            ServiceError::InvalidCredentials => "INVALID_CREDENTIALS",
+            ServiceError::InvalidImportRow => "INVALID_IMPORT_ROW",
+            ServiceError::NoCredentials => "NO_CREDENTIALS",
+            ServiceError::InvalidLetterPassword => "INVALID_LETTER_PASSWORD",
+            ServiceError::TooManyLetters => "TOO_MANY_LETTERS",
+            ServiceError::MissingPersonalIdIndex => "MISSING_PERSONAL_ID_INDEX",
+            ServiceError::Unauthorized => "UNAUTHORIZED",
+            ServiceError::Forbidden => "FORBIDDEN",
+            ServiceError::ExpiredSession => "EXPIRED_SESSION",
+            ServiceError::MissingDetails => "MISSING_DETAILS",
+            ServiceError::ValidationError(_) => "VALIDATION_ERROR",
+            ServiceError::UserAlreadyExists => "USER_ALREADY_EXISTS",
+            ServiceError::CandidateNotFound => "CANDIDATE_NOT_FOUND",
+            ServiceError::Locked => "LOCKED",
+            ServiceError::TooManyApplications => "TOO_MANY_APPLICATIONS",
+            ServiceError::TooManyFieldsForOnePerson => "TOO_MANY_FIELDS_FOR_ONE_PERSON",
+            ServiceError::InternalServerError => "INTERNAL_SERVER_ERROR",
+            ServiceError::ParentNotFound => "PARENT_NOT_FOUND",
+            ServiceError::InvalidDate => "INVALID_DATE",
+            ServiceError::DbError(_) => "DB_ERROR",
+            ServiceError::ParentOverflow => "PARENT_OVERFLOW",
+            ServiceError::UserNotFoundBySessionId => "USER_NOT_FOUND_BY_SESSION_ID",
+            ServiceError::CryptoEncryptFailed => "CRYPTO_ENCRYPT_FAILED",
+            ServiceError::CryptoDecryptFailed => "CRYPTO_DECRYPT_FAILED",
+            ServiceError::CandidateDetailsNotSet => "CANDIDATE_DETAILS_NOT_SET",
+            ServiceError::TokioJoinError(_) => "TOKIO_JOIN_ERROR",
+            ServiceError::AgeNoRecipientsError => "AGE_NO_RECIPIENTS_ERROR",
+            ServiceError::AgeEncryptError(_) => "AGE_ENCRYPT_ERROR",
+            ServiceError::AgeDecryptError(_) => "AGE_DECRYPT_ERROR",
+            ServiceError::AgeKeyError(_) => "AGE_KEY_ERROR",
+            ServiceError::IOError(_) => "IO_ERROR",
+            ServiceError::Base64DecodeError(_) => "BASE64_DECODE_ERROR",
+            ServiceError::UTF8DecodeError(_) => "UTF8_DECODE_ERROR",
+            ServiceError::ArgonConfigError(_) => "ARGON_CONFIG_ERROR",
+            ServiceError::ArgonHashError(_) => "ARGON_HASH_ERROR",
+            ServiceError::AesError(_) => "AES_ERROR",
+            ServiceError::IncompletePortfolio => "INCOMPLETE_PORTFOLIO",
+            ServiceError::PortfolioWriteError => "PORTFOLIO_WRITE_ERROR",
+            ServiceError::ZipError(_) => "ZIP_ERROR",
+            ServiceError::InvalidPortfolioZip(_) => "INVALID_PORTFOLIO_ZIP",
+            ServiceError::CsvError(_) => "CSV_ERROR",
+            ServiceError::CsvIntoInnerError => "CSV_INTO_INNER_ERROR",
+            ServiceError::PdfError(_) => "PDF_ERROR",
+            ServiceError::FormatError => "FORMAT_ERROR",
+            ServiceError::InvalidFieldOfStudy => "INVALID_FIELD_OF_STUDY",
+            ServiceError::AdmissionRoundNotFound => "ADMISSION_ROUND_NOT_FOUND",
+            ServiceError::DeadlinePassed => "DEADLINE_PASSED",
+            ServiceError::InvalidPublicKey => "INVALID_PUBLIC_KEY",
+            ServiceError::StorageError(_) => "STORAGE_ERROR",
+            ServiceError::NotificationError(_) => "NOTIFICATION_ERROR",
+            ServiceError::BackupError(_) => "BACKUP_ERROR",
+            ServiceError::SecondFactorRequired => "SECOND_FACTOR_REQUIRED",
+            ServiceError::InvalidSecondFactor => "INVALID_SECOND_FACTOR",
+            ServiceError::SecondFactorNotEnrolled => "SECOND_FACTOR_NOT_ENROLLED",
+            ServiceError::TooManyLoginAttempts => "TOO_MANY_LOGIN_ATTEMPTS",
+            ServiceError::LockoutNotFound => "LOCKOUT_NOT_FOUND",
+            ServiceError::SessionNotFound => "SESSION_NOT_FOUND",
+            ServiceError::InvalidId => "INVALID_ID",
+            ServiceError::AdminNotFound => "ADMIN_NOT_FOUND",
+            ServiceError::InvalidRole => "INVALID_ROLE",
+            ServiceError::MissingBlindIndexKey => "MISSING_BLIND_INDEX_KEY",
+        }
+    }
+
+    pub fn message(&self, locale: Locale) -> String {
+        match locale {
+            Locale::En => self.to_string(),
+            Locale::Cs => self.message_cs(),
+        }
+    }
+
+    fn message_cs(&self) -> String {
+        match self {
+            ServiceError::InvalidApplicationId => "Neplatné ev. číslo přihlášky".to_string(),
+            ServiceError::InvalidCredentials => "Neplatné přihlašovací údaje".to_string(),
+            ServiceError::InvalidImportRow => "Neplatný řádek importu".to_string(),
+            ServiceError::NoCredentials => "Nejsou zadány žádné přístupové údaje".to_string(),
+            ServiceError::InvalidLetterPassword => "Heslo neodpovídá přihlášce".to_string(),
+            ServiceError::TooManyLetters => "Příliš mnoho dopisů najednou".to_string(),
+            ServiceError::MissingPersonalIdIndex => "Uchazeči nemají vyhledávací index, spusťte cli blind_index".to_string(),
+            ServiceError::Unauthorized => "Nepřihlášený uživatel".to_string(),
+            ServiceError::Forbidden => "Přístup zamítnut".to_string(),
+            ServiceError::ExpiredSession => "Platnost přihlášení vypršela, přihlaste se prosím znovu".to_string(),
+            ServiceError::MissingDetails => "Chybí osobní údaje".to_string(),
+            ServiceError::ValidationError(_) => "Některé údaje nejsou vyplněny správně".to_string(),
+            ServiceError::UserAlreadyExists => "Uživatel již existuje".to_string(),
+            ServiceError::CandidateNotFound => "Uchazeč nebyl nalezen".to_string(),
+            ServiceError::Locked => "Záznam je uzamčen".to_string(),
+            ServiceError::TooManyApplications => "Příliš mnoho přihlášek".to_string(),
+            ServiceError::TooManyFieldsForOnePerson => "Příliš mnoho oborů pro jednoho uchazeče".to_string(),
+            ServiceError::ParentOverflow => "Příliš mnoho zákonných zástupců".to_string(),
+            ServiceError::UserNotFoundBySessionId => "Uživatel nebyl nalezen, kontaktujte prosím technickou podporu".to_string(),
+            ServiceError::CandidateDetailsNotSet => "Osobní údaje nejsou vyplněny, kontaktujte prosím technickou podporu".to_string(),
+            ServiceError::IncompletePortfolio => "Portfolio není kompletní".to_string(),
+            ServiceError::InvalidPortfolioZip(e) => format!("Neplatný zip s portfoliem: {}", e),
+            ServiceError::InvalidFieldOfStudy => "Neplatný obor".to_string(),
+            ServiceError::AdmissionRoundNotFound => "Kolo přijímacího řízení nebylo nalezeno".to_string(),
+            ServiceError::DeadlinePassed => "Termín již vypršel".to_string(),
+            ServiceError::InvalidPublicKey => "Neplatný veřejný klíč".to_string(),
+            ServiceError::SecondFactorRequired => "Vyžadováno dvoufázové ověření".to_string(),
+            ServiceError::InvalidSecondFactor => "Neplatný ověřovací kód".to_string(),
+            ServiceError::SecondFactorNotEnrolled => "Dvoufázové ověření není nastaveno".to_string(),
+            ServiceError::TooManyLoginAttempts => "Příliš mnoho neúspěšných pokusů o přihlášení, zkuste to prosím později".to_string(),
+            ServiceError::LockoutNotFound => "Blokace nebyla nalezena".to_string(),
+            ServiceError::SessionNotFound => "Relace nebyla nalezena".to_string(),
+            ServiceError::InvalidId => "Neplatný identifikátor".to_string(),
+            ServiceError::AdminNotFound => "Administrátor nebyl nalezen".to_string(),
+            ServiceError::InvalidRole => "Neplatná role administrátora".to_string(),
+            // internal errors, details are only logged
+            _ => "Interní chyba serveru, kontaktujte prosím technickou podporu".to_string(),
+        }
+    }
+
+    /// Invalid fields of a `ValidationError` as `{"candidate.email": ["email"]}`
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            ServiceError::ValidationError(errors) => {
+                let mut fields = BTreeMap::new();
+                flatten_validation_errors(errors, "", &mut fields);
+                serde_json::to_value(fields).ok()
+            },
+            _ => None,
         }
     }
 
@@ -147,7 +370,74 @@ impl ServiceError {
             ServiceError::ArgonConfigError(e) => Some(e.to_string()),
             ServiceError::ZipError(e) => Some(e.to_string()),
             ServiceError::CsvError(e) => Some(e.to_string()),
+            ServiceError::PdfError(e) => Some(e.to_string()),
+            ServiceError::StorageError(e) => Some(e.to_string()),
+            ServiceError::NotificationError(e) => Some(e.to_string()),
+            ServiceError::BackupError(e) => Some(e.to_string()),
             _ => None,
         }
     }
 }
+
+fn flatten_validation_errors(errors: &ValidationErrors, prefix: &str, fields: &mut BTreeMap<String, Vec<String>>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() { field.to_string() } else { format!("{}.{}", prefix, field) };
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields.entry(path)
+                    .or_default()
+                    .extend(errors.iter().map(|e| e.code.to_string()));
+            },
+            ValidationErrorsKind::Struct(errors) => flatten_validation_errors(errors, &path, fields),
+            ValidationErrorsKind::List(list) => {
+                for (i, errors) in list {
+                    flatten_validation_errors(errors, &format!("{}[{}]", path, i), fields);
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use validator::Validate;
+
+    use super::{Locale, ServiceError};
+
+    #[derive(Validate)]
+    struct Form {
+        #[validate(email)]
+        email: String,
+        #[validate(length(min = 1))]
+        name: String,
+    }
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(ServiceError::TooManyApplications.error_code(), "TOO_MANY_APPLICATIONS");
+        assert_eq!(ServiceError::UTF8DecodeError(String::from_utf8(vec![0xff]).unwrap_err()).error_code(), "UTF8_DECODE_ERROR");
+        assert_eq!(ServiceError::IOError(std::io::ErrorKind::Other.into()).error_code(), "IO_ERROR");
+    }
+
+    #[test]
+    fn test_localized_message() {
+        assert_eq!(Locale::from_accept_language(Some("en-US,en;q=0.9,cs;q=0.8")), Locale::En);
+        assert_eq!(Locale::from_accept_language(Some("de-DE, cs;q=0.5")), Locale::Cs);
+        assert_eq!(Locale::from_accept_language(None), Locale::Cs);
+
+        assert_eq!(ServiceError::DeadlinePassed.message(Locale::En), "Deadline has passed");
+        assert_eq!(ServiceError::DeadlinePassed.message(Locale::Cs), "Termín již vypršel");
+        // internal details are not shown to the user
+        assert!(!ServiceError::BackupError("disk full".to_string()).message(Locale::Cs).contains("disk"));
+    }
+
+    #[test]
+    fn test_validation_details() {
+        let form = Form { email: "not an email".to_string(), name: "".to_string() };
+        let e = ServiceError::from(form.validate().unwrap_err());
+
+        assert_eq!(e.error_code(), "VALIDATION_ERROR");
+        assert_eq!(e.details().unwrap(), serde_json::json!({"email": ["email"], "name": ["length"]}));
+        assert!(ServiceError::Forbidden.details().is_none());
+    }
+}
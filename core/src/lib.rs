@@ -9,3 +9,5 @@ pub mod services;
 pub mod error;
 pub mod utils;
 pub mod models;
+pub mod storage;
+pub mod notification;
\ No newline at end of file
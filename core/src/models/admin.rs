@@ -0,0 +1,213 @@
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use entity::admin;
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
+/// What an admin is allowed to do, checked by the request guards
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    ListCandidates,
+    ReadCandidates,
+    ReadPortfolios,
+    ExportData,
+    CreateCandidates,
+    EditCandidates,
+    DeleteCandidates,
+    ManageSettings,
+    ManageAdmins,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdminRole {
+    /// Everything including admin accounts and settings
+    Superadmin,
+    /// Office clerk creating candidates and resetting their passwords
+    Clerk,
+    /// Read-only access to all candidates
+    Staff,
+    /// Lists candidates and downloads portfolios of the assigned fields of study only
+    Reviewer,
+}
+
+impl AdminRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminRole::Superadmin => "superadmin",
+            AdminRole::Clerk => "clerk",
+            AdminRole::Staff => "staff",
+            AdminRole::Reviewer => "reviewer",
+        }
+    }
+
+    pub fn permissions(&self) -> &'static [Permission] {
+        use Permission::*;
+        match self {
+            AdminRole::Superadmin => &[
+                ListCandidates, ReadCandidates, ReadPortfolios, ExportData,
+                CreateCandidates, EditCandidates, DeleteCandidates, ManageSettings, ManageAdmins,
+            ],
+            AdminRole::Clerk => &[ListCandidates, ReadCandidates, CreateCandidates, EditCandidates],
+            AdminRole::Staff => &[ListCandidates, ReadCandidates, ReadPortfolios, ExportData],
+            AdminRole::Reviewer => &[ListCandidates, ReadPortfolios],
+        }
+    }
+
+    /// Role stored on the admin, unknown roles have no permissions
+    pub fn of(admin: &admin::Model) -> Option<Self> {
+        admin.role.parse().ok()
+    }
+
+    pub fn allows(admin: &admin::Model, permission: Permission) -> bool {
+        Self::of(admin).map_or(false, |role| role.permissions().contains(&permission))
+    }
+}
+
+impl FromStr for AdminRole {
+    type Err = ServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "superadmin" => Ok(AdminRole::Superadmin),
+            "clerk" => Ok(AdminRole::Clerk),
+            "staff" => Ok(AdminRole::Staff),
+            "reviewer" => Ok(AdminRole::Reviewer),
+            _ => Err(ServiceError::InvalidRole),
+        }
+    }
+}
+
+/// Fields of study whose candidates an admin can see
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldScope {
+    All,
+    /// Field of study codes, e.g. `IT`
+    Only(Vec<String>),
+}
+
+impl FieldScope {
+    /// Reviewers are limited to their assigned fields, everyone else sees all candidates
+    pub fn of(admin: &admin::Model) -> Self {
+        match AdminRole::of(admin) {
+            Some(AdminRole::Reviewer) | None => FieldScope::Only(assigned_fields(admin)),
+            Some(_) => FieldScope::All,
+        }
+    }
+
+    pub fn allows(&self, field_of_study: &str) -> bool {
+        match self {
+            FieldScope::All => true,
+            FieldScope::Only(fields) => fields.iter().any(|f| f == field_of_study),
+        }
+    }
+
+    /// Fields to filter a query by, `None` means no filter
+    pub fn fields(&self) -> Option<Vec<String>> {
+        match self {
+            FieldScope::All => None,
+            FieldScope::Only(fields) => Some(fields.to_owned()),
+        }
+    }
+}
+
+fn assigned_fields(admin: &admin::Model) -> Vec<String> {
+    admin.fields_of_study.as_deref()
+        .and_then(|f| serde_json::from_str(f).ok())
+        .unwrap_or_default()
+}
+
+/// Admin account without any key material (admin endpoint)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminResponse {
+    pub id: i32,
+    pub name: String,
+    pub public_key: String,
+    pub second_factor: bool,
+    pub role: String,
+    pub fields_of_study: Vec<String>,
+    pub created_at: NaiveDateTime,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub key_retired_at: Option<NaiveDateTime>,
+    pub key_pending_at: Option<NaiveDateTime>,
+}
+
+impl From<admin::Model> for AdminResponse {
+    fn from(admin: admin::Model) -> Self {
+        Self {
+            id: admin.id,
+            second_factor: admin.totp_enabled_at.is_some(),
+            fields_of_study: assigned_fields(&admin),
+            role: admin.role.to_owned(),
+            name: admin.name,
+            public_key: admin.public_key,
+            created_at: admin.created_at,
+            disabled_at: admin.disabled_at,
+            key_retired_at: admin.key_retired_at,
+            key_pending_at: admin.key_pending_at,
+        }
+    }
+}
+
+/// New admin with the generated password, which is shown only once (admin endpoint)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateAdminResponse {
+    pub id: i32,
+    pub name: String,
+    pub password: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use entity::admin;
+
+    use super::{AdminRole, FieldScope, Permission};
+
+    fn admin(role: &str, fields_of_study: Option<&str>) -> admin::Model {
+        admin::Model {
+            id: 1,
+            name: "admin".to_string(),
+            public_key: "".to_string(),
+            private_key: "".to_string(),
+            password: "".to_string(),
+            totp_secret: None,
+            totp_recovery_codes: None,
+            totp_enabled_at: None,
+            totp_last_step: None,
+            disabled_at: None,
+            key_retired_at: None,
+            key_pending_at: None,
+            role: role.to_string(),
+            fields_of_study: fields_of_study.map(|f| f.to_string()),
+            created_at: chrono::Local::now().naive_local(),
+            updated_at: chrono::Local::now().naive_local(),
+        }
+    }
+
+    #[test]
+    fn test_role_permissions() {
+        assert!(AdminRole::allows(&admin("superadmin", None), Permission::ManageAdmins));
+        assert!(AdminRole::allows(&admin("clerk", None), Permission::EditCandidates));
+        assert!(!AdminRole::allows(&admin("clerk", None), Permission::ExportData));
+        assert!(!AdminRole::allows(&admin("staff", None), Permission::CreateCandidates));
+        assert!(!AdminRole::allows(&admin("reviewer", None), Permission::ReadCandidates));
+        assert!(!AdminRole::allows(&admin("", None), Permission::ListCandidates));
+        assert!("root".parse::<AdminRole>().is_err());
+    }
+
+    #[test]
+    fn test_field_scope() {
+        assert_eq!(FieldScope::of(&admin("staff", Some("[\"IT\"]"))), FieldScope::All);
+
+        let scope = FieldScope::of(&admin("reviewer", Some("[\"IT\", \"KB\"]")));
+        assert!(scope.allows("IT"));
+        assert!(!scope.allows("G"));
+
+        // reviewer without assigned fields sees nobody
+        assert_eq!(FieldScope::of(&admin("reviewer", None)), FieldScope::Only(vec![]));
+    }
+}
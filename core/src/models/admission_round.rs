@@ -0,0 +1,46 @@
+use chrono::NaiveDate;
+use entity::admission_round;
+use serde::{Serialize, Deserialize};
+use validator::Validate;
+
+use crate::error::ServiceError;
+
+/// Create or update admission round (admin endpoints)
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionRoundRequest {
+    // NaiveDate validated natively
+    pub date: NaiveDate,
+    /// Short label used in CSV column names, e.g. `13. 4.`
+    #[validate(length(min = 1, max = 255))]
+    pub label: String,
+    /// Round only for one field of study, None for all fields
+    pub field_of_study_id: Option<i32>,
+}
+
+impl AdmissionRoundRequest {
+    pub fn validate_self(&self) -> Result<(), ServiceError> {
+        self.validate()
+            .map_err(ServiceError::ValidationError)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdmissionRoundResponse {
+    pub id: i32,
+    pub date: NaiveDate,
+    pub label: String,
+    pub field_of_study_id: Option<i32>,
+}
+
+impl From<admission_round::Model> for AdmissionRoundResponse {
+    fn from(r: admission_round::Model) -> Self {
+        Self {
+            id: r.id,
+            date: r.date,
+            label: r.label,
+            field_of_study_id: r.field_of_study_id,
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use chrono::NaiveDateTime;
+use entity::audit_log;
+use serde::{Serialize, Deserialize};
+
+/// Operation on personal data recorded in the audit log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuditAction {
+    DecryptDetails,
+    DecryptPortfolio,
+    ListCandidates,
+    ExportApplications,
+    ExportCandidates,
+    DeleteApplication,
+    ResetPassword,
+    GrantExtension,
+    EnableSecondFactor,
+    ResetSecondFactor,
+    ClearLockout,
+    CreateAdmin,
+    ActivateAdmin,
+    DisableAdmin,
+    EnableAdmin,
+    DeleteAdmin,
+    SetAdminRole,
+    ImportCandidates,
+    PrintLetters,
+    RetireAdminKey,
+    PackagePortfolios,
+    CreateBackup,
+    VerifyBackup,
+    RestoreBackup,
+    ChangePassword,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::DecryptDetails => "DECRYPT_DETAILS",
+            AuditAction::DecryptPortfolio => "DECRYPT_PORTFOLIO",
+            AuditAction::ListCandidates => "LIST_CANDIDATES",
+            AuditAction::ExportApplications => "EXPORT_APPLICATIONS",
+            AuditAction::ExportCandidates => "EXPORT_CANDIDATES",
+            AuditAction::DeleteApplication => "DELETE_APPLICATION",
+            AuditAction::ResetPassword => "RESET_PASSWORD",
+            AuditAction::GrantExtension => "GRANT_EXTENSION",
+            AuditAction::EnableSecondFactor => "ENABLE_SECOND_FACTOR",
+            AuditAction::ResetSecondFactor => "RESET_SECOND_FACTOR",
+            AuditAction::ClearLockout => "CLEAR_LOCKOUT",
+            AuditAction::CreateAdmin => "CREATE_ADMIN",
+            AuditAction::ActivateAdmin => "ACTIVATE_ADMIN",
+            AuditAction::DisableAdmin => "DISABLE_ADMIN",
+            AuditAction::EnableAdmin => "ENABLE_ADMIN",
+            AuditAction::DeleteAdmin => "DELETE_ADMIN",
+            AuditAction::SetAdminRole => "SET_ADMIN_ROLE",
+            AuditAction::ImportCandidates => "IMPORT_CANDIDATES",
+            AuditAction::PrintLetters => "PRINT_LETTERS",
+            AuditAction::RetireAdminKey => "RETIRE_ADMIN_KEY",
+            AuditAction::PackagePortfolios => "PACKAGE_PORTFOLIOS",
+            AuditAction::CreateBackup => "CREATE_BACKUP",
+            AuditAction::VerifyBackup => "VERIFY_BACKUP",
+            AuditAction::RestoreBackup => "RESTORE_BACKUP",
+            AuditAction::ChangePassword => "CHANGE_PASSWORD",
+        }
+    }
+}
+
+impl ToString for AuditAction {
+    fn to_string(&self) -> String {
+        self.as_str().to_string()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AuditLogFilter {
+    pub candidate_id: Option<i32>,
+    pub admin_id: Option<i32>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogResponse {
+    pub id: i32,
+    pub admin_id: Option<i32>,
+    pub candidate_id: Option<i32>,
+    pub application_id: Option<i32>,
+    pub action: String,
+    pub detail: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub hash: String,
+}
+
+impl From<audit_log::Model> for AuditLogResponse {
+    fn from(entry: audit_log::Model) -> Self {
+        Self {
+            id: entry.id,
+            admin_id: entry.admin_id,
+            candidate_id: entry.candidate_id,
+            application_id: entry.application_id,
+            action: entry.action,
+            detail: entry.detail,
+            created_at: entry.created_at,
+            hash: entry.hash,
+        }
+    }
+}
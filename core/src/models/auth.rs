@@ -1,5 +1,9 @@
 use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use entity::{admin_session, session};
 use sea_orm::{prelude::Uuid, DbConn};
+use serde::{Deserialize, Serialize};
+use validator::Validate;
 
 use crate::error::ServiceError;
 
@@ -8,9 +12,71 @@ use crate::error::ServiceError;
 pub trait AuthenticableTrait {
     type User;
     type Session;
-    async fn login(db: &DbConn, user: i32, password: String, ip_addr: String) -> Result<(String, String), ServiceError>;
+    async fn login(db: &DbConn, user: i32, password: String, ip_addr: String, user_agent: Option<String>) -> Result<(String, String), ServiceError>;
     async fn auth(db: &DbConn, session_id: Uuid) -> Result<Self::User, ServiceError>;
     async fn logout(db: &DbConn, session: Self::Session) -> Result<(), ServiceError>;
-    async fn new_session(db: &DbConn, user: &Self::User, ip_addr: String, password: String) -> Result<String, ServiceError>;
+    async fn new_session(db: &DbConn, user: &Self::User, ip_addr: String, password: String, user_agent: Option<String>) -> Result<String, ServiceError>;
     async fn delete_old_sessions(db: &DbConn, user: &Self::User, keep_n_recent: usize) -> Result<(), ServiceError>;
-}
\ No newline at end of file
+}
+
+/// Password chosen by the candidate, generated passwords have 12 characters
+#[derive(Debug, Validate)]
+pub struct NewPassword {
+    #[validate(length(min = 8, max = 255))]
+    pub password: String,
+}
+
+/// Secret of a second factor being enrolled (admin endpoint)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TotpEnrolmentResponse {
+    pub secret: String,
+    pub uri: String,
+}
+
+/// One-time recovery codes, shown to the admin only once (admin endpoint)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+/// Active session of the logged in user (candidate and admin endpoint)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    pub id: String,
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub last_seen_at: Option<NaiveDateTime>,
+    pub expires_at: NaiveDateTime,
+    /// Session the request was made with
+    pub current: bool,
+}
+
+impl SessionResponse {
+    pub fn from_candidate_session(session: session::Model, current: Option<Uuid>) -> Self {
+        Self {
+            id: session.id.to_string(),
+            ip_address: session.ip_address,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+            current: Some(session.id) == current,
+        }
+    }
+
+    pub fn from_admin_session(session: admin_session::Model, current: Option<Uuid>) -> Self {
+        Self {
+            id: session.id.to_string(),
+            ip_address: session.ip_address,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_seen_at: session.last_seen_at,
+            expires_at: session.expires_at,
+            current: Some(session.id) == current,
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::NaiveDateTime;
+use serde::{Serialize, Deserialize};
+
+pub const BACKUP_VERSION: u32 = 1;
+pub const BACKUP_MANIFEST: &str = "manifest.json";
+
+/// File stored in the backup archive
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupEntry {
+    pub path: String,
+    pub size: u64,
+    /// Hex encoded SHA-256 of the file
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupTable {
+    pub name: String,
+    pub rows: usize,
+}
+
+/// Content of the backup, stored in the archive as `manifest.json`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: NaiveDateTime,
+    pub tables: Vec<BackupTable>,
+    /// Directories of the portfolio store, kept so that empty candidate folders are restored too
+    pub directories: Vec<String>,
+    /// Database dumps and candidate files
+    pub entries: Vec<BackupEntry>,
+}
+
+impl BackupManifest {
+    pub fn new(created_at: NaiveDateTime) -> Self {
+        Self {
+            version: BACKUP_VERSION,
+            created_at,
+            tables: vec![],
+            directories: vec![],
+            entries: vec![],
+        }
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+}
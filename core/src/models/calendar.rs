@@ -0,0 +1,91 @@
+use chrono::NaiveDateTime;
+use serde::{Serialize, Deserialize};
+
+use crate::{error::ServiceError, utils::date::parse_naive_datetime};
+
+/// Part of the admissions process with its own deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Filling in candidate and parent details
+    Details,
+    /// Uploading, submitting and deleting portfolio
+    Portfolio,
+}
+
+/// Admissions calendar configured by `PORTFOLIO_DETAILS_DEADLINE` and `PORTFOLIO_PORTFOLIO_DEADLINE`,
+/// phase without a deadline is open indefinitely
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AdmissionsCalendar {
+    pub details_deadline: Option<NaiveDateTime>,
+    pub portfolio_deadline: Option<NaiveDateTime>,
+}
+
+impl AdmissionsCalendar {
+    pub fn from_env() -> Result<Self, ServiceError> {
+        let deadline = |name: &str| std::env::var(name)
+            .ok()
+            .map(|d| parse_naive_datetime(&d))
+            .transpose();
+
+        Ok(Self {
+            details_deadline: deadline("PORTFOLIO_DETAILS_DEADLINE")?,
+            portfolio_deadline: deadline("PORTFOLIO_PORTFOLIO_DEADLINE")?,
+        })
+    }
+
+    /// Deadline of the phase for a candidate, individual extension can only postpone it
+    pub fn deadline(&self, phase: Phase, extension: Option<NaiveDateTime>) -> Option<NaiveDateTime> {
+        let deadline = match phase {
+            Phase::Details => self.details_deadline,
+            Phase::Portfolio => self.portfolio_deadline,
+        };
+        match (deadline, extension) {
+            (Some(deadline), Some(extension)) => Some(deadline.max(extension)),
+            (deadline, _) => deadline,
+        }
+    }
+
+    pub fn check(&self, phase: Phase, extension: Option<NaiveDateTime>, now: NaiveDateTime) -> Result<(), ServiceError> {
+        match self.deadline(phase, extension) {
+            Some(deadline) if now > deadline => Err(ServiceError::DeadlinePassed),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Deadlines of the candidate (candidate endpoint)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadlinesResponse {
+    pub details_deadline: Option<NaiveDateTime>,
+    pub portfolio_deadline: Option<NaiveDateTime>,
+    pub extended: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::{AdmissionsCalendar, Phase};
+
+    fn datetime(day: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2023, 3, day).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_calendar_check() {
+        let calendar = AdmissionsCalendar {
+            details_deadline: Some(datetime(10)),
+            portfolio_deadline: None,
+        };
+
+        assert!(calendar.check(Phase::Details, None, datetime(9)).is_ok());
+        assert!(calendar.check(Phase::Details, None, datetime(11)).is_err());
+        assert!(calendar.check(Phase::Portfolio, None, datetime(11)).is_ok());
+
+        // extension postpones the deadline, but never brings it forward
+        assert!(calendar.check(Phase::Details, Some(datetime(15)), datetime(11)).is_ok());
+        assert!(calendar.check(Phase::Details, Some(datetime(5)), datetime(9)).is_ok());
+        assert!(calendar.check(Phase::Details, Some(datetime(15)), datetime(16)).is_err());
+    }
+}
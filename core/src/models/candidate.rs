@@ -1,6 +1,6 @@
+use base64::Engine;
 use chrono::NaiveDate;
-use sea_orm::strum::Display;
-use entity::{application, candidate};
+use entity::{admission_round, application, candidate};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
@@ -8,57 +8,7 @@ use crate::{
     error::ServiceError,
 };
 
-use super::{candidate_details::{EncryptedString, EncryptedCandidateDetails}, grade::GradeList, school::School};
-
-#[derive(Debug, Clone, Serialize, Display)]
-pub enum FieldOfStudy {
-    G,
-    IT,
-    KB,
-}
-
-impl Into<String> for FieldOfStudy {
-    fn into(self) -> String {
-        match self {
-            FieldOfStudy::G => "G".to_string(),
-            FieldOfStudy::IT => "IT".to_string(),
-            FieldOfStudy::KB => "KB".to_string(),
-        }
-    }
-}
-
-impl From<i32> for FieldOfStudy {
-    fn from(id: i32) -> Self {
-        match &id.to_string().as_str()[0..3] {
-            "101" => FieldOfStudy::G,
-            "102" => FieldOfStudy::IT,
-            "103" => FieldOfStudy::KB,
-            _ => panic!("Invalid field of study id"), // TODO: handle using TryFrom
-        }
-    }
-}
-
-impl TryFrom<String> for FieldOfStudy {
-    type Error = ServiceError;
-    fn try_from(s: String) -> Result<Self, ServiceError> {
-        match s.as_str() {
-            "7941K41-Gymnázium" => Ok(FieldOfStudy::G),
-            "1820M01-Informační technologie" => Ok(FieldOfStudy::IT), // TODO: constants
-            "1820M01-Informační technologie - Kybernetická bezpečnost" => Ok(FieldOfStudy::KB),
-            _ => Err(ServiceError::InvalidFieldOfStudy),
-        }
-    }
-}
-
-impl Into<i32> for FieldOfStudy {
-    fn into(self) -> i32 {
-        match self {
-            FieldOfStudy::G => 101,
-            FieldOfStudy::IT => 102,
-            FieldOfStudy::KB => 103,
-        }
-    }
-}
+use super::{candidate_details::{EncryptedString, EncryptedCandidateDetails}, field_of_study::FieldsCombination, grade::GradeList, school::School};
 
 /// Minimal candidate response containing database only not null fields
 #[derive(Debug, Serialize, Deserialize)]
@@ -84,6 +34,76 @@ pub struct CreateCandidateResponse {
     pub password: String,
 }
 
+/// Row of the bulk candidate import, the `email` column is optional
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct CandidateImportRow {
+    pub application_id: i32,
+    pub personal_id_number: String,
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ImportStatus {
+    /// New candidate with a new application
+    Created,
+    /// New application of a candidate who already applied to another field of study
+    Linked,
+    /// Application imported before, e.g. on a previous run
+    Existing,
+    Rejected,
+}
+
+/// Outcome of a single row of the import, `reason` is the error code of a rejected row
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportRowResult {
+    pub line: u64,
+    pub application_id: Option<i32>,
+    pub status: ImportStatus,
+    pub reason: Option<String>,
+}
+
+/// Results of the import and the generated passwords as an age encrypted CSV
+#[derive(Debug)]
+pub struct ImportReport {
+    pub results: Vec<ImportRowResult>,
+    pub passwords: Vec<u8>,
+}
+
+impl ImportReport {
+    pub fn count(&self, status: ImportStatus) -> usize {
+        self.results.iter().filter(|r| r.status == status).count()
+    }
+
+    pub fn summary(&self) -> String {
+        format!("created={} linked={} existing={} rejected={}",
+            self.count(ImportStatus::Created),
+            self.count(ImportStatus::Linked),
+            self.count(ImportStatus::Existing),
+            self.count(ImportStatus::Rejected),
+        )
+    }
+}
+
+/// Bulk import (admin endpoint), `passwords` is the base64 encoded age file
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportResponse {
+    pub results: Vec<ImportRowResult>,
+    pub passwords: String,
+}
+
+impl From<ImportReport> for ImportResponse {
+    fn from(report: ImportReport) -> Self {
+        Self {
+            results: report.results,
+            passwords: base64::engine::general_purpose::STANDARD.encode(report.passwords),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct CandidateDetails {
@@ -152,7 +172,10 @@ impl NewCandidateResponse {
         private_key: &String,
         c: candidate::Model,
     ) -> Result<Self, ServiceError> {
-        let field_of_study = FieldOfStudy::from(current_application).into();
+        let field_of_study = applications.iter()
+            .find(|a| a.id == current_application)
+            .map(|a| a.field_of_study.to_owned())
+            .ok_or(ServiceError::CandidateNotFound)?;
         let id_number = EncryptedString::from(c.personal_identification_number.to_owned())
             .decrypt(private_key)
             .await?;
@@ -170,89 +193,86 @@ impl NewCandidateResponse {
     }
 }
 
-#[derive(Debug, Serialize, PartialEq)]
-pub enum FieldsCombination {
-    #[serde(rename = "Žádný obor na SSPŠ")]
-    Unknown,
-    #[serde(rename = "G")]
-    G,
-    #[serde(rename = "IT")]
-    IT,
-    #[serde(rename = "KB")]
-    KB,
-    #[serde(rename = "G a IT")]
-    GIt,
-    #[serde(rename = "G a KB")]
-    GKb,
-    #[serde(rename = "IT a KB")]
-    ItKb,
+/// Candidate's school in one admission round
+#[derive(Debug)]
+pub struct CandidateRoundRow {
+    /// Candidate takes the exam at our school in this round
+    pub admissions: bool,
+    /// Code of our field of study
+    pub field: Option<String>,
+    pub school: String,
+    pub school_field: String,
 }
 
-impl FieldsCombination {
-    pub fn from_fields(first: &Option<FieldOfStudy>, second: &Option<FieldOfStudy>) -> Self {
-        match (first, second) {
-            (None, None) => FieldsCombination::Unknown,
-            (Some(FieldOfStudy::G), None) => FieldsCombination::G,
-            (Some(FieldOfStudy::IT), None) => FieldsCombination::IT,
-            (Some(FieldOfStudy::KB), None) => FieldsCombination::KB,
-            (None, Some(FieldOfStudy::G)) => FieldsCombination::G,
-            (None, Some(FieldOfStudy::IT)) => FieldsCombination::IT,
-            (None, Some(FieldOfStudy::KB)) => FieldsCombination::KB,
-            // Field combinations
-            (Some(FieldOfStudy::G), Some(FieldOfStudy::IT)) => FieldsCombination::GIt,
-            (Some(FieldOfStudy::G), Some(FieldOfStudy::KB)) => FieldsCombination::GKb,
-            (Some(FieldOfStudy::IT), Some(FieldOfStudy::KB)) => FieldsCombination::ItKb,
-            (Some(FieldOfStudy::IT), Some(FieldOfStudy::G)) => FieldsCombination::GIt,
-            (Some(FieldOfStudy::KB), Some(FieldOfStudy::G)) => FieldsCombination::GKb,
-            (Some(FieldOfStudy::KB), Some(FieldOfStudy::IT)) => FieldsCombination::ItKb,
-            // Some candidates filled in the same field twice
-            (Some(FieldOfStudy::G), Some(FieldOfStudy::G)) => FieldsCombination::G,
-            (Some(FieldOfStudy::IT), Some(FieldOfStudy::IT)) => FieldsCombination::IT,
-            (Some(FieldOfStudy::KB), Some(FieldOfStudy::KB)) => FieldsCombination::KB,
-        }
-    }
-}
-
-#[derive(Debug, Serialize)]
+/// Candidate CSV row, round columns are generated from the admission rounds
+#[derive(Debug)]
 pub struct CandidateRow {
-    #[serde(rename = "Číslo uchazeče (přiděleno systémem)")]
     pub id: i32,
-    #[serde(rename = "Ev. č. první přihlášky")]
     pub first_application: i32,
-    #[serde(rename = "Ev. č. druhé přihlášky (pokud podával dvě)")]
     pub second_application: Option<i32>,
-    #[serde(rename = "Rodné číslo")]
     pub personal_id_number: String,
-    #[serde(rename = "Bude dělat JPZ na SSPŠ 13. 4.")]
-    pub first_day_admissions: bool,
-    #[serde(rename = "Bude dělat JPZ na SSPŠ 14. 4.")]
-    pub second_day_admissions: bool,
-    #[serde(rename = "Obor první přihlášky SSPŠ 13. 4.")]
-    pub first_day_field: Option<FieldOfStudy>,
-    #[serde(rename = "Obor druhé přihlášky SSPŠ 14. 4.")]
-    pub second_day_field: Option<FieldOfStudy>,
-    #[serde(rename = "Kombinace SSPŠ oborů")]
+    pub rounds: Vec<CandidateRoundRow>,
     pub fields_combination: FieldsCombination,
-    #[serde(rename = "Název první školy (JPZ 13. 4.)")]
-    pub first_school: String,
-    #[serde(rename = "Obor první školy")]
-    pub first_school_field: String,
-    #[serde(rename = "Název druhé školy (JPZ 14. 4.)")]
-    pub second_school: String,
-    #[serde(rename = "Obor druhé školy")]
-    pub second_school_field: String,
-    #[serde(rename = "Obory vyplněné uchazečem odpovídají s přihláškami")]
     pub fields_match: bool,
-    #[serde(rename = "Jméno (pokud vyplnil)")]
     pub name: String,
-    #[serde(rename = "Příjmení (pokud vyplnil)")]
     pub surname: String,
-    #[serde(rename = "Email uchazeče (pokud vyplnil)")]
     pub email: String,
-    #[serde(rename = "Telefon uchazeče (pokud vyplnil)")]
     pub telephone: String,
-    #[serde(rename = "Email zákonného zástupce (pokud vyplnil)")]
     pub parent_email: Option<String>,
-    #[serde(rename = "Telefon zákonného zástupce (pokud vyplnil)")]
     pub parent_telephone: Option<String>,
-}
\ No newline at end of file
+}
+
+impl CandidateRow {
+    pub fn headers(rounds: &[admission_round::Model]) -> Vec<String> {
+        let mut headers = vec![
+            "Číslo uchazeče (přiděleno systémem)".to_string(),
+            "Ev. č. první přihlášky".to_string(),
+            "Ev. č. druhé přihlášky (pokud podával dvě)".to_string(),
+            "Rodné číslo".to_string(),
+        ];
+        headers.extend(rounds.iter().map(|r| format!("Bude dělat JPZ u nás {}", r.label)));
+        headers.extend(rounds.iter().map(|r| format!("Obor naší přihlášky {}", r.label)));
+        headers.push("Kombinace našich oborů".to_string());
+        for r in rounds {
+            headers.push(format!("Název školy (JPZ {})", r.label));
+            headers.push(format!("Obor školy (JPZ {})", r.label));
+        }
+        headers.extend([
+            "Obory vyplněné uchazečem odpovídají s přihláškami",
+            "Jméno (pokud vyplnil)",
+            "Příjmení (pokud vyplnil)",
+            "Email uchazeče (pokud vyplnil)",
+            "Telefon uchazeče (pokud vyplnil)",
+            "Email zákonného zástupce (pokud vyplnil)",
+            "Telefon zákonného zástupce (pokud vyplnil)",
+        ].map(String::from));
+        headers
+    }
+
+    /// Values in the same order as `CandidateRow::headers`
+    pub fn into_record(self) -> Vec<String> {
+        let mut record = vec![
+            self.id.to_string(),
+            self.first_application.to_string(),
+            self.second_application.map(|id| id.to_string()).unwrap_or_default(),
+            self.personal_id_number,
+        ];
+        record.extend(self.rounds.iter().map(|r| r.admissions.to_string()));
+        record.extend(self.rounds.iter().map(|r| r.field.to_owned().unwrap_or_default()));
+        record.push(self.fields_combination.to_string());
+        for r in self.rounds {
+            record.push(r.school);
+            record.push(r.school_field);
+        }
+        record.extend([
+            self.fields_match.to_string(),
+            self.name,
+            self.surname,
+            self.email,
+            self.telephone,
+            self.parent_email.unwrap_or_default(),
+            self.parent_telephone.unwrap_or_default(),
+        ]);
+        record
+    }
+}
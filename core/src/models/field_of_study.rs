@@ -0,0 +1,146 @@
+use std::fmt::Display;
+
+use entity::field_of_study;
+use serde::{Serialize, Deserialize, Serializer};
+use validator::Validate;
+
+use crate::error::ServiceError;
+
+const DEFAULT_SCHOOL_NAME: &str = "Smíchovská střední průmyslová škola a gymnázium";
+
+/// Name of the school candidates fill in when they apply to one of our fields of study,
+/// configurable with `PORTFOLIO_SCHOOL_NAME`
+pub fn our_school_name() -> String {
+    std::env::var("PORTFOLIO_SCHOOL_NAME").unwrap_or(DEFAULT_SCHOOL_NAME.to_string())
+}
+
+/// Field of study (admin endpoints)
+#[derive(Debug, Clone, Serialize, Deserialize, Validate, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldOfStudyResponse {
+    #[validate(length(min = 1, max = 5))]
+    pub prefix: String,
+    #[validate(length(min = 1, max = 15))]
+    pub code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(range(min = 0))]
+    pub capacity: i32,
+}
+
+impl FieldOfStudyResponse {
+    pub fn validate_self(&self) -> Result<(), ServiceError> {
+        if !self.prefix.chars().all(|c| c.is_ascii_digit()) {
+            return Err(ServiceError::InvalidFieldOfStudy);
+        }
+        self.validate()
+            .map_err(ServiceError::ValidationError)
+    }
+}
+
+impl From<field_of_study::Model> for FieldOfStudyResponse {
+    fn from(f: field_of_study::Model) -> Self {
+        Self {
+            prefix: f.prefix,
+            code: f.code,
+            name: f.name,
+            capacity: f.capacity,
+        }
+    }
+}
+
+/// All fields of study loaded from the database
+pub struct FieldsOfStudy(Vec<field_of_study::Model>);
+
+impl From<Vec<field_of_study::Model>> for FieldsOfStudy {
+    fn from(fields: Vec<field_of_study::Model>) -> Self {
+        Self(fields)
+    }
+}
+
+impl FieldsOfStudy {
+    /// Application id starts with the field of study prefix, e.g. 102151 belongs to field with prefix 102
+    pub fn by_application_id(&self, application_id: i32) -> Option<&field_of_study::Model> {
+        let id = application_id.to_string();
+        self.0.iter()
+            .filter(|f| id.len() > f.prefix.len() && id.starts_with(&f.prefix))
+            .max_by_key(|f| f.prefix.len())
+    }
+
+    /// Official name of the field of study as filled in by the candidate
+    pub fn by_name(&self, name: &str) -> Option<&field_of_study::Model> {
+        self.0.iter().find(|f| f.name == name)
+    }
+}
+
+/// Combination of our fields of study the candidate applied to, ordered by prefix
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldsCombination(Vec<String>);
+
+impl FieldsCombination {
+    pub fn from_fields<'a>(fields: impl IntoIterator<Item = Option<&'a field_of_study::Model>>) -> Self {
+        let mut fields: Vec<&field_of_study::Model> = fields.into_iter().flatten().collect();
+        fields.sort_by(|a, b| a.prefix.cmp(&b.prefix));
+        // Some candidates filled in the same field twice
+        fields.dedup_by(|a, b| a.prefix == b.prefix);
+
+        Self(fields.into_iter().map(|f| f.code.to_owned()).collect())
+    }
+}
+
+impl Display for FieldsCombination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_empty() {
+            write!(f, "Žádný náš obor")
+        } else {
+            write!(f, "{}", self.0.join(" a "))
+        }
+    }
+}
+
+impl Serialize for FieldsCombination {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use entity::field_of_study;
+
+    use super::{FieldsCombination, FieldsOfStudy};
+
+    fn field(prefix: &str, code: &str) -> field_of_study::Model {
+        field_of_study::Model {
+            id: 0,
+            prefix: prefix.to_string(),
+            code: code.to_string(),
+            name: format!("{} name", code),
+            capacity: 30,
+        }
+    }
+
+    #[test]
+    fn test_by_application_id() {
+        let fields = FieldsOfStudy::from(vec![field("101", "G"), field("102", "IT")]);
+
+        assert_eq!(fields.by_application_id(102151).unwrap().code, "IT");
+        assert!(fields.by_application_id(103151).is_none());
+        assert!(fields.by_application_id(101).is_none());
+        assert_eq!(fields.by_name("G name").unwrap().prefix, "101");
+    }
+
+    #[test]
+    fn test_fields_combination() {
+        let (g, it) = (field("101", "G"), field("102", "IT"));
+
+        assert_eq!(FieldsCombination::from_fields([None, None]).to_string(), "Žádný náš obor");
+        assert_eq!(FieldsCombination::from_fields([None, Some(&it)]).to_string(), "IT");
+        assert_eq!(FieldsCombination::from_fields([Some(&it), Some(&g)]).to_string(), "G a IT");
+        assert_eq!(
+            FieldsCombination::from_fields([Some(&g), Some(&it)]),
+            FieldsCombination::from_fields([Some(&it), Some(&g)]),
+        );
+        assert_eq!(FieldsCombination::from_fields([Some(&g), Some(&g)]).to_string(), "G");
+    }
+}
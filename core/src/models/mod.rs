@@ -3,4 +3,13 @@ pub mod candidate;
 pub mod auth;
 pub mod application;
 pub mod grade;
-pub mod school;
\ No newline at end of file
+pub mod school;
+pub mod audit;
+
+pub mod field_of_study;
+pub mod admission_round;
+pub mod calendar;
+pub mod package;
+pub mod backup;
+pub mod throttle;
+pub mod admin;
\ No newline at end of file
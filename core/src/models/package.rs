@@ -0,0 +1,111 @@
+use crate::error::ServiceError;
+
+pub const DEFAULT_FOLDER_NAME_TEMPLATE: &str = "{surname} {name} ({applications})";
+
+/// Candidate whose portfolio is being packaged, values available in the folder name template
+#[derive(Debug, Clone)]
+pub struct PackagedCandidate {
+    pub candidate_id: i32,
+    pub name: String,
+    pub surname: String,
+    pub applications: Vec<i32>,
+    pub fields: Vec<String>,
+}
+
+/// Template of candidate folder names with placeholders `{name}`, `{surname}`,
+/// `{applications}`, `{fields}` and `{candidate_id}`
+#[derive(Debug, Clone)]
+pub struct FolderNameTemplate(String);
+
+impl Default for FolderNameTemplate {
+    fn default() -> Self {
+        Self(DEFAULT_FOLDER_NAME_TEMPLATE.to_string())
+    }
+}
+
+impl FolderNameTemplate {
+    pub fn new(template: &str) -> Result<Self, ServiceError> {
+        if template.trim().is_empty() {
+            return Err(ServiceError::FormatError);
+        }
+        Ok(Self(template.to_string()))
+    }
+
+    /// Renders folder name, characters not allowed in file names are replaced with `_`
+    pub fn render(&self, candidate: &PackagedCandidate) -> String {
+        let join = |items: Vec<String>| items.join(", ");
+
+        let name = self.0
+            .replace("{name}", &candidate.name)
+            .replace("{surname}", &candidate.surname)
+            .replace("{applications}", &join(candidate.applications.iter().map(|id| id.to_string()).collect()))
+            .replace("{fields}", &join(candidate.fields.to_owned()))
+            .replace("{candidate_id}", &candidate.candidate_id.to_string());
+
+        let name: String = name.chars()
+            .map(|c| if c.is_control() || "/\\:*?\"<>|".contains(c) { '_' } else { c })
+            .collect();
+        let name = name.trim().trim_matches('.').trim();
+
+        if name.is_empty() {
+            candidate.candidate_id.to_string()
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+/// Candidate that could not be packaged
+#[derive(Debug, Clone)]
+pub struct PackageFailure {
+    pub candidate_id: i32,
+    pub applications: Vec<i32>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PackageReport {
+    pub packaged: usize,
+    /// Candidates without submitted portfolio
+    pub not_submitted: Vec<i32>,
+    pub failures: Vec<PackageFailure>,
+}
+
+impl ToString for PackageReport {
+    fn to_string(&self) -> String {
+        let mut report = format!(
+            "Packaged: {}\nNot submitted: {}\nFailed: {}\n",
+            self.packaged,
+            self.not_submitted.len(),
+            self.failures.len(),
+        );
+        for failure in self.failures.iter() {
+            report.push_str(&format!(
+                "candidate {} (applications {:?}): {}\n",
+                failure.candidate_id, failure.applications, failure.reason
+            ));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FolderNameTemplate, PackagedCandidate};
+
+    #[test]
+    fn test_folder_name_template() {
+        let candidate = PackagedCandidate {
+            candidate_id: 7,
+            name: "Jan".to_string(),
+            surname: "Novák/Dvořák".to_string(),
+            applications: vec![101101, 102101],
+            fields: vec!["G".to_string(), "IT".to_string()],
+        };
+
+        assert_eq!(FolderNameTemplate::default().render(&candidate), "Novák_Dvořák Jan (101101, 102101)");
+        assert_eq!(FolderNameTemplate::new("{fields} - {candidate_id}").unwrap().render(&candidate), "G, IT - 7");
+        assert_eq!(FolderNameTemplate::new("..").unwrap().render(&candidate), "7");
+        assert!(FolderNameTemplate::new(" ").is_err());
+    }
+}
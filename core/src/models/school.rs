@@ -4,11 +4,16 @@ use validator::Validate;
 use crate::error::ServiceError;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Validate, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
 pub struct School {
     #[validate(length(min = 1, max = 255))]
     name: String,
     #[validate(length(min = 1, max = 255))]
     field: String,
+    /// Admission round the candidate takes the exam for this school in,
+    /// schools without it fall back to rounds in chronological order
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    admission_round: Option<i32>,
 }
 
 impl School {
@@ -30,6 +35,10 @@ impl School {
     pub fn field(&self) -> &str {
         &self.field
     }
+
+    pub fn admission_round(&self) -> Option<i32> {
+        self.admission_round
+    }
 }
 
 impl ToString for School {
@@ -43,6 +52,7 @@ impl Default for School {
         Self {
             name: String::default(),
             field: String::default(),
+            admission_round: None,
         }
     }
 }
\ No newline at end of file
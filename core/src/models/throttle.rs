@@ -0,0 +1,146 @@
+use chrono::{Duration, NaiveDateTime};
+use serde::{Serialize, Deserialize};
+
+/// Subject of login throttling
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThrottleKey {
+    Ip(String),
+    Candidate(i32),
+    Admin(i32),
+}
+
+impl ThrottleKey {
+    pub fn is_account(&self) -> bool {
+        !matches!(self, ThrottleKey::Ip(_))
+    }
+}
+
+impl ToString for ThrottleKey {
+    fn to_string(&self) -> String {
+        match self {
+            ThrottleKey::Ip(ip) => format!("ip:{}", ip),
+            ThrottleKey::Candidate(id) => format!("candidate:{}", id),
+            ThrottleKey::Admin(id) => format!("admin:{}", id),
+        }
+    }
+}
+
+/// Failed attempts allowed within a window before the key is locked out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThrottlePolicy {
+    pub max_failures: i32,
+    pub window: Duration,
+    pub lockout: Duration,
+}
+
+impl ThrottlePolicy {
+    /// Failures counted in a window started before this time are forgotten
+    pub fn window_start_limit(&self, now: NaiveDateTime) -> NaiveDateTime {
+        now - self.window
+    }
+
+    /// End of the lockout when the failures counted in the current window reach the limit
+    pub fn locked_until(&self, failures: i32, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        (failures >= self.max_failures).then(|| now + self.lockout)
+    }
+}
+
+/// Limits configured by `PORTFOLIO_LOGIN_MAX_FAILURES` (per account), `PORTFOLIO_LOGIN_IP_MAX_FAILURES` (per IP address),
+/// `PORTFOLIO_LOGIN_WINDOW_MINUTES` and `PORTFOLIO_LOGIN_LOCKOUT_MINUTES`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoginThrottle {
+    pub account: ThrottlePolicy,
+    pub ip: ThrottlePolicy,
+}
+
+impl Default for LoginThrottle {
+    fn default() -> Self {
+        Self {
+            account: ThrottlePolicy {
+                max_failures: 5,
+                window: Duration::minutes(15),
+                lockout: Duration::minutes(15),
+            },
+            ip: ThrottlePolicy {
+                max_failures: 50,
+                window: Duration::minutes(15),
+                lockout: Duration::minutes(15),
+            },
+        }
+    }
+}
+
+impl LoginThrottle {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        let var = |name: &str| std::env::var(name).ok().and_then(|v| v.parse::<i64>().ok());
+
+        let window = var("PORTFOLIO_LOGIN_WINDOW_MINUTES").map(Duration::minutes);
+        let lockout = var("PORTFOLIO_LOGIN_LOCKOUT_MINUTES").map(Duration::minutes);
+
+        Self {
+            account: ThrottlePolicy {
+                max_failures: var("PORTFOLIO_LOGIN_MAX_FAILURES").map(|v| v as i32).unwrap_or(default.account.max_failures),
+                window: window.unwrap_or(default.account.window),
+                lockout: lockout.unwrap_or(default.account.lockout),
+            },
+            ip: ThrottlePolicy {
+                max_failures: var("PORTFOLIO_LOGIN_IP_MAX_FAILURES").map(|v| v as i32).unwrap_or(default.ip.max_failures),
+                window: window.unwrap_or(default.ip.window),
+                lockout: lockout.unwrap_or(default.ip.lockout),
+            },
+        }
+    }
+
+    pub fn policy(&self, key: &ThrottleKey) -> &ThrottlePolicy {
+        if key.is_account() {
+            &self.account
+        } else {
+            &self.ip
+        }
+    }
+}
+
+/// Locked out IP address or account (admin endpoint)
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockoutResponse {
+    pub key: String,
+    pub failures: i32,
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+impl From<entity::login_throttle::Model> for LockoutResponse {
+    fn from(throttle: entity::login_throttle::Model) -> Self {
+        Self {
+            key: throttle.key,
+            failures: throttle.failures,
+            locked_until: throttle.locked_until,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{LoginThrottle, ThrottleKey};
+
+    #[test]
+    fn test_policy() {
+        let policy = LoginThrottle::default().account;
+        let now = NaiveDate::from_ymd_opt(2023, 2, 1).unwrap().and_hms_opt(8, 0, 0).unwrap();
+
+        assert_eq!(policy.window_start_limit(now), now - policy.window);
+        assert_eq!(policy.locked_until(4, now), None);
+        assert_eq!(policy.locked_until(5, now), Some(now + policy.lockout));
+    }
+
+    #[test]
+    fn test_throttle_key() {
+        assert_eq!(ThrottleKey::Ip("10.0.0.1".to_string()).to_string(), "ip:10.0.0.1");
+        assert_eq!(ThrottleKey::Candidate(103151).to_string(), "candidate:103151");
+        assert_eq!(LoginThrottle::default().policy(&ThrottleKey::Admin(1)).max_failures, 5);
+        assert_eq!(LoginThrottle::default().policy(&ThrottleKey::Ip("::1".to_string())).max_failures, 50);
+    }
+}
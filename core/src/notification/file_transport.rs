@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use log::info;
+
+use crate::error::ServiceError;
+
+use super::{Message, NotificationTransport};
+
+/// Development transport, messages are only logged
+/// or also written as `.eml` files into `PORTFOLIO_MAIL_DIR` when set
+pub struct FileTransport {
+    dir: Option<PathBuf>,
+}
+
+impl FileTransport {
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    pub fn from_env() -> Self {
+        Self::new(std::env::var("PORTFOLIO_MAIL_DIR").ok().map(PathBuf::from))
+    }
+}
+
+#[async_trait]
+impl NotificationTransport for FileTransport {
+    async fn send(&self, message: &Message) -> Result<(), ServiceError> {
+        info!("MAIL TO {} SUBJECT {}", message.to.join(", "), message.subject);
+
+        if let Some(dir) = &self.dir {
+            tokio::fs::create_dir_all(dir).await?;
+            let file_name = format!(
+                "{}_{}.eml",
+                chrono::Utc::now().format("%Y%m%d%H%M%S"),
+                uuid::Uuid::new_v4()
+            );
+            let content = format!(
+                "To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+                message.to.join(", "),
+                message.subject,
+                message.body
+            );
+            tokio::fs::write(dir.join(file_name), content).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notification::{Message, NotificationTransport};
+
+    use super::FileTransport;
+
+    #[tokio::test]
+    async fn test_file_transport() {
+        let dir = std::env::temp_dir().join(format!("portfolio_mail_test_{}", uuid::Uuid::new_v4()));
+        let transport = FileTransport::new(Some(dir.clone()));
+
+        let message = Message {
+            to: vec!["uchazec@example.com".to_string()],
+            subject: "Předmět".to_string(),
+            body: "Text zprávy".to_string(),
+        };
+        transport.send(&message).await.unwrap();
+
+        let mut entries = tokio::fs::read_dir(&dir).await.unwrap();
+        let entry = entries.next_entry().await.unwrap().unwrap();
+        let content = tokio::fs::read_to_string(entry.path()).await.unwrap();
+        assert!(content.contains("To: uchazec@example.com"));
+        assert!(content.contains("Subject: Předmět"));
+        assert!(content.ends_with("Text zprávy"));
+
+        tokio::fs::remove_dir_all(dir).await.unwrap();
+    }
+}
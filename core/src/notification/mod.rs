@@ -0,0 +1,45 @@
+use async_trait::async_trait;
+
+use crate::error::ServiceError;
+
+pub mod file_transport;
+pub mod smtp_transport;
+pub mod template;
+
+pub use self::file_transport::FileTransport;
+pub use self::smtp_transport::SmtpTransport;
+pub use self::template::Template;
+
+/// Plain text email
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Message {
+    pub fn from_template(to: Vec<String>, template: &Template) -> Self {
+        Self {
+            to,
+            subject: template.subject(),
+            body: template.body(),
+        }
+    }
+}
+
+/// Way messages leave the system
+#[async_trait]
+pub trait NotificationTransport: Send + Sync {
+    async fn send(&self, message: &Message) -> Result<(), ServiceError>;
+}
+
+/// Returns transport selected by `PORTFOLIO_MAIL_TRANSPORT` (`file` by default or `smtp`)
+pub fn get_transport() -> Result<Box<dyn NotificationTransport>, ServiceError> {
+    dotenv::dotenv().ok();
+    match std::env::var("PORTFOLIO_MAIL_TRANSPORT").unwrap_or_else(|_| "file".to_string()).as_str() {
+        "file" => Ok(Box::new(FileTransport::from_env())),
+        "smtp" => Ok(Box::new(SmtpTransport::from_env()?)),
+        other => Err(ServiceError::NotificationError(format!("Unknown mail transport {}", other))),
+    }
+}
@@ -0,0 +1,123 @@
+use async_trait::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Tokio1Executor,
+};
+
+use crate::error::ServiceError;
+
+use super::{Message, NotificationTransport};
+
+/// Messages sent through SMTP server
+pub struct SmtpTransport {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+}
+
+fn smtp_error<E: ToString>(e: E) -> ServiceError {
+    ServiceError::NotificationError(e.to_string())
+}
+
+fn env_var(name: &str) -> Result<String, ServiceError> {
+    std::env::var(name).map_err(|_| ServiceError::NotificationError(format!("{} not set", name)))
+}
+
+impl SmtpTransport {
+    /// Without TLS the connection is unencrypted, meant only for local SMTP sinks
+    pub fn new(
+        host: &str,
+        port: u16,
+        tls: bool,
+        credentials: Option<(String, String)>,
+        from: &str,
+    ) -> Result<Self, ServiceError> {
+        let builder = if tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(smtp_error)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)
+        }
+            .port(port);
+
+        let builder = match credentials {
+            Some((username, password)) => builder.credentials(Credentials::new(username, password)),
+            None => builder,
+        };
+
+        Ok(Self {
+            transport: builder.build(),
+            from: from.parse().map_err(smtp_error)?,
+        })
+    }
+
+    /// Configured by `PORTFOLIO_SMTP_HOST`, `PORTFOLIO_SMTP_PORT`, `PORTFOLIO_SMTP_TLS`,
+    /// `PORTFOLIO_SMTP_USERNAME`, `PORTFOLIO_SMTP_PASSWORD` and `PORTFOLIO_MAIL_FROM`
+    pub fn from_env() -> Result<Self, ServiceError> {
+        let tls = std::env::var("PORTFOLIO_SMTP_TLS").map(|v| v != "false").unwrap_or(true);
+        let port = match std::env::var("PORTFOLIO_SMTP_PORT") {
+            Ok(port) => port.parse().map_err(smtp_error)?,
+            Err(_) => if tls { 587 } else { 25 },
+        };
+        let credentials = match (std::env::var("PORTFOLIO_SMTP_USERNAME"), std::env::var("PORTFOLIO_SMTP_PASSWORD")) {
+            (Ok(username), Ok(password)) => Some((username, password)),
+            _ => None,
+        };
+
+        Self::new(
+            &env_var("PORTFOLIO_SMTP_HOST")?,
+            port,
+            tls,
+            credentials,
+            &env_var("PORTFOLIO_MAIL_FROM")?,
+        )
+    }
+}
+
+#[async_trait]
+impl NotificationTransport for SmtpTransport {
+    async fn send(&self, message: &Message) -> Result<(), ServiceError> {
+        let mut builder = lettre::Message::builder()
+            .from(self.from.clone())
+            .subject(message.subject.to_owned())
+            .header(ContentType::TEXT_PLAIN);
+        for to in &message.to {
+            builder = builder.to(to.parse().map_err(smtp_error)?);
+        }
+        let email = builder.body(message.body.to_owned()).map_err(smtp_error)?;
+
+        self.transport.send(email).await.map_err(smtp_error)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::notification::{Message, NotificationTransport};
+
+    use super::SmtpTransport;
+
+    /// Needs a local SMTP sink (e.g. MailHog), run with
+    /// `PORTFOLIO_SMTP_TEST_HOST=127.0.0.1:1025 cargo test -p portfolio-core -- --ignored smtp_transport`
+    #[tokio::test]
+    #[ignore]
+    async fn test_smtp_transport() {
+        let host = std::env::var("PORTFOLIO_SMTP_TEST_HOST")
+            .expect("PORTFOLIO_SMTP_TEST_HOST must be set");
+        let (host, port) = host.split_once(':').unwrap_or((&host, "25"));
+
+        let transport = SmtpTransport::new(
+            host,
+            port.parse().unwrap(),
+            false,
+            None,
+            "Přijímací řízení <portfolio@example.com>"
+        ).unwrap();
+
+        let message = Message {
+            to: vec!["uchazec@example.com".to_string()],
+            subject: "Předmět".to_string(),
+            body: "Text zprávy".to_string(),
+        };
+        transport.send(&message).await.unwrap();
+    }
+}
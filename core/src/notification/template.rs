@@ -0,0 +1,83 @@
+use chrono::NaiveDateTime;
+
+/// Messages sent to candidates and their parents
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Template {
+    AccountCreated {
+        application_id: i32,
+        password: String,
+    },
+    PasswordReset {
+        application_id: i32,
+        password: String,
+    },
+    PortfolioSubmitted {
+        applications: Vec<i32>,
+    },
+    DeadlineReminder {
+        applications: Vec<i32>,
+        deadline: NaiveDateTime,
+    },
+}
+
+const SIGNATURE: &str = "\n\nTato zpráva byla vygenerována automaticky, neodpovídejte na ni.";
+
+fn join_ids(ids: &[i32]) -> String {
+    ids.iter()
+        .map(|id| id.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Template {
+    pub fn subject(&self) -> String {
+        match self {
+            Template::AccountCreated { application_id, .. } => format!("Přístup do přihláškového systému ({})", application_id),
+            Template::PasswordReset { application_id, .. } => format!("Nové heslo do přihláškového systému ({})", application_id),
+            Template::PortfolioSubmitted { .. } => "Portfolio bylo odevzdáno".to_string(),
+            Template::DeadlineReminder { .. } => "Připomínka termínu odevzdání portfolia".to_string(),
+        }
+    }
+
+    pub fn body(&self) -> String {
+        let body = match self {
+            Template::AccountCreated { application_id, password } => format!(
+                "Dobrý den,\n\nbyl Vám vytvořen účet v přihláškovém systému.\n\nEvidenční číslo přihlášky: {}\nHeslo: {}\n\nHeslo si po prvním přihlášení můžete změnit.",
+                application_id, password
+            ),
+            Template::PasswordReset { application_id, password } => format!(
+                "Dobrý den,\n\nheslo k přihlášce {} bylo obnoveno.\n\nNové heslo: {}\n\nPokud jste o obnovení nežádali, kontaktujte prosím školu.",
+                application_id, password
+            ),
+            Template::PortfolioSubmitted { applications } => format!(
+                "Dobrý den,\n\nportfolio k přihlášce {} bylo úspěšně odevzdáno.",
+                join_ids(applications)
+            ),
+            Template::DeadlineReminder { applications, deadline } => format!(
+                "Dobrý den,\n\nportfolio k přihlášce {} zatím nebylo odevzdáno. Odevzdat ho můžete do {}.",
+                join_ids(applications),
+                deadline.format("%-d. %-m. %Y %H:%M")
+            ),
+        };
+        body + SIGNATURE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::Template;
+
+    #[test]
+    fn test_template_rendering() {
+        let template = Template::AccountCreated { application_id: 103151, password: "heslo123".to_string() };
+        assert!(template.subject().contains("103151"));
+        assert!(template.body().contains("heslo123"));
+
+        let deadline = NaiveDate::from_ymd_opt(2023, 2, 28).unwrap().and_hms_opt(23, 59, 0).unwrap();
+        let template = Template::DeadlineReminder { applications: vec![101101, 102101], deadline };
+        assert!(template.body().contains("101101, 102101"));
+        assert!(template.body().contains("28. 2. 2023 23:59"));
+    }
+}
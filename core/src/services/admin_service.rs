@@ -1,25 +1,630 @@
+use std::pin::Pin;
+
 use async_trait::async_trait;
-use entity::{admin, admin_session, session};
-use sea_orm::{prelude::Uuid, DbConn, IntoActiveModel};
+use chrono::NaiveDateTime;
+use entity::{admin, admin_session, application, candidate, session};
+use log::info;
+use sea_orm::{prelude::Uuid, DbConn, IntoActiveModel, TransactionTrait};
+use tokio::io::AsyncRead;
 
-use crate::{crypto, error::ServiceError, Query, Mutation, models::auth::AuthenticableTrait};
+use crate::{crypto, error::ServiceError, Query, Mutation, utils::csv::CsvExporter, models::{admin::{AdminResponse, AdminRole, CreateAdminResponse, FieldScope}, auth::{AuthenticableTrait, SessionResponse}, audit::AuditAction, application::ApplicationResponse, candidate::{ApplicationDetails, CreateCandidateResponse}, candidate_details::{EncryptedString, EncryptedCandidateDetails}}};
 
-use super::session_service::SessionService;
+use super::{session_service::SessionService, application_service::ApplicationService, portfolio_service::PortfolioService, audit_service::AuditService, calendar_service::CalendarService, totp_service::TotpService};
 
 pub struct AdminService;
 
 impl AdminService {
     async fn decrypt_private_key(
-        db: &DbConn,
-        admin_id: i32,
+        admin: &admin::Model,
         password: String,
     ) -> Result<String, ServiceError> {
-        let admin = Query::find_admin_by_id(db, admin_id).await?.ok_or(ServiceError::InvalidCredentials)?;
-        let private_key_encrypted = admin.private_key;
+        let private_key_encrypted = admin.private_key.to_owned();
         let private_key = crypto::decrypt_password(private_key_encrypted, password).await?;
 
         Ok(private_key)
     }
+
+    /// Verifies the password and stores the unlocked private key in the key slot of a new session.
+    /// Returns the session id and the secret of its key slot.
+    async fn create_session(
+        db: &DbConn,
+        admin: &admin::Model,
+        password: String,
+        ip_addr: String,
+        user_agent: Option<String>,
+    ) -> Result<(String, String), ServiceError> {
+        if !crypto::verify_password(password.clone(), admin.password.clone()).await? {
+            return Err(ServiceError::InvalidCredentials);
+        }
+        if crypto::password_needs_rehash(&admin.password) {
+            let password_hash = crypto::hash_password(password.clone()).await?;
+            Mutation::update_admin_password_hash(db, admin.to_owned(), password_hash).await?;
+        }
+        // user is authenticated, generate a new session
+        let private_key = Self::decrypt_private_key(admin, password.clone()).await?;
+        if crypto::is_legacy_ciphertext(&admin.private_key) {
+            let enc_priv_key = crypto::encrypt_password(private_key.clone(), password).await?;
+            Mutation::update_admin_private_key(db, admin.to_owned(), enc_priv_key).await?;
+        }
+        let (key_slot, secret) = SessionService::seal_private_key(private_key).await?;
+        let random_uuid: Uuid = Uuid::new_v4();
+
+        let session = Mutation::insert_admin_session(db, admin.id, random_uuid, ip_addr, user_agent, key_slot).await?;
+
+        Self::delete_old_sessions(db, &admin, 1).await?;
+
+        Ok((session.id.to_string(), secret))
+    }
+
+    async fn find_valid_session(db: &DbConn, session_uuid: Uuid) -> Result<admin_session::Model, ServiceError> {
+        let session = Query::find_admin_session_by_uuid(db, session_uuid)
+            .await?
+            .ok_or(ServiceError::Unauthorized)?;
+
+        if !SessionService::is_valid(&session).await? {
+            Mutation::delete_session(db, session.into_active_model()).await?;
+            return Err(ServiceError::ExpiredSession);
+        }
+
+        if SessionService::should_update_last_seen(session.last_seen_at) {
+            return Ok(Mutation::update_admin_session_last_seen(db, session).await?);
+        }
+        Ok(session)
+    }
+
+    /// Active sessions of the admin, `current` marks the session of the request
+    pub async fn list_sessions(
+        db: &DbConn,
+        admin: &admin::Model,
+        current: Option<Uuid>,
+    ) -> Result<Vec<SessionResponse>, ServiceError> {
+        let sessions = Query::find_related_admin_sessions(db, admin).await?;
+
+        Ok(
+            sessions.into_iter()
+                .map(|s| SessionResponse::from_admin_session(s, current))
+                .collect()
+        )
+    }
+
+    pub async fn revoke_session(
+        db: &DbConn,
+        admin: &admin::Model,
+        session_id: Uuid,
+    ) -> Result<(), ServiceError> {
+        let session = Query::find_admin_session_by_uuid(db, session_id)
+            .await?
+            .filter(|s| s.admin_id == admin.id)
+            .ok_or(ServiceError::SessionNotFound)?;
+
+        Mutation::delete_session(db, session.into_active_model()).await?;
+        Ok(())
+    }
+
+    /// Authenticates the session and unlocks the admin private key from its key slot
+    pub async fn auth_with_private_key(
+        db: &DbConn,
+        session_uuid: Uuid,
+        secret: String,
+    ) -> Result<(admin::Model, String), ServiceError> {
+        let session = Self::find_valid_session(db, session_uuid).await?;
+        let private_key = SessionService::open_private_key(session.key_slot.to_owned(), secret).await?;
+
+        let admin = Query::find_admin_by_id(db, session.admin_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+        if admin.disabled_at.is_some() || admin.key_retired_at.is_some() {
+            return Err(ServiceError::Unauthorized);
+        }
+
+        Ok((admin, private_key))
+    }
+
+    async fn find_application(db: &DbConn, application_id: i32) -> Result<entity::application::Model, ServiceError> {
+        Query::find_application_by_id(db, application_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)
+    }
+
+    /// Applications of fields of study outside of the admin's scope are forbidden
+    async fn find_application_in_scope(
+        db: &DbConn,
+        application_id: i32,
+        scope: &FieldScope,
+    ) -> Result<entity::application::Model, ServiceError> {
+        let application = Self::find_application(db, application_id).await?;
+        if !scope.allows(&application.field_of_study) {
+            return Err(ServiceError::Forbidden);
+        }
+        Ok(application)
+    }
+
+    /// Decrypts all details of the candidate and records it in the audit log
+    pub async fn get_candidate_details(
+        db: &DbConn,
+        admin_id: i32,
+        scope: &FieldScope,
+        private_key: String,
+        application_id: i32,
+    ) -> Result<ApplicationDetails, ServiceError> {
+        let application = Self::find_application_in_scope(db, application_id, scope).await?;
+
+        let details = ApplicationService::decrypt_all_details(private_key, db, &application).await?;
+
+        AuditService::log(db, Some(admin_id), Some(application.candidate_id), Some(application.id), AuditAction::DecryptDetails, None).await?;
+        Ok(details)
+    }
+
+    /// Returns reader of decrypted portfolio and records it in the audit log
+    pub async fn get_candidate_portfolio(
+        db: &DbConn,
+        admin_id: i32,
+        scope: &FieldScope,
+        private_key: String,
+        application_id: i32,
+    ) -> Result<Pin<Box<dyn AsyncRead + Send>>, ServiceError> {
+        let application = Self::find_application_in_scope(db, application_id, scope).await?;
+
+        let portfolio = PortfolioService::get_portfolio(application.candidate_id, private_key).await?;
+
+        AuditService::log(db, Some(admin_id), Some(application.candidate_id), Some(application.id), AuditAction::DecryptPortfolio, None).await?;
+        Ok(portfolio)
+    }
+
+    pub async fn list_candidates(
+        db: &DbConn,
+        admin_id: i32,
+        scope: &FieldScope,
+        private_key: &String,
+        field_of_study: Option<String>,
+        page: Option<u64>,
+        sort: Option<String>,
+    ) -> Result<Vec<ApplicationResponse>, ServiceError> {
+        if let Some(field) = field_of_study.as_ref() {
+            let fields = Query::list_fields_of_study(db).await?;
+            if !fields.iter().any(|f| &f.code == field) {
+                return Err(ServiceError::InvalidFieldOfStudy);
+            }
+            if !scope.allows(field) {
+                return Err(ServiceError::Forbidden);
+            }
+        }
+        let detail = format!("field={:?} page={:?} sort={:?}", field_of_study, page, sort);
+        let applications = ApplicationService::list_applications(private_key, db, field_of_study, scope.fields(), page, sort).await?;
+
+        AuditService::log(db, Some(admin_id), None, None, AuditAction::ListCandidates, Some(detail)).await?;
+        Ok(applications)
+    }
+
+    /// Exports decrypted data of all candidates and records it in the audit log
+    pub async fn export_csv<T: CsvExporter>(
+        db: &DbConn,
+        admin_id: Option<i32>,
+        scope: &FieldScope,
+        private_key: String,
+        action: AuditAction,
+    ) -> Result<Vec<u8>, ServiceError> {
+        let csv = T::export(db, private_key, scope).await?;
+
+        AuditService::log(db, admin_id, None, None, action, None).await?;
+        Ok(csv)
+    }
+
+    pub async fn delete_candidate(
+        db: &DbConn,
+        admin_id: i32,
+        application_id: i32,
+    ) -> Result<(), ServiceError> {
+        let application = Self::find_application(db, application_id).await?;
+        let candidate_id = application.candidate_id;
+
+        ApplicationService::delete(db, application).await?;
+
+        AuditService::log(db, Some(admin_id), Some(candidate_id), Some(application_id), AuditAction::DeleteApplication, None).await?;
+        Ok(())
+    }
+
+    pub async fn reset_candidate_password(
+        db: &DbConn,
+        admin_id: i32,
+        private_key: String,
+        application_id: i32,
+    ) -> Result<CreateCandidateResponse, ServiceError> {
+        let application = Self::find_application(db, application_id).await?;
+
+        let response = ApplicationService::reset_password(private_key, db, application_id).await?;
+
+        AuditService::log(db, Some(admin_id), Some(application.candidate_id), Some(application_id), AuditAction::ResetPassword, None).await?;
+        Ok(response)
+    }
+
+    /// Grants the candidate an individual extension of all deadlines, None revokes it
+    pub async fn extend_deadline(
+        db: &DbConn,
+        admin_id: i32,
+        application_id: i32,
+        until: Option<NaiveDateTime>,
+    ) -> Result<(), ServiceError> {
+        let application = Self::find_application(db, application_id).await?;
+        let candidate = Query::find_candidate_by_id(db, application.candidate_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+
+        CalendarService::set_extension(db, candidate, until).await?;
+
+        let detail = format!("until={:?}", until);
+        AuditService::log(db, Some(admin_id), Some(application.candidate_id), Some(application_id), AuditAction::GrantExtension, Some(detail)).await?;
+        Ok(())
+    }
+
+    /// Login with an optional TOTP or recovery code,
+    /// fails with `ServiceError::SecondFactorRequired` when the admin has one enabled and no code was sent
+    pub async fn login_with_second_factor(
+        db: &DbConn,
+        admin_id: i32,
+        password: String,
+        code: Option<String>,
+        ip_addr: String,
+        user_agent: Option<String>,
+    ) -> Result<(String, String), ServiceError> {
+        let admin = Query::find_admin_by_id(db, admin_id)
+            .await?
+            .filter(|a| a.disabled_at.is_none() && a.key_retired_at.is_none() && a.key_pending_at.is_none())
+            .ok_or(ServiceError::InvalidCredentials)?;
+        TotpService::verify_login(db, &admin, &password, code).await?;
+
+        Self::create_session(db, &admin, password, ip_addr, user_agent).await
+    }
+
+    /// Re-encrypts all personal data and the submitted portfolio of one candidate
+    /// to the new admin recipients. Public keys of the candidate's applications are always kept,
+    /// so the candidate never loses access to their own data.
+    pub async fn rotate_candidate_keys(
+        db: &DbConn,
+        admin_private_key: &String,
+        candidate_id: i32,
+        admin_recipients: &Vec<String>,
+    ) -> Result<(), ServiceError> {
+        Self::rotate_candidate_data_keys(db, admin_private_key, candidate_id, admin_recipients).await?;
+        Self::rotate_candidate_portfolio_keys(db, admin_private_key, candidate_id, admin_recipients).await
+    }
+
+    /// First step of `rotate_candidate_keys`, re-encrypts the database rows of the candidate.
+    /// All updates succeed or none, so a failed step can be run again with the old key.
+    /// Once it succeeds the rows aren't readable by the old key anymore, callers resuming
+    /// a rotation have to continue with `rotate_candidate_portfolio_keys`.
+    pub async fn rotate_candidate_data_keys(
+        db: &DbConn,
+        admin_private_key: &String,
+        candidate_id: i32,
+        admin_recipients: &Vec<String>,
+    ) -> Result<(), ServiceError> {
+        let (candidate, applications, recipients) = Self::rotation_recipients(db, candidate_id, admin_recipients).await?;
+        let encrypted_by_id = candidate.encrypted_by_id
+            .or(applications.first().map(|a| a.id))
+            .ok_or(ServiceError::CandidateNotFound)?;
+
+        info!("CANDIDATE {} KEY ROTATION STARTED", candidate_id);
+
+        let txn = db.begin().await?;
+
+        // personal id number stored on application is readable by that application and admins only
+        for application in applications {
+            let personal_id_number = EncryptedString::from(application.personal_id_number.to_owned())
+                .decrypt(admin_private_key)
+                .await?;
+
+            let mut application_recipients = admin_recipients.to_owned();
+            application_recipients.push(application.public_key.to_owned());
+            let enc_personal_id_number = EncryptedString::new(&personal_id_number, &application_recipients).await?;
+
+            Mutation::update_application_personal_id(&txn, application, enc_personal_id_number.to_string()).await?;
+        }
+
+        if EncryptedCandidateDetails::from(&candidate).is_filled() {
+            ApplicationService::update_all_application_details(&txn,
+                encrypted_by_id,
+                candidate,
+                &recipients,
+                admin_private_key,
+            ).await?;
+        } else {
+            let personal_id_number = EncryptedString::from(candidate.personal_identification_number.to_owned())
+                .decrypt(admin_private_key)
+                .await?;
+            let enc_personal_id_number = EncryptedString::new(&personal_id_number, &recipients).await?;
+
+            Mutation::update_personal_id(&txn, candidate, &enc_personal_id_number.to_string()).await?;
+        }
+        txn.commit().await?;
+
+        info!("CANDIDATE {} DATA KEY ROTATION FINISHED", candidate_id);
+
+        Ok(())
+    }
+
+    /// Second step of `rotate_candidate_keys`, re-encrypts the submitted portfolio.
+    /// The portfolio is replaced only after it was re-encrypted, a failed step can be run again with the old key.
+    pub async fn rotate_candidate_portfolio_keys(
+        db: &DbConn,
+        admin_private_key: &String,
+        candidate_id: i32,
+        admin_recipients: &Vec<String>,
+    ) -> Result<(), ServiceError> {
+        let (_, _, recipients) = Self::rotation_recipients(db, candidate_id, admin_recipients).await?;
+
+        if PortfolioService::is_portfolio_submitted(candidate_id).await {
+            PortfolioService::reencrypt_portfolio(candidate_id,
+                admin_private_key.to_owned(),
+                &recipients
+            ).await?;
+        }
+
+        info!("CANDIDATE {} KEY ROTATION FINISHED", candidate_id);
+
+        Ok(())
+    }
+
+    /// Admin recipients with the public keys of the candidate's applications
+    async fn rotation_recipients(
+        db: &DbConn,
+        candidate_id: i32,
+        admin_recipients: &Vec<String>,
+    ) -> Result<(candidate::Model, Vec<application::Model>, Vec<String>), ServiceError> {
+        if admin_recipients.is_empty() || !admin_recipients.iter().all(|r| crypto::is_valid_public_key(r)) {
+            return Err(ServiceError::InvalidPublicKey);
+        }
+
+        let candidate = Query::find_candidate_by_id(db, candidate_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+        let applications = Query::find_applications_by_candidate_id(db, candidate_id).await?;
+
+        let mut recipients = admin_recipients.to_owned();
+        recipients.append(&mut applications.iter().map(|a| a.public_key.to_owned()).collect());
+
+        Ok((candidate, applications, recipients))
+    }
+
+    /// Retires keys of admins which are not among the new recipients of a key rotation,
+    /// so they aren't used for new data anymore. Retired admins are logged out for good.
+    pub async fn retire_admin_keys(db: &DbConn, recipients: &Vec<String>) -> Result<Vec<admin::Model>, ServiceError> {
+        let admins: Vec<admin::Model> = Query::list_admins(db).await?
+            .into_iter()
+            .filter(|a| a.key_retired_at.is_none())
+            .collect();
+        // someone has to be able to read the data encrypted after the rotation
+        if !admins.iter().any(|a| recipients.contains(&a.public_key)) {
+            return Err(ServiceError::InvalidPublicKey);
+        }
+
+        let mut retired = vec![];
+        for admin in admins.into_iter().filter(|a| !recipients.contains(&a.public_key)) {
+            for session in Query::find_related_admin_sessions(db, &admin).await? {
+                Mutation::delete_session(db, session.into_active_model()).await?;
+            }
+            let admin = Mutation::update_admin_key_retired_at(db, admin, chrono::Local::now().naive_local()).await?;
+
+            AuditService::log(db, None, None, None, AuditAction::RetireAdminKey, Some(format!("admin={}", admin.id))).await?;
+            retired.push(admin);
+        }
+        Ok(retired)
+    }
+
+    /// Re-encrypts every candidate to the admin recipients, needed before an admin is added or deleted.
+    /// Every candidate is rotated in its own transaction, so it can be run again after a failure.
+    pub async fn reencrypt_all_candidates(db: &DbConn, admin_private_key: &String, recipients: &Vec<String>) -> Result<(), ServiceError> {
+        let ids = Query::list_all_candidate_ids(db).await?;
+
+        for id in ids.iter().map(|id| id.to_i32()) {
+            Self::rotate_candidate_keys(db, admin_private_key, id, recipients).await?;
+        }
+        Ok(())
+    }
+
+    async fn find_admin(db: &DbConn, admin_id: i32) -> Result<admin::Model, ServiceError> {
+        Query::find_admin_by_id(db, admin_id)
+            .await?
+            .ok_or(ServiceError::AdminNotFound)
+    }
+
+    pub async fn list_admins(db: &DbConn) -> Result<Vec<AdminResponse>, ServiceError> {
+        Ok(
+            Query::list_admins(db).await?
+                .into_iter()
+                .map(AdminResponse::from)
+                .collect()
+        )
+    }
+
+    /// Generates a key pair encrypted with the password. A pending admin's key is a recipient
+    /// of new data right away, but the admin can't log in until `activate_pending_admins`
+    /// re-encrypts the existing candidates to it.
+    async fn create_admin(
+        db: &DbConn,
+        name: String,
+        password: String,
+        role: AdminRole,
+        pending: bool,
+    ) -> Result<admin::Model, ServiceError> {
+        let (public_key, private_key) = crypto::create_identity();
+        let enc_priv_key = crypto::encrypt_password(private_key, password.to_owned()).await?;
+        let password_hash = crypto::hash_password(password).await?;
+        let key_pending_at = pending.then(|| chrono::Local::now().naive_local());
+
+        Ok(Mutation::insert_admin(db, name, public_key, enc_priv_key, password_hash, role, key_pending_at).await?)
+    }
+
+    /// Re-encrypts all candidates to the admin keys including the pending ones and activates the pending admins (cli).
+    /// Candidates created since a pending admin was inserted already include its key,
+    /// a failed run leaves the admins pending and can be run again.
+    pub async fn activate_pending_admins(db: &DbConn, admin_private_key: &String) -> Result<Vec<admin::Model>, ServiceError> {
+        let pending: Vec<admin::Model> = Query::list_admins(db).await?
+            .into_iter()
+            .filter(|a| a.key_pending_at.is_some() && a.key_retired_at.is_none())
+            .collect();
+        if pending.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let recipients = Query::get_all_admin_public_keys(db).await?;
+        Self::reencrypt_all_candidates(db, admin_private_key, &recipients).await?;
+
+        let mut activated = vec![];
+        for admin in pending {
+            let admin = Mutation::update_admin_key_pending_at(db, admin, None).await?;
+            AuditService::log(db, None, None, None, AuditAction::ActivateAdmin, Some(format!("admin={}", admin.id))).await?;
+            activated.push(admin);
+        }
+        Ok(activated)
+    }
+
+    /// Field codes are checked against the fields of study, they are kept only for reviewers
+    async fn encode_fields_of_study(
+        db: &DbConn,
+        role: AdminRole,
+        fields_of_study: Vec<String>,
+    ) -> Result<Option<String>, ServiceError> {
+        if role != AdminRole::Reviewer {
+            return Ok(None);
+        }
+        let codes: Vec<String> = Query::list_fields_of_study(db).await?
+            .into_iter()
+            .map(|f| f.code)
+            .collect();
+        if !fields_of_study.iter().all(|f| codes.contains(f)) {
+            return Err(ServiceError::InvalidFieldOfStudy);
+        }
+
+        Ok(Some(serde_json::to_string(&fields_of_study).map_err(|_| ServiceError::FormatError)?))
+    }
+
+    /// Admins can't change their own role, so there is always someone able to manage admins
+    pub async fn set_admin_role(
+        db: &DbConn,
+        acting_admin_id: i32,
+        admin_id: i32,
+        role: AdminRole,
+        fields_of_study: Vec<String>,
+    ) -> Result<AdminResponse, ServiceError> {
+        if admin_id == acting_admin_id {
+            return Err(ServiceError::Forbidden);
+        }
+        let admin = Self::find_admin(db, admin_id).await?;
+
+        let fields_of_study = Self::encode_fields_of_study(db, role, fields_of_study).await?;
+        let admin = Mutation::update_admin_role(db, admin, role, fields_of_study).await?;
+
+        AuditService::log(db, Some(acting_admin_id), None, None, AuditAction::SetAdminRole, Some(format!("admin={} role={}", admin_id, role.as_str()))).await?;
+        Ok(admin.into())
+    }
+
+    /// First admin of a new installation (cli), fails when there already is one unless forced.
+    /// Existing candidates are re-encrypted for the new admin when the private key of another admin is given,
+    /// the admin is removed again when the re-encryption fails.
+    pub async fn bootstrap_admin(
+        db: &DbConn,
+        name: String,
+        password: String,
+        force: bool,
+        admin_private_key: Option<&String>,
+    ) -> Result<admin::Model, ServiceError> {
+        if !force && !Query::list_admins(db).await?.is_empty() {
+            return Err(ServiceError::UserAlreadyExists);
+        }
+
+        let Some(admin_private_key) = admin_private_key else {
+            return Self::create_admin(db, name, password, AdminRole::Superadmin, false).await;
+        };
+
+        let admin = Self::create_admin(db, name, password, AdminRole::Superadmin, true).await?;
+        if let Err(e) = Self::activate_pending_admins(db, admin_private_key).await {
+            Mutation::delete_admin(db, admin).await?;
+            return Err(e);
+        }
+        Self::find_admin(db, admin.id).await
+    }
+
+    /// Creates a pending admin with a generated password, the admin can log in
+    /// once `cli admin activate` re-encrypts the existing candidates to their key
+    pub async fn add_admin(
+        db: &DbConn,
+        acting_admin_id: i32,
+        name: String,
+        role: AdminRole,
+        fields_of_study: Vec<String>,
+    ) -> Result<CreateAdminResponse, ServiceError> {
+        let fields_of_study = Self::encode_fields_of_study(db, role, fields_of_study).await?;
+
+        let password = crypto::random_12_char_string();
+        let mut admin = Self::create_admin(db, name, password.to_owned(), role, true).await?;
+        if fields_of_study.is_some() {
+            admin = Mutation::update_admin_role(db, admin, role, fields_of_study).await?;
+        }
+        AuditService::log(db, Some(acting_admin_id), None, None, AuditAction::CreateAdmin, Some(format!("admin={} role={}", admin.id, role.as_str()))).await?;
+
+        Ok(CreateAdminResponse {
+            id: admin.id,
+            name: admin.name,
+            password,
+        })
+    }
+
+    /// Disabled admin is logged out and can't log in until enabled again, admins can't disable themselves
+    pub async fn set_admin_disabled(
+        db: &DbConn,
+        acting_admin_id: i32,
+        admin_id: i32,
+        disabled: bool,
+    ) -> Result<AdminResponse, ServiceError> {
+        if admin_id == acting_admin_id {
+            return Err(ServiceError::Forbidden);
+        }
+        let admin = Self::find_admin(db, admin_id).await?;
+
+        let (disabled_at, action) = if disabled {
+            (Some(chrono::Local::now().naive_local()), AuditAction::DisableAdmin)
+        } else {
+            (None, AuditAction::EnableAdmin)
+        };
+        for session in Query::find_related_admin_sessions(db, &admin).await? {
+            Mutation::delete_session(db, session.into_active_model()).await?;
+        }
+        let admin = Mutation::update_admin_disabled_at(db, admin, disabled_at).await?;
+
+        AuditService::log(db, Some(acting_admin_id), None, None, action, Some(format!("admin={}", admin_id))).await?;
+        Ok(admin.into())
+    }
+
+    /// Re-encrypts all candidates without the admin's public key and deletes the admin.
+    /// The key is retired first, so it isn't used for new data and the admin can't log in,
+    /// an interrupted delete leaves the admin listed with a retired key and can be run again.
+    pub async fn delete_admin(
+        db: &DbConn,
+        acting_admin_id: i32,
+        admin_private_key: &String,
+        admin_id: i32,
+    ) -> Result<(), ServiceError> {
+        if admin_id == acting_admin_id {
+            return Err(ServiceError::Forbidden);
+        }
+        let admin = Self::find_admin(db, admin_id).await?;
+
+        for session in Query::find_related_admin_sessions(db, &admin).await? {
+            Mutation::delete_session(db, session.into_active_model()).await?;
+        }
+        let admin = match admin.key_retired_at {
+            Some(_) => admin,
+            None => Mutation::update_admin_key_retired_at(db, admin, chrono::Local::now().naive_local()).await?,
+        };
+
+        let recipients = Query::get_all_admin_public_keys(db).await?;
+        Self::reencrypt_all_candidates(db, admin_private_key, &recipients).await?;
+
+        Mutation::delete_admin(db, admin).await?;
+        AuditService::log(db, Some(acting_admin_id), None, None, AuditAction::DeleteAdmin, Some(format!("admin={}", admin_id))).await?;
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -27,41 +632,27 @@ impl AuthenticableTrait for AdminService {
     type User = admin::Model;
     type Session = admin_session::Model;
 
+    /// Login of an admin without a second factor, see `AdminService::login_with_second_factor`.
+    /// Returns the session id and the secret of the session key slot.
     async fn login(
         db: &DbConn,
         admin_id: i32,
         password: String,
         ip_addr: String,
+        user_agent: Option<String>,
     ) -> Result<(String, String), ServiceError> {
-        let admin = Query::find_admin_by_id(db, admin_id).await?.ok_or(ServiceError::InvalidCredentials)?;
-        println!("admin is {:?}", admin);
-        let session_id = Self::new_session(db,
-            &admin,
-            password.clone(),
-            ip_addr
-        )
-            .await?;
-        println!("session id is {:?}", session_id);
-        
-        //let private_key = Self::decrypt_private_key(db, admin.id, password).await?;
-        let private_key = "13".to_string();
-        //println!("private key is {:?}", private_key);
-        Ok((session_id, private_key))
+        Self::login_with_second_factor(db, admin_id, password, None, ip_addr, user_agent).await
     }
 
     async fn auth(db: &DbConn, session_uuid: Uuid) -> Result<admin::Model, ServiceError> {
-        let session = Query::find_admin_session_by_uuid(db, session_uuid)
-            .await?
-            .ok_or(ServiceError::Unauthorized)?;
-
-        if !SessionService::is_valid(&session).await? {
-            Mutation::delete_session(db, session.into_active_model()).await?;
-            return Err(ServiceError::ExpiredSession);
-        }
+        let session = Self::find_valid_session(db, session_uuid).await?;
 
         let admin = Query::find_admin_by_id(db, session.admin_id)
             .await?
             .ok_or(ServiceError::CandidateNotFound)?;
+        if admin.disabled_at.is_some() || admin.key_retired_at.is_some() {
+            return Err(ServiceError::Unauthorized);
+        }
 
         Ok(admin)
     }
@@ -71,24 +662,15 @@ impl AuthenticableTrait for AdminService {
         Ok(())
     }
 
+    /// Secret of the session key slot is returned only by `login`
     async fn new_session(
         db: &DbConn,
         admin: &admin::Model,
         password: String,
         ip_addr: String,
+        user_agent: Option<String>,
     ) -> Result<String, ServiceError> {
-        if !crypto::verify_password(password.clone(), admin.password.clone()).await? {
-            println!("crypto couldn't verify that password");
-            return Err(ServiceError::InvalidCredentials);
-        }
-        // user is authenticated, generate a new session
-        let random_uuid: Uuid = Uuid::new_v4();
-
-        let session = Mutation::insert_admin_session(db, admin.id, random_uuid, ip_addr).await?;
-
-        Self::delete_old_sessions(db, &admin, 1).await?;
-
-        Ok(session.id.to_string())
+        Ok(Self::create_session(db, admin, password, ip_addr, user_agent).await?.0)
     }
     async fn delete_old_sessions(
         db: &DbConn,
@@ -130,6 +712,7 @@ pub mod admin_tests {
             private_key: Set(enc_priv_key),
             // should be password hash
             password: Set("admin".to_string()),
+            role: Set("superadmin".to_string()),
             created_at: Set(Utc::now().naive_utc()),
             updated_at: Set(Utc::now().naive_utc()),
             ..Default::default()
@@ -152,6 +735,7 @@ pub mod admin_tests {
             private_key: Set("5KCEGk0ueWVGnu5Xo3rmpLoilcVZ2ZWmwIcdZEJ8rrBNW7jwzZU/XTcTXtk/xyy/zjF8s+YnuVpOklQvX3EC/Sn+ZwyPY3jokM2RNwnZZlnqdehOEV1SMm/Y".to_owned()),
             // test
             password: Set("$argon2i$v=19$m=6000,t=3,p=10$WE9xCQmmWdBK82R4SEjoqA$TZSc6PuLd4aWK2x2WAb+Lm9sLySqjK3KLbNyqyQmzPQ".to_owned()),
+            role: Set("superadmin".to_string()),
             created_at: Set(Local::now().naive_local()),
             updated_at: Set(Local::now().naive_local()),
             ..Default::default()
@@ -159,15 +743,199 @@ pub mod admin_tests {
             .insert(&db)
             .await?;
 
-        let (session_id, _private_key) = AdminService::login(&db, admin.id, "test".to_owned(), "127.0.0.1".to_owned()).await?;
+        let (session_id, secret) = AdminService::login(&db, admin.id, "test".to_owned(), "127.0.0.1".to_owned(), None).await?;
 
         let logged_admin = AdminService::auth(&db, session_id.parse().unwrap()).await?;
 
         assert_eq!(logged_admin.id, 1);
         assert_eq!(logged_admin.name, "Admin");
-        
+
+        let (_, private_key) = AdminService::auth_with_private_key(&db, session_id.parse().unwrap(), secret).await?;
+        assert_eq!(private_key, "AGE-SECRET-KEY-14QG24502DMUUQDT2SPMX2YXPSES0X8UD6NT0PCTDAT6RH8V5Q3GQGSRXPS");
+
+        // key slot can't be opened without the secret from the client
+        assert!(AdminService::auth_with_private_key(&db, session_id.parse().unwrap(), crypto::random_secret()).await.is_err());
+
+        // legacy private key and password hash are upgraded by the login
+        let admin = Query::find_admin_by_id(&db, admin.id).await?.unwrap();
+        assert!(!crypto::is_legacy_ciphertext(&admin.private_key));
+        assert!(!crypto::password_needs_rehash(&admin.password));
+        let (session_id, secret) = AdminService::login(&db, admin.id, "test".to_owned(), "127.0.0.1".to_owned(), None).await?;
+        let (_, private_key) = AdminService::auth_with_private_key(&db, session_id.parse().unwrap(), secret).await?;
+        assert_eq!(private_key, "AGE-SECRET-KEY-14QG24502DMUUQDT2SPMX2YXPSES0X8UD6NT0PCTDAT6RH8V5Q3GQGSRXPS");
 
         Ok(())
 
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_rotate_candidate_keys() {
+        use crate::{services::candidate_service::tests::put_user_data, models::candidate_details::EncryptedApplicationDetails};
+
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let (application, candidate, _) = put_user_data(&db).await;
+
+        let old_private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        let (new_public_key, new_private_key) = crypto::create_identity();
+
+        AdminService::rotate_candidate_keys(&db, &old_private_key, candidate.id, &vec![new_public_key]).await.unwrap();
+
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let parents = Query::find_candidate_parents(&db, &candidate).await.unwrap();
+        let application = Query::find_application_by_id(&db, application.id).await.unwrap().unwrap();
+
+        assert!(EncryptedApplicationDetails::from((&candidate, &parents)).decrypt(new_private_key.to_owned()).await.is_ok());
+        assert!(EncryptedApplicationDetails::from((&candidate, &parents)).decrypt(old_private_key.to_owned()).await.is_err());
+        assert!(EncryptedString::from(application.personal_id_number.to_owned()).decrypt(&new_private_key).await.is_ok());
+
+        // candidate keeps access to their own data
+        let application_private_key = crypto::decrypt_password(application.private_key, "test".to_string()).await.unwrap();
+        assert!(EncryptedApplicationDetails::from((&candidate, &parents)).decrypt(application_private_key).await.is_ok());
+    }
+
+    #[tokio::test]
+    #[serial_test::serial]
+    async fn test_resume_candidate_key_rotation() {
+        use tokio::io::AsyncReadExt;
+        use crate::{services::candidate_service::tests::put_user_data, utils::filetype::ZipManifest};
+
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let (_, candidate, _) = put_user_data(&db).await;
+
+        let temp_dir = std::env::temp_dir().join("portfolio_test_tempdir").join("rotate_resume");
+        tokio::fs::create_dir_all(temp_dir.join(candidate.id.to_string()).join("cache")).await.unwrap();
+        std::env::set_var("PORTFOLIO_STORE_PATH", temp_dir.to_str().unwrap());
+
+        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0], &ZipManifest::default()).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &"".to_string()).await.unwrap();
+
+        let old_private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        let (new_public_key, new_private_key) = crypto::create_identity();
+        let recipients = vec![new_public_key];
+
+        AdminService::rotate_candidate_data_keys(&db, &old_private_key, candidate.id, &recipients).await.unwrap();
+        // portfolio step fails, the portfolio is left as it was
+        assert!(AdminService::rotate_candidate_portfolio_keys(&db, &new_private_key, candidate.id, &recipients).await.is_err());
+        assert!(PortfolioService::get_portfolio(candidate.id, old_private_key.to_owned()).await.is_ok());
+
+        // rows are already rotated, the rotation resumes with the portfolio
+        assert!(AdminService::rotate_candidate_data_keys(&db, &old_private_key, candidate.id, &recipients).await.is_err());
+        AdminService::rotate_candidate_portfolio_keys(&db, &old_private_key, candidate.id, &recipients).await.unwrap();
+
+        let mut portfolio = vec![];
+        PortfolioService::get_portfolio(candidate.id, new_private_key).await.unwrap()
+            .read_to_end(&mut portfolio)
+            .await
+            .unwrap();
+        assert!(!portfolio.is_empty());
+
+        tokio::fs::remove_dir_all(temp_dir).await.unwrap();
+        std::env::remove_var("PORTFOLIO_STORE_PATH");
+    }
+
+    #[tokio::test]
+    async fn test_manage_admins() {
+        use crate::{services::candidate_service::tests::put_user_data, models::candidate_details::EncryptedApplicationDetails};
+
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let (_, candidate, _) = put_user_data(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key.to_owned(), "admin".to_string()).await.unwrap();
+
+        assert!(matches!(
+            AdminService::bootstrap_admin(&db, "second".to_string(), "test".to_string(), false, None).await,
+            Err(ServiceError::UserAlreadyExists)
+        ));
+
+        // failed re-encryption doesn't leave a new admin behind
+        assert!(AdminService::bootstrap_admin(&db, "second".to_string(), "test".to_string(), true, Some(&"invalid key".to_string())).await.is_err());
+        assert_eq!(AdminService::list_admins(&db).await.unwrap().len(), 1);
+
+        // new admin is pending until the candidates are re-encrypted to their key
+        let created = AdminService::add_admin(&db, admin.id, "new".to_string(), AdminRole::Staff, vec![]).await.unwrap();
+        assert!(AdminService::activate_pending_admins(&db, &"invalid key".to_string()).await.is_err());
+        assert!(matches!(
+            AdminService::login(&db, created.id, created.password.to_owned(), "127.0.0.1".to_string(), None).await,
+            Err(ServiceError::InvalidCredentials)
+        ));
+
+        // new admin can read data of existing candidates
+        let activated = AdminService::activate_pending_admins(&db, &private_key).await.unwrap();
+        assert_eq!(activated.iter().map(|a| a.id).collect::<Vec<i32>>(), vec![created.id]);
+        assert!(AdminService::activate_pending_admins(&db, &private_key).await.unwrap().is_empty());
+        let new_admin = Query::find_admin_by_id(&db, created.id).await.unwrap().unwrap();
+        let new_private_key = crypto::decrypt_password(new_admin.private_key, created.password.to_owned()).await.unwrap();
+
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let parents = Query::find_candidate_parents(&db, &candidate).await.unwrap();
+        assert!(EncryptedApplicationDetails::from((&candidate, &parents)).decrypt(new_private_key.to_owned()).await.is_ok());
+        assert_eq!(AdminService::list_admins(&db).await.unwrap().len(), 2);
+
+        // disabled admin can't log in
+        assert!(matches!(
+            AdminService::set_admin_disabled(&db, admin.id, admin.id, true).await,
+            Err(ServiceError::Forbidden)
+        ));
+        AdminService::login(&db, created.id, created.password.to_owned(), "127.0.0.1".to_string(), None).await.unwrap();
+        let disabled = AdminService::set_admin_disabled(&db, admin.id, created.id, true).await.unwrap();
+        assert!(disabled.disabled_at.is_some());
+        assert!(Query::find_related_admin_sessions(&db, &Query::find_admin_by_id(&db, created.id).await.unwrap().unwrap()).await.unwrap().is_empty());
+        assert!(matches!(
+            AdminService::login(&db, created.id, created.password.to_owned(), "127.0.0.1".to_string(), None).await,
+            Err(ServiceError::InvalidCredentials)
+        ));
+        AdminService::set_admin_disabled(&db, admin.id, created.id, false).await.unwrap();
+        assert!(AdminService::login(&db, created.id, created.password.to_owned(), "127.0.0.1".to_string(), None).await.is_ok());
+
+        // deleted admin loses access
+        AdminService::delete_admin(&db, admin.id, &private_key, created.id).await.unwrap();
+        assert!(Query::find_admin_by_id(&db, created.id).await.unwrap().is_none());
+        let candidate = Query::find_candidate_by_id(&db, candidate.id).await.unwrap().unwrap();
+        let parents = Query::find_candidate_parents(&db, &candidate).await.unwrap();
+        assert!(EncryptedApplicationDetails::from((&candidate, &parents)).decrypt(new_private_key).await.is_err());
+        assert!(EncryptedApplicationDetails::from((&candidate, &parents)).decrypt(private_key).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_retire_admin_keys() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key.to_owned(), "admin".to_string()).await.unwrap();
+        let created = AdminService::add_admin(&db, admin.id, "new".to_string(), AdminRole::Superadmin, vec![]).await.unwrap();
+        AdminService::activate_pending_admins(&db, &private_key).await.unwrap();
+        let new_admin = Query::find_admin_by_id(&db, created.id).await.unwrap().unwrap();
+
+        // at least one admin has to keep access
+        let (public_key, _) = crypto::create_identity();
+        assert!(matches!(
+            AdminService::retire_admin_keys(&db, &vec![public_key]).await,
+            Err(ServiceError::InvalidPublicKey)
+        ));
+
+        AdminService::login(&db, created.id, created.password.to_owned(), "127.0.0.1".to_string(), None).await.unwrap();
+        let retired = AdminService::retire_admin_keys(&db, &vec![admin.public_key.to_owned()]).await.unwrap();
+        assert_eq!(retired.iter().map(|a| a.id).collect::<Vec<i32>>(), vec![created.id]);
+        assert_eq!(Query::get_all_admin_public_keys(&db).await.unwrap(), vec![admin.public_key.to_owned()]);
+        assert!(Query::find_related_admin_sessions(&db, &new_admin).await.unwrap().is_empty());
+        assert!(matches!(
+            AdminService::login(&db, created.id, created.password, "127.0.0.1".to_string(), None).await,
+            Err(ServiceError::InvalidCredentials)
+        ));
+
+        // running it again retires nothing more
+        assert!(AdminService::retire_admin_keys(&db, &vec![admin.public_key]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rotate_candidate_keys_invalid_recipient() {
+        let db = get_memory_sqlite_connection().await;
+
+        assert!(
+            AdminService::rotate_candidate_keys(&db, &"".to_string(), 1, &vec!["not a key".to_string()]).await.is_err()
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use entity::admission_round;
+use sea_orm::{DbConn, IntoActiveModel, Set};
+
+use crate::{error::ServiceError, Query, Mutation, models::{admission_round::{AdmissionRoundRequest, AdmissionRoundResponse}, school::School}};
+
+pub struct AdmissionRoundService;
+
+impl AdmissionRoundService {
+    pub async fn list(db: &DbConn) -> Result<Vec<AdmissionRoundResponse>, ServiceError> {
+        let rounds = Query::list_admission_rounds(db).await?;
+
+        Ok(
+            rounds.into_iter()
+                .map(AdmissionRoundResponse::from)
+                .collect()
+        )
+    }
+
+    pub async fn create(db: &DbConn, request: AdmissionRoundRequest) -> Result<AdmissionRoundResponse, ServiceError> {
+        Self::validate_request(db, &request).await?;
+
+        let round = Mutation::insert_admission_round(db, admission_round::ActiveModel {
+            date: Set(request.date),
+            label: Set(request.label),
+            field_of_study_id: Set(request.field_of_study_id),
+            ..Default::default()
+        }).await?;
+
+        Ok(
+            AdmissionRoundResponse::from(round)
+        )
+    }
+
+    pub async fn update(db: &DbConn, id: i32, request: AdmissionRoundRequest) -> Result<AdmissionRoundResponse, ServiceError> {
+        Self::validate_request(db, &request).await?;
+
+        let mut round = Query::find_admission_round_by_id(db, id)
+            .await?
+            .ok_or(ServiceError::AdmissionRoundNotFound)?
+            .into_active_model();
+        round.date = Set(request.date);
+        round.label = Set(request.label);
+        round.field_of_study_id = Set(request.field_of_study_id);
+
+        let round = Mutation::update_admission_round(db, round).await?;
+
+        Ok(
+            AdmissionRoundResponse::from(round)
+        )
+    }
+
+    pub async fn delete(db: &DbConn, id: i32) -> Result<(), ServiceError> {
+        let round = Query::find_admission_round_by_id(db, id)
+            .await?
+            .ok_or(ServiceError::AdmissionRoundNotFound)?;
+
+        Mutation::delete_admission_round(db, round).await?;
+        Ok(())
+    }
+
+    /// Schools linked to a round by the candidate have to point to an existing round
+    pub async fn validate_schools(db: &DbConn, schools: &[&School]) -> Result<(), ServiceError> {
+        for round_id in schools.iter().filter_map(|s| s.admission_round()) {
+            if Query::find_admission_round_by_id(db, round_id).await?.is_none() {
+                return Err(ServiceError::AdmissionRoundNotFound);
+            }
+        }
+        Ok(())
+    }
+
+    async fn validate_request(db: &DbConn, request: &AdmissionRoundRequest) -> Result<(), ServiceError> {
+        request.validate_self()?;
+
+        if let Some(field_id) = request.field_of_study_id {
+            Query::find_field_of_study_by_id(db, field_id)
+                .await?
+                .ok_or(ServiceError::InvalidFieldOfStudy)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::{utils::db::get_memory_sqlite_connection, models::admission_round::AdmissionRoundRequest};
+
+    use super::AdmissionRoundService;
+
+    fn request(day: u32, label: &str, field_of_study_id: Option<i32>) -> AdmissionRoundRequest {
+        AdmissionRoundRequest {
+            date: NaiveDate::from_ymd_opt(2024, 4, day).unwrap(),
+            label: label.to_string(),
+            field_of_study_id,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_admission_round_crud() {
+        let db = get_memory_sqlite_connection().await;
+
+        let second = AdmissionRoundService::create(&db, request(17, "17. 4.", None)).await.unwrap();
+        let first = AdmissionRoundService::create(&db, request(16, "16. 4.", None)).await.unwrap();
+
+        let rounds = AdmissionRoundService::list(&db).await.unwrap();
+        assert_eq!(rounds.iter().map(|r| r.id).collect::<Vec<_>>(), vec![first.id, second.id]);
+
+        let updated = AdmissionRoundService::update(&db, second.id, request(18, "18. 4.", None)).await.unwrap();
+        assert_eq!(updated.label, "18. 4.");
+
+        AdmissionRoundService::delete(&db, first.id).await.unwrap();
+        assert_eq!(AdmissionRoundService::list(&db).await.unwrap().len(), 1);
+        assert!(AdmissionRoundService::delete(&db, first.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_admission_round_invalid_field_of_study() {
+        let db = get_memory_sqlite_connection().await;
+
+        assert!(AdmissionRoundService::create(&db, request(16, "16. 4.", Some(9999))).await.is_err());
+        assert!(AdmissionRoundService::create(&db, request(16, "", None)).await.is_err());
+    }
+}
@@ -2,13 +2,12 @@ use async_trait::async_trait;
 use chrono::Duration;
 use entity::{candidate, parent, application, session};
 use log::warn;
-use sea_orm::{DbConn, prelude::Uuid, IntoActiveModel};
+use sea_orm::{ConnectionTrait, DbConn, prelude::Uuid, IntoActiveModel};
+use validator::Validate;
 
-use crate::{error::ServiceError, Query, utils::db::get_recipients, models::candidate_details::EncryptedApplicationDetails, models::{candidate::{ApplicationDetails, CreateCandidateResponse}, candidate_details::{EncryptedString, EncryptedCandidateDetails}, auth::AuthenticableTrait, application::ApplicationResponse}, Mutation, crypto::{hash_password, self}};
+use crate::{error::ServiceError, Query, utils::db::get_recipients, models::candidate_details::EncryptedApplicationDetails, models::{candidate::{ApplicationDetails, CreateCandidateResponse}, candidate_details::{EncryptedString, EncryptedCandidateDetails}, auth::{AuthenticableTrait, NewPassword, SessionResponse}, application::ApplicationResponse}, Mutation, crypto::{hash_password, self, BlindIndexKey}, notification::Template, models::calendar::Phase, models::audit::AuditAction, models::throttle::ThrottleKey};
 
-use super::{parent_service::ParentService, candidate_service::CandidateService, session_service::SessionService, portfolio_service::{PortfolioService, SubmissionProgress}};
-
-const FIELD_OF_STUDY_PREFIXES: [&str; 3] = ["101", "102", "103"];
+use super::{admission_round_service::AdmissionRoundService, audit_service::AuditService, calendar_service::CalendarService, notification_service::NotificationService, parent_service::ParentService, candidate_service::CandidateService, login_throttle_service::LoginThrottleService, session_service::SessionService, portfolio_service::{PortfolioService, SubmissionProgress}};
 
 pub struct ApplicationService;
 
@@ -19,14 +18,14 @@ impl ApplicationService {
     /// Encrypted private key
     /// Public key
     pub async fn create(
-        admin_private_key: &String,
         db: &DbConn,
         application_id: i32,
         plain_text_password: &String,
         personal_id_number: String,
+        email: Option<String>,
     ) -> Result<(application::Model, Vec<application::Model>, String), ServiceError> {
-        // Check if application id starts with 101, 102 or 103
-        if !Self::is_application_id_valid(application_id) {
+        // Check if application id starts with prefix of one of the fields of study
+        if !Self::is_application_id_valid(db, application_id).await? {
             println!("invalid app id");
             return Err(ServiceError::InvalidApplicationId);
         }
@@ -52,7 +51,6 @@ impl ApplicationService {
 
         let (candidate, enc_personal_id_number) = Self::find_or_create_candidate_with_personal_id(
             application_id,
-            admin_private_key,
             db,
             &personal_id_number,
             &pubkey,
@@ -80,6 +78,14 @@ impl ApplicationService {
             }
             return Err(ServiceError::InternalServerError);
         }
+
+        if let Some(email) = email {
+            NotificationService::notify(vec![email], Template::AccountCreated {
+                application_id: application.id,
+                password: plain_text_password.to_string(),
+            }).await;
+        }
+
         Ok(
             /* NewCandidateResponse {
                 current_application: application.id,
@@ -102,59 +108,36 @@ impl ApplicationService {
 
     async fn find_or_create_candidate_with_personal_id(
         application_id: i32,
-        admin_private_key: &String,
         db: &DbConn,
         personal_id_number: &String,
         pubkey: &String,
         // enc_personal_id_number: &EncryptedString,
     ) -> Result<(candidate::Model, String), ServiceError> {
-        println!("doing some wacky shit");
-        let candidates = Query::list_candidates_full(db).await?;
-        let ids_decrypted = futures::future::join_all(
-        candidates.iter().map(|c| async {(
-                c.id,
-                EncryptedString::from(c.personal_identification_number.clone())
-                    .decrypt(admin_private_key)
-                    .await
-                    .unwrap_or_default(),
-            )}
-        ))
-            .await;
-
-        println!("doing some more wacky shit");
-
-        let found_ids: Vec<&(i32, String)> = ids_decrypted
-            .iter()
-            .filter(|(_, id)| id == personal_id_number)
-            .collect();
+        let key = BlindIndexKey::from_env()?;
+        let candidate = CandidateService::find_by_personal_id(db, &key, personal_id_number).await?;
 
-        println!("doing even more wacky shit");
-            
-        if let Some((candidate_id, _)) = found_ids.first() {
-            println!("a");
+        if let Some(candidate) = candidate {
             Ok(
                 Self::find_linkable_candidate(db, 
                     application_id,
-                    *candidate_id,
+                    candidate.id,
                     pubkey,
                     personal_id_number.to_owned()
                 ).await?
             )
         } else {
-            println!("b");
             let recipients = get_recipients(db, pubkey).await?;
-            println!("c");
             let enc_personal_id_number = EncryptedString::new(
                 personal_id_number,
                 &recipients,
             ).await?;
 
-            println!("d");
-            let s = enc_personal_id_number.to_owned().to_string();
-            println!("stringy string is {:?}", s.clone());
             Ok(
                 (
-                    CandidateService::create(db, s).await?,
+                    CandidateService::create(db,
+                        enc_personal_id_number.to_string(),
+                        key.personal_id_number(personal_id_number),
+                    ).await?,
                     enc_personal_id_number.to_string(),
                 )
             )
@@ -221,14 +204,12 @@ impl ApplicationService {
         Ok(())
     }
 
-    fn is_application_id_valid(application_id: i32) -> bool {
-        let s = &application_id.to_string();
-        if s.len() <= 3 {
-            // TODO: does the field of study prefix have to be exactly 6 digits? VYRESIT PODLE PRIHLASEK!!!
-            return false;
-        }
-        let field_of_study_prefix = &s[0..3];
-        FIELD_OF_STUDY_PREFIXES.contains(&field_of_study_prefix)
+    async fn is_application_id_valid(db: &DbConn, application_id: i32) -> Result<bool, ServiceError> {
+        Ok(
+            Query::find_field_of_study_by_application_id(db, application_id)
+                .await?
+                .is_some()
+        )
     }
 
     pub async fn find_related_candidate(
@@ -249,6 +230,9 @@ impl ApplicationService {
         candidate: candidate::Model,
         form: &ApplicationDetails,
     ) -> Result<(candidate::Model, Vec<parent::Model>), ServiceError> {
+        CalendarService::check(&candidate, Phase::Details)?;
+        AdmissionRoundService::validate_schools(db, &[&form.candidate.first_school, &form.candidate.second_school]).await?;
+
         let mut recipients = Query::get_all_admin_public_keys(db).await?;
         let applications = Query::find_applications_by_candidate_id(db, candidate.id).await?;
         recipients.append(&mut applications.iter().map(|a| a.public_key.to_owned()).collect());
@@ -285,10 +269,11 @@ impl ApplicationService {
         private_key: &String,
         db: &DbConn,
         field_of_study: Option<String>,
+        allowed_fields: Option<Vec<String>>,
         page: Option<u64>,
         sort: Option<String>,
     ) -> Result<Vec<ApplicationResponse>, ServiceError> {
-        let applications = Query::list_applications(db, field_of_study, page, sort).await?;
+        let applications = Query::list_applications(db, field_of_study, allowed_fields, page, sort).await?;
 
         futures::future::try_join_all(
             applications
@@ -316,6 +301,103 @@ impl ApplicationService {
         Ok(private_key)
     }
 
+    /// Verifies the password and stores the unlocked private key in the key slot of a new session.
+    /// Returns the session id and the secret of its key slot.
+    async fn create_session(
+        db: &DbConn,
+        application: &application::Model,
+        password: String,
+        ip_addr: String,
+        user_agent: Option<String>,
+    ) -> Result<(String, String), ServiceError> {
+        if !crypto::verify_password(password.clone(), application.password.clone()).await? {
+            return Err(ServiceError::InvalidCredentials);
+        }
+        if crypto::password_needs_rehash(&application.password) {
+            let password_hash = crypto::hash_password(password.clone()).await?;
+            Mutation::update_application_password_hash(db, application.to_owned(), password_hash).await?;
+        }
+        // user is authenticated, generate a new session
+        let private_key = Self::decrypt_private_key(application.to_owned(), password.clone()).await?;
+        if crypto::is_legacy_ciphertext(&application.private_key) {
+            let enc_priv_key = crypto::encrypt_password(private_key.clone(), password).await?;
+            Mutation::update_application_private_key(db, application.to_owned(), enc_priv_key).await?;
+        }
+        let (key_slot, secret) = SessionService::seal_private_key(private_key).await?;
+        let random_uuid: Uuid = Uuid::new_v4();
+
+        let session = Mutation::insert_candidate_session(db, random_uuid, application.id, ip_addr, user_agent, key_slot).await?;
+
+        Self::delete_old_sessions(db, &application, 3).await?;
+
+        Ok((session.id.to_string(), secret))
+    }
+
+    /// Finds the session and extends it, expired session is deleted together with its key slot
+    async fn find_valid_session(db: &DbConn, session_uuid: Uuid) -> Result<session::Model, ServiceError> {
+        let session = Query::find_session_by_uuid(db, session_uuid)
+            .await?
+            .ok_or(ServiceError::Unauthorized)?;
+
+        if !SessionService::is_valid(&session).await? {
+            Mutation::delete_session(db, session.into_active_model()).await?;
+            return Err(ServiceError::ExpiredSession);
+        }
+        // Candidate authenticated
+
+        let session = Self::extend_session_duration_to_14_days(db, session).await?;
+        if SessionService::should_update_last_seen(session.last_seen_at) {
+            return Ok(Mutation::update_session_last_seen(db, session).await?);
+        }
+        Ok(session)
+    }
+
+    /// Active sessions of the application, `current` marks the session of the request
+    pub async fn list_sessions(
+        db: &DbConn,
+        application: &application::Model,
+        current: Option<Uuid>,
+    ) -> Result<Vec<SessionResponse>, ServiceError> {
+        let sessions = Query::find_related_application_sessions(db, application).await?;
+
+        Ok(
+            sessions.into_iter()
+                .map(|s| SessionResponse::from_candidate_session(s, current))
+                .collect()
+        )
+    }
+
+    /// Logs out one session of the application, e.g. on a lost device
+    pub async fn revoke_session(
+        db: &DbConn,
+        application: &application::Model,
+        session_id: Uuid,
+    ) -> Result<(), ServiceError> {
+        let session = Query::find_session_by_uuid(db, session_id)
+            .await?
+            .filter(|s| s.candidate_id == application.id)
+            .ok_or(ServiceError::SessionNotFound)?;
+
+        Mutation::delete_session(db, session.into_active_model()).await?;
+        Ok(())
+    }
+
+    /// Authenticates the session and unlocks the candidate private key from its key slot
+    pub async fn auth_with_private_key(
+        db: &DbConn,
+        session_uuid: Uuid,
+        secret: String,
+    ) -> Result<(application::Model, String), ServiceError> {
+        let session = Self::find_valid_session(db, session_uuid).await?;
+        let private_key = SessionService::open_private_key(session.key_slot.to_owned(), secret).await?;
+
+        let application = Query::find_application_by_id(db, session.candidate_id)
+            .await?
+            .ok_or(ServiceError::CandidateNotFound)?;
+
+        Ok((application, private_key))
+    }
+
     pub async fn extend_session_duration_to_14_days(db: &DbConn, session: session::Model) -> Result<session::Model, ServiceError> {
         let now = chrono::Utc::now().naive_utc();
         if now >= session.updated_at.checked_add_signed(Duration::days(1)).ok_or(ServiceError::Unauthorized)? {
@@ -375,11 +457,19 @@ impl ApplicationService {
         if PortfolioService::get_submission_progress(candidate.id).await? == SubmissionProgress::Submitted {
             PortfolioService::reencrypt_portfolio(
                 candidate.id,
-                admin_private_key,
+                admin_private_key.to_owned(),
                 &recipients
             ).await?;
         }
 
+        NotificationService::notify(
+            NotificationService::candidate_emails(&candidate, &admin_private_key).await,
+            Template::PasswordReset {
+                application_id: id,
+                password: new_password_plain.to_owned(),
+            }
+        ).await;
+
         Ok(
             CreateCandidateResponse {
                 application_id: id,
@@ -393,7 +483,46 @@ impl ApplicationService {
         )
     }
 
-    async fn update_all_application_details(db: &DbConn,
+    /// Candidate's own password change
+    /// Existing private key is only re-wrapped with the new password, so no data needs to be re-encrypted
+    /// All other sessions of the application are invalidated and the change is recorded in the audit log
+    /// Wrong old passwords count towards the login lockout of the application
+    pub async fn change_password(
+        db: &DbConn,
+        application: application::Model,
+        session_id: Uuid,
+        old_password: String,
+        new_password: String,
+    ) -> Result<application::Model, ServiceError> {
+        let throttle_keys = [ThrottleKey::Candidate(application.id)];
+        LoginThrottleService::attempt(db, &throttle_keys, async {
+            if !crypto::verify_password(old_password.clone(), application.password.clone()).await? {
+                return Err(ServiceError::InvalidCredentials);
+            }
+            Ok(())
+        }).await?;
+        NewPassword { password: new_password.clone() }.validate()?;
+
+        let private_key = Self::decrypt_private_key(application.clone(), old_password).await?;
+        let new_password_hash = crypto::hash_password(new_password.clone()).await?;
+        let encrypted_priv_key = crypto::encrypt_password(private_key, new_password).await?;
+
+        let sessions = Query::find_related_application_sessions(db, &application).await?;
+        for session in sessions.into_iter().filter(|s| s.id != session_id) {
+            Mutation::delete_session(db, session.into_active_model()).await?;
+        }
+
+        let application = Mutation::update_application_password(db,
+            application,
+            new_password_hash,
+            encrypted_priv_key,
+        ).await?;
+
+        AuditService::log(db, None, Some(application.candidate_id), Some(application.id), AuditAction::ChangePassword, None).await?;
+        Ok(application)
+    }
+
+    pub(in crate::services) async fn update_all_application_details<C: ConnectionTrait>(db: &C,
          application_id: i32,
          candidate: candidate::Model,
          recipients: &Vec<String>,
@@ -435,29 +564,17 @@ impl AuthenticableTrait for ApplicationService {
         application_id: i32,
         password: String,
         ip_addr: String,
+        user_agent: Option<String>,
     ) -> Result<(String, String), ServiceError> {
         let application = Query::find_application_by_id(db, application_id)
             .await?
             .ok_or(ServiceError::CandidateNotFound)?;
 
-        let session_id = Self::new_session(db, &application, password.clone(), ip_addr).await?;
-
-        let private_key = Self::decrypt_private_key(application, password).await?;
-        Ok((session_id, private_key))
+        Self::create_session(db, &application, password, ip_addr, user_agent).await
     }
 
     async fn auth(db: &DbConn, session_uuid: Uuid) -> Result<application::Model, ServiceError> {
-        let session = Query::find_session_by_uuid(db, session_uuid)
-            .await?
-            .ok_or(ServiceError::Unauthorized)?;
-
-        if !SessionService::is_valid(&session).await? {
-            Mutation::delete_session(db, session.into_active_model()).await?;
-            return Err(ServiceError::ExpiredSession);
-        }
-        // Candidate authenticated
-
-        Self::extend_session_duration_to_14_days(db, session.clone()).await?;
+        let session = Self::find_valid_session(db, session_uuid).await?;
 
         let application = Query::find_application_by_id(db, session.candidate_id)
             .await?
@@ -471,23 +588,15 @@ impl AuthenticableTrait for ApplicationService {
         Ok(())
     }
 
+    /// Secret of the session key slot is returned only by `login`
     async fn new_session(
         db: &DbConn,
         application: &application::Model,
         password: String,
         ip_addr: String,
+        user_agent: Option<String>,
     ) -> Result<String, ServiceError> {
-        if !crypto::verify_password(password.clone(), application.password.clone()).await? {
-            return Err(ServiceError::InvalidCredentials);
-        }
-        // user is authenticated, generate a new session
-        let random_uuid: Uuid = Uuid::new_v4();
-
-        let session = Mutation::insert_candidate_session(db, random_uuid, application.id, ip_addr).await?;
-
-        Self::delete_old_sessions(db, &application, 3).await?;
-
-        Ok(session.id.to_string())
+        Ok(Self::create_session(db, application, password, ip_addr, user_agent).await?.0)
     }
     async fn delete_old_sessions(
         db: &DbConn,
@@ -507,18 +616,22 @@ impl AuthenticableTrait for ApplicationService {
 
 #[cfg(test)]
 mod application_tests {
-    use crate::{services::{application_service::ApplicationService, candidate_service::tests::put_user_data}, utils::db::get_memory_sqlite_connection, crypto, models::auth::AuthenticableTrait};
+    use crate::{services::{application_service::ApplicationService, candidate_service::tests::put_user_data}, utils::db::get_memory_sqlite_connection, crypto, models::auth::AuthenticableTrait, Mutation, Query};
     use crate::services::admin_service::admin_tests::create_admin;
+    use crate::{error::ServiceError, models::{audit::AuditLogFilter, throttle::ThrottleKey}, services::{audit_service::AuditService, login_throttle_service::LoginThrottleService}};
+    use sea_orm::prelude::Uuid;
 
     #[tokio::test]
     async fn test_application_id_validation() {
-        assert!(ApplicationService::is_application_id_valid(101_101));
-        assert!(ApplicationService::is_application_id_valid(102_107));
-        assert!(ApplicationService::is_application_id_valid(103_109));
-        assert!(!ApplicationService::is_application_id_valid(104_109));
-        assert!(!ApplicationService::is_application_id_valid(100_109));
-        assert!(!ApplicationService::is_application_id_valid(201_109));
-        assert!(!ApplicationService::is_application_id_valid(101));
+        let db = get_memory_sqlite_connection().await;
+
+        assert!(ApplicationService::is_application_id_valid(&db, 101_101).await.unwrap());
+        assert!(ApplicationService::is_application_id_valid(&db, 102_107).await.unwrap());
+        assert!(ApplicationService::is_application_id_valid(&db, 103_109).await.unwrap());
+        assert!(!ApplicationService::is_application_id_valid(&db, 104_109).await.unwrap());
+        assert!(!ApplicationService::is_application_id_valid(&db, 100_109).await.unwrap());
+        assert!(!ApplicationService::is_application_id_valid(&db, 201_109).await.unwrap());
+        assert!(!ApplicationService::is_application_id_valid(&db, 101).await.unwrap());
     }
 
     #[tokio::test]
@@ -530,17 +643,93 @@ mod application_tests {
         let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
 
         assert!(
-            ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string()).await.is_ok()
+            ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.is_ok()
         );
 
         let new_password = ApplicationService::reset_password(private_key, &db, application.id).await.unwrap().password;
 
         assert!(
-            ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string()).await.is_err()
+            ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.is_err()
         );
         
         assert!(
-            ApplicationService::login(&db, application.id, new_password, "127.0.0.1".to_string()).await.is_ok()
+            ApplicationService::login(&db, application.id, new_password, "127.0.0.1".to_string(), None).await.is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_change_password() {
+        let db = get_memory_sqlite_connection().await;
+        create_admin(&db).await;
+        let application = ApplicationService::create(&db, 103100, &"test".to_string(), "".to_string(), None).await.unwrap().0;
+
+        let (kept_session, secret) = ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.unwrap();
+        let (other_session, _) = ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.unwrap();
+        let kept_session = Uuid::parse_str(&kept_session).unwrap();
+        let (_, key) = ApplicationService::auth_with_private_key(&db, kept_session, secret).await.unwrap();
+
+        assert!(
+            ApplicationService::change_password(&db, application.clone(), kept_session, "wrong".to_string(), "new password".to_string()).await.is_err()
+        );
+        assert!(matches!(
+            ApplicationService::change_password(&db, application.clone(), kept_session, "test".to_string(), "new".to_string()).await,
+            Err(ServiceError::ValidationError(_))
+        ));
+
+        ApplicationService::change_password(&db, application.clone(), kept_session, "test".to_string(), "new password".to_string()).await.unwrap();
+        let audit = AuditService::list(&db, &AuditLogFilter { candidate_id: Some(application.candidate_id), ..Default::default() }).await.unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].action, "CHANGE_PASSWORD");
+
+        assert!(ApplicationService::auth(&db, kept_session).await.is_ok());
+        assert!(ApplicationService::auth(&db, Uuid::parse_str(&other_session).unwrap()).await.is_err());
+
+        assert!(
+            ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.is_err()
+        );
+        let (new_session, new_secret) = ApplicationService::login(&db, application.id, "new password".to_string(), "127.0.0.1".to_string(), None).await.unwrap();
+        let (_, new_key) = ApplicationService::auth_with_private_key(&db, Uuid::parse_str(&new_session).unwrap(), new_secret).await.unwrap();
+        assert_eq!(key, new_key);
+    }
+
+    #[tokio::test]
+    async fn test_change_password_throttled() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, _, _) = put_user_data(&db).await;
+        let (session, _) = ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.unwrap();
+        let session = Uuid::parse_str(&session).unwrap();
+
+        for _ in 0..5 {
+            assert!(matches!(
+                ApplicationService::change_password(&db, application.clone(), session, "wrong".to_string(), "new password".to_string()).await,
+                Err(ServiceError::InvalidCredentials)
+            ));
+        }
+        // locked out also for the right password and for the login
+        assert!(matches!(
+            ApplicationService::change_password(&db, application.clone(), session, "test".to_string(), "new password".to_string()).await,
+            Err(ServiceError::TooManyLoginAttempts)
+        ));
+        assert!(matches!(
+            LoginThrottleService::check(&db, &[ThrottleKey::Candidate(application.id)]).await,
+            Err(ServiceError::TooManyLoginAttempts)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_login_rehashes_outdated_password() {
+        const LEGACY_HASH: &str = "$argon2i$v=19$m=6000,t=3,p=10$WE9xCQmmWdBK82R4SEjoqA$TZSc6PuLd4aWK2x2WAb+Lm9sLySqjK3KLbNyqyQmzPQ";
+        let db = get_memory_sqlite_connection().await;
+        let (application, _, _) = put_user_data(&db).await;
+        Mutation::update_application_password_hash(&db, application.clone(), LEGACY_HASH.to_string()).await.unwrap();
+
+        ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.unwrap();
+
+        let application = Query::find_application_by_id(&db, application.id).await.unwrap().unwrap();
+        assert!(application.password.starts_with("$argon2id$"));
+        assert!(!crypto::password_needs_rehash(&application.password));
+        assert!(
+            ApplicationService::login(&db, application.id, "test".to_string(), "127.0.0.1".to_string(), None).await.is_ok()
         );
     }
 
@@ -552,7 +741,7 @@ mod application_tests {
 
         let secret_message = "trnka".to_string();
 
-        let application = ApplicationService::create(&"".to_string(), &db, 103100, &plain_text_password, "".to_string()).await.unwrap().0;
+        let application = ApplicationService::create(&db, 103100, &plain_text_password, "".to_string(), None).await.unwrap().0;
 
         let encrypted_message =
             crypto::encrypt_password_with_recipients(&secret_message, &vec![&application.public_key])
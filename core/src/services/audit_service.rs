@@ -0,0 +1,200 @@
+use chrono::{Local, NaiveDateTime, Timelike};
+use entity::audit_log;
+use log::warn;
+use sea_orm::{DbConn, Set};
+use sha2::{Digest, Sha256};
+
+use crate::{error::ServiceError, Query, Mutation, models::audit::{AuditAction, AuditLogFilter, AuditLogResponse}};
+
+/// Previous hash of the first entry in the chain
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+/// Inserts of one entry racing with other writers before giving up
+const MAX_CHAIN_ATTEMPTS: u32 = 10;
+
+pub struct AuditService;
+
+impl AuditService {
+    /// Appends new entry to the hash chained audit log
+    pub async fn log(
+        db: &DbConn,
+        admin_id: Option<i32>,
+        candidate_id: Option<i32>,
+        application_id: Option<i32>,
+        action: AuditAction,
+        detail: Option<String>,
+    ) -> Result<audit_log::Model, ServiceError> {
+        // previous hash is unique, a writer which chained to an entry another writer
+        // took in the meantime fails to insert and retries on top of the new last entry
+        let mut attempts = 0;
+        loop {
+            let prev_hash = Query::find_last_audit_log(db)
+                .await?
+                .map(|entry| entry.hash)
+                .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+            // database doesn't store fractions of second
+            let created_at = Local::now().naive_local().with_nanosecond(0).ok_or(ServiceError::InvalidDate)?;
+
+            let hash = Self::compute_hash(
+                &prev_hash,
+                admin_id,
+                candidate_id,
+                application_id,
+                action.as_str(),
+                &detail,
+                &created_at,
+            );
+
+            let inserted = Mutation::insert_audit_log(db, audit_log::ActiveModel {
+                admin_id: Set(admin_id),
+                candidate_id: Set(candidate_id),
+                application_id: Set(application_id),
+                action: Set(action.to_string()),
+                detail: Set(detail.to_owned()),
+                created_at: Set(created_at),
+                prev_hash: Set(prev_hash.to_owned()),
+                hash: Set(hash),
+                ..Default::default()
+            }).await;
+
+            attempts += 1;
+            match inserted {
+                Ok(entry) => return Ok(entry),
+                Err(e) if attempts < MAX_CHAIN_ATTEMPTS
+                    && Query::find_audit_log_by_prev_hash(db, &prev_hash).await?.is_some() => {
+                    warn!("AUDIT LOG ENTRY {} CHAINED CONCURRENTLY, RETRYING ({})", action.as_str(), e);
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    fn compute_hash(
+        prev_hash: &str,
+        admin_id: Option<i32>,
+        candidate_id: Option<i32>,
+        application_id: Option<i32>,
+        action: &str,
+        detail: &Option<String>,
+        created_at: &NaiveDateTime,
+    ) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(format!(
+            "{}|{:?}|{:?}|{:?}|{}|{:?}|{}",
+            prev_hash,
+            admin_id,
+            candidate_id,
+            application_id,
+            action,
+            detail,
+            created_at.format("%Y-%m-%d %H:%M:%S"),
+        ));
+
+        format!("{:x}", hasher.finalize())
+    }
+
+    pub async fn list(db: &DbConn, filter: &AuditLogFilter) -> Result<Vec<AuditLogResponse>, ServiceError> {
+        let entries = Query::list_audit_log(db, filter).await?;
+
+        Ok(
+            entries.into_iter()
+                .map(AuditLogResponse::from)
+                .collect()
+        )
+    }
+
+    /// Walks the whole chain, returns id of the first tampered entry or None if the chain is intact
+    pub async fn verify_chain(db: &DbConn) -> Result<Option<i32>, ServiceError> {
+        let entries = Query::list_audit_log(db, &AuditLogFilter::default()).await?;
+
+        let mut prev_hash = GENESIS_HASH.to_string();
+        for entry in entries {
+            let hash = Self::compute_hash(
+                &prev_hash,
+                entry.admin_id,
+                entry.candidate_id,
+                entry.application_id,
+                &entry.action,
+                &entry.detail,
+                &entry.created_at,
+            );
+
+            if entry.prev_hash != prev_hash || entry.hash != hash {
+                return Ok(Some(entry.id));
+            }
+            prev_hash = entry.hash;
+        }
+
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use entity::audit_log;
+    use sea_orm::{ActiveModelTrait, IntoActiveModel, Set};
+
+    use crate::{utils::db::get_memory_sqlite_connection, models::audit::{AuditAction, AuditLogFilter}};
+
+    use super::AuditService;
+
+    #[tokio::test]
+    async fn test_audit_log_chain() {
+        let db = get_memory_sqlite_connection().await;
+
+        let first = AuditService::log(&db, Some(1), Some(2), Some(103151), AuditAction::DecryptDetails, None).await.unwrap();
+        let second = AuditService::log(&db, Some(1), None, None, AuditAction::ExportCandidates, Some("csv".to_string())).await.unwrap();
+
+        assert_eq!(second.prev_hash, first.hash);
+        assert_eq!(AuditService::verify_chain(&db).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_concurrent_writers() {
+        let db = get_memory_sqlite_connection().await;
+
+        let log = |candidate_id| AuditService::log(&db, Some(1), Some(candidate_id), None, AuditAction::DecryptDetails, None);
+        let (a, b, c, d) = tokio::join!(log(1), log(2), log(3), log(4));
+        for entry in [a, b, c, d] {
+            entry.unwrap();
+        }
+
+        assert_eq!(AuditService::list(&db, &AuditLogFilter::default()).await.unwrap().len(), 4);
+        assert_eq!(AuditService::verify_chain(&db).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_tampering_detected() {
+        let db = get_memory_sqlite_connection().await;
+
+        let first = AuditService::log(&db, Some(1), Some(2), None, AuditAction::DecryptPortfolio, None).await.unwrap();
+        AuditService::log(&db, Some(1), Some(3), None, AuditAction::ResetPassword, None).await.unwrap();
+
+        let mut tampered: audit_log::ActiveModel = first.clone().into_active_model();
+        tampered.admin_id = Set(Some(2));
+        tampered.update(&db).await.unwrap();
+
+        assert_eq!(AuditService::verify_chain(&db).await.unwrap(), Some(first.id));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_filter() {
+        let db = get_memory_sqlite_connection().await;
+
+        AuditService::log(&db, Some(1), Some(2), None, AuditAction::DecryptDetails, None).await.unwrap();
+        AuditService::log(&db, Some(2), Some(3), None, AuditAction::DecryptDetails, None).await.unwrap();
+        AuditService::log(&db, Some(1), Some(3), None, AuditAction::DeleteApplication, None).await.unwrap();
+
+        let filter = AuditLogFilter {
+            admin_id: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(AuditService::list(&db, &filter).await.unwrap().len(), 2);
+
+        let filter = AuditLogFilter {
+            candidate_id: Some(3),
+            ..Default::default()
+        };
+        assert_eq!(AuditService::list(&db, &filter).await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,470 @@
+use std::path::Path;
+
+use async_compat::{Compat, CompatExt};
+use entity::{admin, admin_session, admission_round, application, audit_log, candidate, field_of_study, parent, session};
+use futures::io::{AsyncReadExt, AsyncWriteExt as _};
+use log::{info, warn};
+use sea_orm::{ActiveModelBehavior, ActiveModelTrait, ConnectionTrait, DatabaseTransaction, DbBackend, DbConn, DbErr, EntityTrait, IntoActiveModel, PaginatorTrait, Statement, TransactionTrait};
+use serde::{Serialize, de::DeserializeOwned};
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncWriteExt;
+
+use crate::{crypto, error::ServiceError, Query, models::backup::{BackupEntry, BackupManifest, BackupTable, BACKUP_MANIFEST, BACKUP_VERSION}, storage::{get_temp_path, PortfolioStore}, utils::filetype::is_safe_entry_name};
+
+/// Backed up tables in the order they are restored in, referenced tables first.
+/// Sessions are not backed up, everybody has to log in again after restore.
+const TABLES: [&str; 7] = ["admin", "field_of_study", "admission_round", "candidate", "application", "parent", "audit_log"];
+/// Tables emptied before restore (seeded rows and sessions), referencing tables first
+const CLEARED_TABLES: [&str; 9] = ["session", "admin_session", "audit_log", "parent", "application", "candidate", "admission_round", "field_of_study", "admin"];
+const DB_DIR: &str = "db";
+const FILES_DIR: &str = "files";
+const INSERT_CHUNK: usize = 100;
+const BUFFER_SIZE: usize = 64 * 1024;
+
+type ArchiveReader = async_zip::base::read::seek::ZipFileReader<Compat<tokio::fs::File>>;
+
+fn backup_error(e: impl ToString) -> ServiceError {
+    ServiceError::BackupError(e.to_string())
+}
+
+pub struct BackupService;
+
+impl BackupService {
+    /// Creates backup of the database and the portfolio store encrypted to all admins
+    /// and the extra recipients. The archive is written next to the output first,
+    /// so an interrupted backup never replaces a previous one.
+    pub async fn create(
+        db: &DbConn,
+        store: &dyn PortfolioStore,
+        output: &Path,
+        extra_recipients: &[String],
+    ) -> Result<BackupManifest, ServiceError> {
+        let mut recipients = Query::get_all_admin_public_keys(db).await?;
+        recipients.extend(extra_recipients.iter().cloned());
+        let recipients: Vec<&str> = recipients.iter().map(|r| r.as_str()).collect();
+
+        info!("BACKUP {} STARTED", output.display());
+
+        let mut manifest = BackupManifest::new(chrono::Local::now().naive_local());
+        let part_path = output.with_extension("part");
+        let result = Self::write_archive(db, store, &part_path, &recipients, &mut manifest).await;
+        if let Err(e) = result {
+            tokio::fs::remove_file(&part_path).await.ok();
+            return Err(e);
+        }
+        tokio::fs::rename(&part_path, output).await?;
+
+        info!("BACKUP {} FINISHED, {} FILES", output.display(), manifest.entries.len());
+        Ok(manifest)
+    }
+
+    /// Decrypts the backup and checks all files against the manifest, nothing is restored
+    pub async fn verify(backup: &Path, private_key: &str) -> Result<BackupManifest, ServiceError> {
+        let temp_path = get_temp_path("backup.zip");
+
+        let result = Self::open_and_verify(backup, &temp_path, private_key).await;
+
+        tokio::fs::remove_file(&temp_path).await.ok();
+        result.map(|(_, manifest)| manifest)
+    }
+
+    /// Verifies the backup and restores it into a database without candidates and into the portfolio store.
+    /// Rows seeded by migrations are replaced with the backed up ones.
+    /// Files are restored before the database is committed and deleted again when the restore fails.
+    pub async fn restore(
+        db: &DbConn,
+        backup: &Path,
+        private_key: &str,
+        store: &dyn PortfolioStore,
+    ) -> Result<BackupManifest, ServiceError> {
+        let temp_path = get_temp_path("backup.zip");
+
+        let result = Self::restore_verified(db, backup, &temp_path, private_key, store).await;
+
+        tokio::fs::remove_file(&temp_path).await.ok();
+        result
+    }
+
+    async fn restore_verified(
+        db: &DbConn,
+        backup: &Path,
+        temp_path: &Path,
+        private_key: &str,
+        store: &dyn PortfolioStore,
+    ) -> Result<BackupManifest, ServiceError> {
+        let (mut reader, manifest) = Self::open_and_verify(backup, temp_path, private_key).await?;
+
+        warn!("RESTORE OF BACKUP {} STARTED", backup.display());
+        let txn = db.begin().await?;
+        Self::restore_database(&txn, &mut reader, &manifest).await?;
+
+        // uncommitted transaction is rolled back when dropped
+        let mut restored = vec![];
+        let result = match Self::restore_files(&mut reader, &manifest, store, &mut restored).await {
+            Ok(_) => txn.commit().await.map_err(ServiceError::from),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            for key in restored {
+                store.delete(&key).await.ok();
+            }
+            return Err(e);
+        }
+
+        Self::reset_sequences(db).await?;
+        warn!("RESTORE OF BACKUP {} FINISHED", backup.display());
+
+        Ok(manifest)
+    }
+
+    async fn write_archive(
+        db: &DbConn,
+        store: &dyn PortfolioStore,
+        path: &Path,
+        recipients: &Vec<&str>,
+        manifest: &mut BackupManifest,
+    ) -> Result<(), ServiceError> {
+        let mut file = tokio::fs::File::create(path).await?;
+        let mut encrypt_writer = crypto::encrypt_writer_with_recipients(&mut file, recipients).await?;
+        let mut writer = async_zip::base::write::ZipFileWriter::new(&mut encrypt_writer);
+
+        for table in TABLES {
+            let (data, rows) = Self::dump_table_by_name(db, table).await?;
+            let path = format!("{}/{}.json", DB_DIR, table);
+
+            let builder = async_zip::ZipEntryBuilder::new(path.to_owned().into(), async_zip::Compression::Deflate);
+            writer.write_entry_whole(builder, &data).await?;
+
+            manifest.tables.push(BackupTable { name: table.to_string(), rows });
+            manifest.entries.push(BackupEntry {
+                path,
+                size: data.len() as u64,
+                sha256: format!("{:x}", Sha256::digest(&data)),
+            });
+        }
+
+        let (directories, files) = store.list("").await?;
+        manifest.directories = directories;
+        for relative_path in files {
+            let path = format!("{}/{}", FILES_DIR, relative_path);
+            // portfolios are already encrypted, compressing them would not help
+            let builder = async_zip::ZipEntryBuilder::new(path.to_owned().into(), async_zip::Compression::Stored);
+            let mut entry_writer = writer.write_entry_stream(builder).await?;
+
+            let mut input = store.reader(&relative_path).await?;
+            let mut hasher = Sha256::new();
+            let mut size = 0u64;
+            let mut buffer = vec![0u8; BUFFER_SIZE];
+            loop {
+                let read = tokio::io::AsyncReadExt::read(&mut input, &mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+                size += read as u64;
+                entry_writer.write_all(&buffer[..read]).await?;
+            }
+            entry_writer.close().await?;
+
+            manifest.entries.push(BackupEntry {
+                path,
+                size,
+                sha256: format!("{:x}", hasher.finalize()),
+            });
+        }
+
+        let manifest_data = serde_json::to_vec_pretty(manifest).map_err(backup_error)?;
+        let builder = async_zip::ZipEntryBuilder::new(BACKUP_MANIFEST.to_string().into(), async_zip::Compression::Deflate);
+        writer.write_entry_whole(builder, &manifest_data).await?;
+
+        writer.close().await?;
+        encrypt_writer.close().await?;
+        file.shutdown().await?;
+
+        Ok(())
+    }
+
+    /// Decrypts the backup into the temporary file and verifies it
+    async fn open_and_verify(backup: &Path, temp_path: &Path, private_key: &str) -> Result<(ArchiveReader, BackupManifest), ServiceError> {
+        crypto::decrypt_file_with_private_key(backup, temp_path, private_key).await?;
+
+        let file = tokio::fs::File::open(temp_path).await?;
+        let mut reader = async_zip::base::read::seek::ZipFileReader::new(file.compat()).await?;
+        let manifest = Self::verify_archive(&mut reader).await?;
+
+        Ok((reader, manifest))
+    }
+
+    fn entry_names(reader: &ArchiveReader) -> Vec<String> {
+        reader.file()
+            .entries()
+            .iter()
+            .map(|e| e.entry().filename().as_str().map(|n| n.to_string()).unwrap_or_default())
+            .collect()
+    }
+
+    fn entry_index(names: &[String], name: &str) -> Result<usize, ServiceError> {
+        names.iter()
+            .position(|n| n == name)
+            .ok_or_else(|| backup_error(format!("{} is missing", name)))
+    }
+
+    async fn read_entry(reader: &mut ArchiveReader, index: usize) -> Result<Vec<u8>, ServiceError> {
+        let mut data = vec![];
+        reader.reader_without_entry(index).await?
+            .read_to_end(&mut data).await?;
+        Ok(data)
+    }
+
+    async fn verify_archive(reader: &mut ArchiveReader) -> Result<BackupManifest, ServiceError> {
+        let names = Self::entry_names(reader);
+
+        let manifest_index = Self::entry_index(&names, BACKUP_MANIFEST)?;
+        let manifest: BackupManifest = serde_json::from_slice(&Self::read_entry(reader, manifest_index).await?)
+            .map_err(backup_error)?;
+        if manifest.version != BACKUP_VERSION {
+            return Err(backup_error(format!("unsupported backup version {}", manifest.version)));
+        }
+
+        for entry in manifest.entries.iter() {
+            let index = Self::entry_index(&names, &entry.path)?;
+
+            let mut entry_reader = reader.reader_without_entry(index).await?;
+            let mut hasher = Sha256::new();
+            let mut size = 0u64;
+            let mut buffer = vec![0u8; BUFFER_SIZE];
+            loop {
+                let read = entry_reader.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+                size += read as u64;
+            }
+
+            if size != entry.size || format!("{:x}", hasher.finalize()) != entry.sha256 {
+                return Err(backup_error(format!("{} does not match the manifest", entry.path)));
+            }
+        }
+
+        if names.len() != manifest.entries.len() + 1 {
+            return Err(backup_error("archive contains files missing in the manifest"));
+        }
+
+        Ok(manifest)
+    }
+
+    async fn restore_database(txn: &DatabaseTransaction, reader: &mut ArchiveReader, manifest: &BackupManifest) -> Result<(), ServiceError> {
+        if candidate::Entity::find().count(txn).await? > 0 {
+            return Err(backup_error("target database already contains candidates"));
+        }
+
+        let names = Self::entry_names(reader);
+        for table in CLEARED_TABLES {
+            Self::clear_table_by_name(txn, table).await?;
+        }
+        for table in manifest.tables.iter() {
+            let index = Self::entry_index(&names, &format!("{}/{}.json", DB_DIR, table.name))?;
+            let data = Self::read_entry(reader, index).await?;
+            let rows = Self::restore_table_by_name(txn, &table.name, &data).await?;
+            info!("RESTORED {} ROWS OF {}", rows, table.name);
+        }
+
+        Ok(())
+    }
+
+    /// Rows were inserted with their ids, sequences have to continue after them
+    async fn reset_sequences(db: &DbConn) -> Result<(), ServiceError> {
+        if db.get_database_backend() == DbBackend::Postgres {
+            for table in TABLES {
+                db.execute(Statement::from_string(
+                    DbBackend::Postgres,
+                    format!(
+                        r#"SELECT setval(pg_get_serial_sequence('"{0}"', 'id'), COALESCE(MAX(id), 0) + 1, false) FROM "{0}""#,
+                        table
+                    ),
+                )).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Keys of the restored files are pushed into `restored`, so they can be deleted when the restore fails
+    async fn restore_files(
+        reader: &mut ArchiveReader,
+        manifest: &BackupManifest,
+        store: &dyn PortfolioStore,
+        restored: &mut Vec<String>,
+    ) -> Result<(), ServiceError> {
+        let names = Self::entry_names(reader);
+
+        for directory in manifest.directories.iter() {
+            if !is_safe_entry_name(directory) {
+                return Err(backup_error(format!("invalid directory {}", directory)));
+            }
+            store.create_dir(directory).await?;
+        }
+
+        let prefix = format!("{}/", FILES_DIR);
+        for entry in manifest.entries.iter() {
+            let Some(relative_path) = entry.path.strip_prefix(&prefix) else {
+                continue;
+            };
+            if !is_safe_entry_name(relative_path) {
+                return Err(backup_error(format!("invalid file {}", entry.path)));
+            }
+            if let Some((parent, _)) = relative_path.rsplit_once('/') {
+                store.create_dir(parent).await?;
+            }
+
+            // written into local temporary file first, large portfolios are never held in memory
+            let temp_path = get_temp_path("restore");
+            let index = Self::entry_index(&names, &entry.path)?;
+            let entry_reader = reader.reader_without_entry(index).await?;
+            let mut output = tokio::fs::File::create(&temp_path).await?;
+            let result: std::io::Result<()> = async {
+                futures::io::copy(entry_reader, &mut output.compat_mut()).await?;
+                output.shutdown().await
+            }.await;
+            if let Err(e) = result {
+                tokio::fs::remove_file(&temp_path).await.ok();
+                return Err(e.into());
+            }
+
+            store.put_file(relative_path, &temp_path).await?;
+            restored.push(relative_path.to_string());
+        }
+
+        Ok(())
+    }
+
+    async fn dump_table_by_name(db: &DbConn, table: &str) -> Result<(Vec<u8>, usize), ServiceError> {
+        match table {
+            "admin" => Self::dump_table::<admin::Entity>(db).await,
+            "field_of_study" => Self::dump_table::<field_of_study::Entity>(db).await,
+            "admission_round" => Self::dump_table::<admission_round::Entity>(db).await,
+            "candidate" => Self::dump_table::<candidate::Entity>(db).await,
+            "application" => Self::dump_table::<application::Entity>(db).await,
+            "parent" => Self::dump_table::<parent::Entity>(db).await,
+            "audit_log" => Self::dump_table::<audit_log::Entity>(db).await,
+            _ => Err(backup_error(format!("unknown table {}", table))),
+        }
+    }
+
+    async fn dump_table<E: EntityTrait>(db: &DbConn) -> Result<(Vec<u8>, usize), ServiceError>
+    where
+        E::Model: Serialize,
+    {
+        let rows = E::find().all(db).await?;
+        let data = serde_json::to_vec(&rows).map_err(backup_error)?;
+
+        Ok((data, rows.len()))
+    }
+
+    async fn clear_table_by_name<C: ConnectionTrait>(db: &C, table: &str) -> Result<(), ServiceError> {
+        match table {
+            "session" => session::Entity::delete_many().exec(db).await?,
+            "admin_session" => admin_session::Entity::delete_many().exec(db).await?,
+            "audit_log" => audit_log::Entity::delete_many().exec(db).await?,
+            "parent" => parent::Entity::delete_many().exec(db).await?,
+            "application" => application::Entity::delete_many().exec(db).await?,
+            "candidate" => candidate::Entity::delete_many().exec(db).await?,
+            "admission_round" => admission_round::Entity::delete_many().exec(db).await?,
+            "field_of_study" => field_of_study::Entity::delete_many().exec(db).await?,
+            "admin" => admin::Entity::delete_many().exec(db).await?,
+            _ => return Err(backup_error(format!("unknown table {}", table))),
+        };
+        Ok(())
+    }
+
+    async fn restore_table_by_name<C: ConnectionTrait>(db: &C, table: &str, data: &[u8]) -> Result<usize, ServiceError> {
+        match table {
+            "admin" => Self::restore_table::<admin::ActiveModel, C>(db, data).await,
+            "field_of_study" => Self::restore_table::<field_of_study::ActiveModel, C>(db, data).await,
+            "admission_round" => Self::restore_table::<admission_round::ActiveModel, C>(db, data).await,
+            "candidate" => Self::restore_table::<candidate::ActiveModel, C>(db, data).await,
+            "application" => Self::restore_table::<application::ActiveModel, C>(db, data).await,
+            "parent" => Self::restore_table::<parent::ActiveModel, C>(db, data).await,
+            "audit_log" => Self::restore_table::<audit_log::ActiveModel, C>(db, data).await,
+            _ => Err(backup_error(format!("unknown table {}", table))),
+        }
+    }
+
+    async fn restore_table<A, C>(db: &C, data: &[u8]) -> Result<usize, ServiceError>
+    where
+        A: ActiveModelTrait + ActiveModelBehavior + Clone + Send,
+        <A::Entity as EntityTrait>::Model: IntoActiveModel<A> + DeserializeOwned,
+        C: ConnectionTrait,
+    {
+        let rows: Vec<serde_json::Value> = serde_json::from_slice(data).map_err(backup_error)?;
+        let rows = rows.into_iter()
+            .map(A::from_json)
+            .collect::<Result<Vec<A>, DbErr>>()?;
+
+        for chunk in rows.chunks(INSERT_CHUNK) {
+            A::Entity::insert_many(chunk.to_vec()).exec(db).await?;
+        }
+
+        Ok(rows.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use entity::candidate;
+    use sea_orm::{EntityTrait, PaginatorTrait};
+    use serial_test::serial;
+
+    use crate::{crypto, services::candidate_service::tests::put_user_data, storage::FsStore, utils::db::get_memory_sqlite_connection};
+
+    use super::BackupService;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_backup_restore() {
+        let db = get_memory_sqlite_connection().await;
+        let (_, candidate, _) = put_user_data(&db).await;
+        let (public_key, private_key) = crypto::create_identity();
+
+        let random_number: u32 = rand::Rng::gen(&mut rand::thread_rng());
+        let temp_dir = std::env::temp_dir().join("portfolio_test_backup").join(random_number.to_string());
+        let root_dir = temp_dir.join("data");
+        let candidate_dir = root_dir.join(candidate.id.to_string());
+        tokio::fs::create_dir_all(candidate_dir.join("cache")).await.unwrap();
+        tokio::fs::write(candidate_dir.join("PORTFOLIO.age"), b"portfolio").await.unwrap();
+
+        let backup = temp_dir.join("backup.age");
+        let manifest = BackupService::create(&db, &FsStore::new(root_dir.to_owned()), &backup, &[public_key]).await.unwrap();
+        assert!(manifest.entries.iter().any(|e| e.path == format!("files/{}/PORTFOLIO.age", candidate.id)));
+
+        assert_eq!(BackupService::verify(&backup, &private_key).await.unwrap(), manifest);
+        let (_, other_key) = crypto::create_identity();
+        assert!(BackupService::verify(&backup, &other_key).await.is_err());
+
+        // database is not restored when files can't be written
+        let db = get_memory_sqlite_connection().await;
+        let restored_dir = temp_dir.join("restored");
+        tokio::fs::write(&restored_dir, b"not a directory").await.unwrap();
+        assert!(BackupService::restore(&db, &backup, &private_key, &FsStore::new(restored_dir.to_owned())).await.is_err());
+        assert_eq!(candidate::Entity::find().count(&db).await.unwrap(), 0);
+        tokio::fs::remove_file(&restored_dir).await.unwrap();
+
+        // restore into fresh database and empty directory
+        BackupService::restore(&db, &backup, &private_key, &FsStore::new(restored_dir.to_owned())).await.unwrap();
+
+        assert_eq!(candidate::Entity::find().count(&db).await.unwrap(), 1);
+        let restored_candidate = candidate::Entity::find_by_id(candidate.id).one(&db).await.unwrap().unwrap();
+        assert_eq!(restored_candidate.name, candidate.name);
+        assert_eq!(
+            tokio::fs::read(restored_dir.join(candidate.id.to_string()).join("PORTFOLIO.age")).await.unwrap(),
+            b"portfolio"
+        );
+        assert!(tokio::fs::metadata(restored_dir.join(candidate.id.to_string()).join("cache")).await.is_ok());
+
+        // candidates are never overwritten
+        assert!(BackupService::restore(&db, &backup, &private_key, &FsStore::new(restored_dir)).await.is_err());
+
+        tokio::fs::remove_dir_all(temp_dir).await.unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::NaiveDateTime;
+use entity::candidate;
+use sea_orm::DbConn;
+
+use crate::{error::ServiceError, Mutation, models::calendar::{AdmissionsCalendar, DeadlinesResponse, Phase}};
+
+pub struct CalendarService;
+
+impl CalendarService {
+    /// Fails with `ServiceError::DeadlinePassed` when the phase is closed for the candidate
+    pub fn check(candidate: &candidate::Model, phase: Phase) -> Result<(), ServiceError> {
+        AdmissionsCalendar::from_env()?.check(
+            phase,
+            candidate.deadline_extension,
+            chrono::Local::now().naive_local()
+        )
+    }
+
+    pub fn deadlines(candidate: &candidate::Model) -> Result<DeadlinesResponse, ServiceError> {
+        let calendar = AdmissionsCalendar::from_env()?;
+
+        Ok(
+            DeadlinesResponse {
+                details_deadline: calendar.deadline(Phase::Details, candidate.deadline_extension),
+                portfolio_deadline: calendar.deadline(Phase::Portfolio, candidate.deadline_extension),
+                extended: candidate.deadline_extension.is_some(),
+            }
+        )
+    }
+
+    /// Individual extension of all deadlines, None removes the extension
+    pub(in crate::services) async fn set_extension(
+        db: &DbConn,
+        candidate: candidate::Model,
+        until: Option<NaiveDateTime>,
+    ) -> Result<candidate::Model, ServiceError> {
+        Ok(
+            Mutation::update_candidate_deadline_extension(db, candidate, until).await?
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{models::calendar::Phase, services::candidate_service::tests::put_user_data, utils::db::get_memory_sqlite_connection};
+
+    use super::CalendarService;
+
+    #[tokio::test]
+    async fn test_set_extension() {
+        let db = get_memory_sqlite_connection().await;
+        let (_, candidate, _) = put_user_data(&db).await;
+
+        let until = chrono::Local::now().naive_local() + chrono::Duration::days(7);
+        let candidate = CalendarService::set_extension(&db, candidate, Some(until)).await.unwrap();
+        assert!(CalendarService::check(&candidate, Phase::Portfolio).is_ok());
+        assert!(CalendarService::deadlines(&candidate).unwrap().extended);
+
+        let candidate = CalendarService::set_extension(&db, candidate, None).await.unwrap();
+        assert!(!CalendarService::deadlines(&candidate).unwrap().extended);
+    }
+}
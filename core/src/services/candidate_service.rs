@@ -1,10 +1,12 @@
 use entity::candidate;
-use sea_orm::DbConn;
+use log::warn;
+use sea_orm::{DbConn, DbErr};
 
 use crate::{
-    models::{candidate_details::EncryptedCandidateDetails, candidate::CandidateDetails},
+    crypto::BlindIndexKey,
+    models::{candidate_details::{EncryptedCandidateDetails, EncryptedString}, candidate::CandidateDetails},
     error::ServiceError,
-    Mutation,
+    Mutation, Query,
 };
 
 use super::{portfolio_service::PortfolioService};
@@ -20,12 +22,16 @@ impl CandidateService {
     pub(in crate::services) async fn create(
         db: &DbConn,
         enc_personal_id_number: String,
+        personal_id_index: String,
     ) -> Result<candidate::Model, ServiceError> {
-        let candidate = Mutation::create_candidate(
+        let candidate = match Mutation::create_candidate(
             db,
             enc_personal_id_number,
-        )
-            .await?;
+            Some(personal_id_index.to_owned()),
+        ).await {
+            Ok(candidate) => candidate,
+            Err(e) => return Err(Self::duplicate_index_error(db, &personal_id_index, None, e).await),
+        };
         
         PortfolioService::create_user_dir(candidate.id).await?;
 
@@ -33,6 +39,84 @@ impl CandidateService {
         Ok(candidate)
     }
 
+    /// Finds the candidate by the blind index of the personal identification number.
+    /// Candidates created before blind indexes are indexed once by `cli blind_index`, see `check_personal_id_index`
+    pub(in crate::services) async fn find_by_personal_id(
+        db: &DbConn,
+        key: &BlindIndexKey,
+        personal_id_number: &str,
+    ) -> Result<Option<candidate::Model>, ServiceError> {
+        let index = key.personal_id_number(personal_id_number);
+        Ok(Query::find_candidate_by_personal_id_index(db, &index).await?)
+    }
+
+    /// Fails while some candidates have no blind index, they would not be found by the personal identification number
+    pub async fn check_personal_id_index(db: &DbConn) -> Result<(), ServiceError> {
+        let count = Query::count_candidates_without_personal_id_index(db).await?;
+        if count > 0 {
+            warn!("{} CANDIDATES HAVE NO PERSONAL ID INDEX, RUN cli blind_index", count);
+            return Err(ServiceError::MissingPersonalIdIndex);
+        }
+        Ok(())
+    }
+
+    async fn update_personal_id_index(
+        db: &DbConn,
+        key: &BlindIndexKey,
+        admin_private_key: &String,
+        candidate: candidate::Model,
+    ) -> Result<candidate::Model, ServiceError> {
+        let personal_id_number = EncryptedString::from(candidate.personal_identification_number.to_owned())
+            .decrypt(admin_private_key)
+            .await?;
+
+        let index = key.personal_id_number(&personal_id_number);
+        let candidate_id = candidate.id;
+        match Mutation::update_personal_id_index(db, candidate, index.to_owned()).await {
+            Ok(candidate) => Ok(candidate),
+            Err(e) => Err(Self::duplicate_index_error(db, &index, Some(candidate_id), e).await),
+        }
+    }
+
+    /// The blind index is unique, a failed write of an index which another candidate already has
+    /// means the same person exists, e.g. created by a concurrent import
+    async fn duplicate_index_error(
+        db: &DbConn,
+        personal_id_index: &str,
+        candidate_id: Option<i32>,
+        e: DbErr,
+    ) -> ServiceError {
+        match Query::find_candidate_by_personal_id_index(db, personal_id_index).await {
+            Ok(Some(other)) if Some(other.id) != candidate_id => ServiceError::UserAlreadyExists,
+            _ => e.into(),
+        }
+    }
+
+    /// Computes blind indexes of candidates created before they were introduced (cli),
+    /// `all` recomputes every index, e.g. after changing the key. Returns the number of updated candidates.
+    pub async fn backfill_personal_id_index(
+        db: &DbConn,
+        admin_private_key: &String,
+        all: bool,
+    ) -> Result<usize, ServiceError> {
+        let key = BlindIndexKey::from_env()?;
+        let candidates = if all {
+            Query::list_candidates_full(db).await?
+        } else {
+            Query::list_candidates_without_personal_id_index(db).await?
+        };
+
+        let count = candidates.len();
+        for candidate in candidates {
+            let candidate_id = candidate.id;
+            if let Err(e) = Self::update_personal_id_index(db, &key, admin_private_key, candidate).await {
+                warn!("CANDIDATE {} PERSONAL ID INDEX FAILED: {}", candidate_id, e);
+                return Err(e);
+            }
+        }
+        Ok(count)
+    }
+
     pub async fn delete_candidate(db: &DbConn, candidate: candidate::Model) -> Result<(), ServiceError> {
         PortfolioService::delete_candidate_root(candidate.id).await?;
 
@@ -72,6 +156,8 @@ pub mod tests {
 
     use crate::services::application_service::ApplicationService;
 
+    use super::CandidateService;
+
     const APPLICATION_ID: i32 = 103151;
 
     #[tokio::test]
@@ -79,13 +165,17 @@ pub mod tests {
         let db = get_memory_sqlite_connection().await;
         let admin = create_admin(&db).await;
         let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
-        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None).await.unwrap();
+        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None, None).await.unwrap();
         assert_eq!(candidates.len(), 0);
 
         put_user_data(&db).await;
 
-        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None).await.unwrap();
+        let candidates = ApplicationService::list_applications(&private_key, &db, None, None, None, None).await.unwrap();
         assert_eq!(candidates.len(), 1);
+
+        let allowed = |fields: &[&str]| Some(fields.iter().map(|f| f.to_string()).collect());
+        assert_eq!(ApplicationService::list_applications(&private_key, &db, None, allowed(&["KB"]), None, None).await.unwrap().len(), 1);
+        assert!(ApplicationService::list_applications(&private_key, &db, None, allowed(&["G", "IT"]), None, None).await.unwrap().is_empty());
     }
 
     #[cfg(test)]
@@ -94,11 +184,11 @@ pub mod tests {
 
         let plain_text_password = "test".to_string();
         let application = ApplicationService::create(
-            &"".to_string(),
             db,
             APPLICATION_ID,
             &plain_text_password,
-            "0000001111".to_string()
+            "0000001111".to_string(),
+            None,
         ).await.unwrap().0;
 
         let candidate= ApplicationService::find_related_candidate(db, &application).await.unwrap();
@@ -117,6 +207,45 @@ pub mod tests {
         )
     }
 
+    #[tokio::test]
+    async fn test_find_by_personal_id() {
+        use crate::{crypto::BlindIndexKey, models::candidate_details::EncryptedString, services::import_service::ImportService, Mutation};
+
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        let key = BlindIndexKey::from_env().unwrap();
+
+        // candidate created before blind indexes were introduced
+        let enc_personal_id_number = EncryptedString::new("7360285163", &vec![admin.public_key]).await.unwrap();
+        let legacy = Mutation::create_candidate(&db, enc_personal_id_number.to_string(), None).await.unwrap();
+
+        // not found by the lookup and refused by the import until the backfill
+        assert!(CandidateService::find_by_personal_id(&db, &key, "736028/5163").await.unwrap().is_none());
+        let import = ImportService::import_candidates(&db, Some(admin.id), b"application_id,personal_id_number\n", &vec![]).await;
+        assert!(matches!(import, Err(crate::error::ServiceError::MissingPersonalIdIndex)));
+        assert!(CandidateService::check_personal_id_index(&db).await.is_err());
+
+        assert_eq!(CandidateService::backfill_personal_id_index(&db, &private_key, false).await.unwrap(), 1);
+        CandidateService::check_personal_id_index(&db).await.unwrap();
+        let found = CandidateService::find_by_personal_id(&db, &key, "736028/5163").await.unwrap();
+        assert_eq!(found.map(|c| c.id), Some(legacy.id));
+
+        let application = ApplicationService::create(&db, APPLICATION_ID, &"test".to_string(), "0000001111".to_string(), None)
+            .await
+            .unwrap()
+            .0;
+        let found = CandidateService::find_by_personal_id(&db, &key, "0000001111").await.unwrap();
+        assert_eq!(found.map(|c| c.id), Some(application.candidate_id));
+        assert!(CandidateService::find_by_personal_id(&db, &key, "0000001112").await.unwrap().is_none());
+
+        // a concurrent create of the same candidate loses on the unique index
+        let duplicate = CandidateService::create(&db, enc_personal_id_number.to_string(), key.personal_id_number("0000001111")).await;
+        assert!(matches!(duplicate, Err(crate::error::ServiceError::UserAlreadyExists)));
+
+        assert_eq!(CandidateService::backfill_personal_id_index(&db, &private_key, true).await.unwrap(), 2);
+    }
+
     #[tokio::test]
     async fn test_put_user_data() {
         let db = get_memory_sqlite_connection().await;
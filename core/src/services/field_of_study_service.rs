@@ -0,0 +1,95 @@
+use entity::field_of_study;
+use sea_orm::{DbConn, IntoActiveModel, Set};
+
+use crate::{error::ServiceError, Query, Mutation, models::field_of_study::{FieldOfStudyResponse, FieldsOfStudy}};
+
+pub struct FieldOfStudyService;
+
+impl FieldOfStudyService {
+    pub async fn load(db: &DbConn) -> Result<FieldsOfStudy, ServiceError> {
+        Ok(
+            FieldsOfStudy::from(Query::list_fields_of_study(db).await?)
+        )
+    }
+
+    pub async fn list(db: &DbConn) -> Result<Vec<FieldOfStudyResponse>, ServiceError> {
+        let fields = Query::list_fields_of_study(db).await?;
+
+        Ok(
+            fields.into_iter()
+                .map(FieldOfStudyResponse::from)
+                .collect()
+        )
+    }
+
+    /// Creates a field of study or updates the one with the same prefix
+    pub async fn save(db: &DbConn, field: FieldOfStudyResponse) -> Result<FieldOfStudyResponse, ServiceError> {
+        field.validate_self()?;
+
+        let saved = match Query::find_field_of_study_by_prefix(db, &field.prefix).await? {
+            Some(existing) => {
+                let mut existing = existing.into_active_model();
+                existing.code = Set(field.code);
+                existing.name = Set(field.name);
+                existing.capacity = Set(field.capacity);
+                Mutation::update_field_of_study(db, existing).await?
+            },
+            None => {
+                let new = field_of_study::ActiveModel {
+                    prefix: Set(field.prefix),
+                    code: Set(field.code),
+                    name: Set(field.name),
+                    capacity: Set(field.capacity),
+                    ..Default::default()
+                };
+                Mutation::insert_field_of_study(db, new).await?
+            },
+        };
+
+        Ok(
+            FieldOfStudyResponse::from(saved)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{utils::db::get_memory_sqlite_connection, models::field_of_study::FieldOfStudyResponse, services::application_service::ApplicationService};
+
+    use super::FieldOfStudyService;
+
+    #[tokio::test]
+    async fn test_save_field_of_study() {
+        let db = get_memory_sqlite_connection().await;
+
+        // Next year's programme
+        let field = FieldOfStudyResponse {
+            prefix: "104".to_string(),
+            code: "EL".to_string(),
+            name: "2641M01-Elektrotechnika".to_string(),
+            capacity: 30,
+        };
+        FieldOfStudyService::save(&db, field.clone()).await.unwrap();
+
+        let application = ApplicationService::create(&db, 104151, &"test".to_string(), "".to_string(), None).await.unwrap().0;
+        assert_eq!(application.field_of_study, "EL");
+
+        FieldOfStudyService::save(&db, FieldOfStudyResponse { capacity: 60, ..field }).await.unwrap();
+        let fields = FieldOfStudyService::list(&db).await.unwrap();
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields.iter().find(|f| f.prefix == "104").unwrap().capacity, 60);
+    }
+
+    #[tokio::test]
+    async fn test_save_invalid_prefix() {
+        let db = get_memory_sqlite_connection().await;
+
+        let field = FieldOfStudyResponse {
+            prefix: "1a".to_string(),
+            code: "EL".to_string(),
+            name: "2641M01-Elektrotechnika".to_string(),
+            capacity: 30,
+        };
+        assert!(FieldOfStudyService::save(&db, field).await.is_err());
+    }
+}
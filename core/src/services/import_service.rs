@@ -0,0 +1,172 @@
+use log::error;
+use sea_orm::DbConn;
+
+use crate::{crypto::{self, BlindIndexKey}, error::ServiceError, Query, utils::csv::parse_candidate_import, models::{audit::AuditAction, candidate::{CandidateImportRow, ImportReport, ImportRowResult, ImportStatus}}};
+
+use super::{application_service::ApplicationService, audit_service::AuditService, candidate_service::CandidateService};
+
+pub struct ImportService;
+
+impl ImportService {
+    /// Creates applications from a CSV with `application_id`, `personal_id_number` and optional `email` columns.
+    /// Applications which already exist for the same candidate are skipped, so the import can be run again.
+    /// Generated passwords are returned as a CSV encrypted for the recipients, also when the audit entry can't be written.
+    pub async fn import_candidates(
+        db: &DbConn,
+        admin_id: Option<i32>,
+        data: &[u8],
+        recipients: &Vec<String>,
+    ) -> Result<ImportReport, ServiceError> {
+        let key = BlindIndexKey::from_env()?;
+        // existing candidates are looked up only by the blind index
+        CandidateService::check_personal_id_index(db).await?;
+        let rows = parse_candidate_import(data)?;
+
+        let mut results = vec![];
+        let mut passwords = csv::Writer::from_writer(vec![]);
+        passwords.write_record(["application_id", "password"])?;
+
+        for (line, row) in rows {
+            let result = match row {
+                Ok(row) => {
+                    let application_id = row.application_id;
+                    match Self::import_row(db, &key, row).await {
+                        Ok((status, password)) => {
+                            if let Some(password) = password {
+                                passwords.write_record([application_id.to_string(), password])?;
+                            }
+                            ImportRowResult { line, application_id: Some(application_id), status, reason: None }
+                        },
+                        Err(e) => ImportRowResult {
+                            line,
+                            application_id: Some(application_id),
+                            status: ImportStatus::Rejected,
+                            reason: Some(e.error_code().to_string()),
+                        },
+                    }
+                },
+                Err(e) => ImportRowResult {
+                    line,
+                    application_id: None,
+                    status: ImportStatus::Rejected,
+                    reason: Some(e.error_code().to_string()),
+                },
+            };
+            results.push(result);
+        }
+
+        let passwords = passwords.into_inner()
+            .map_err(|_| ServiceError::CsvIntoInnerError)?;
+        let report = ImportReport {
+            results,
+            passwords: crypto::encrypt_buffer_with_recipients(&passwords, recipients).await?,
+        };
+
+        // accounts are already created, failing here would lose their passwords for good
+        if let Err(e) = AuditService::log(db, admin_id, None, None, AuditAction::ImportCandidates, Some(report.summary())).await {
+            error!("CANDIDATE IMPORT BY ADMIN {:?} NOT AUDITED ({}): {}", admin_id, report.summary(), e);
+        }
+        Ok(report)
+    }
+
+    /// Returns the status of the row and the password of a created application
+    async fn import_row(
+        db: &DbConn,
+        key: &BlindIndexKey,
+        row: CandidateImportRow,
+    ) -> Result<(ImportStatus, Option<String>), ServiceError> {
+        if row.personal_id_number.is_empty() {
+            return Err(ServiceError::InvalidImportRow);
+        }
+
+        if let Some(application) = Query::find_application_by_id(db, row.application_id).await? {
+            let candidate = CandidateService::find_by_personal_id(db, key, &row.personal_id_number).await?;
+            return match candidate {
+                Some(candidate) if candidate.id == application.candidate_id => Ok((ImportStatus::Existing, None)),
+                _ => Err(ServiceError::UserAlreadyExists),
+            };
+        }
+
+        let password = crypto::random_12_char_string();
+        let (_, applications, _) = ApplicationService::create(
+            db,
+            row.application_id,
+            &password,
+            row.personal_id_number,
+            row.email.filter(|e| !e.is_empty()),
+        ).await?;
+
+        let status = if applications.len() > 1 {
+            ImportStatus::Linked
+        } else {
+            ImportStatus::Created
+        };
+        Ok((status, Some(password)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crypto, models::candidate::{ImportResponse, ImportStatus}, services::admin_service::admin_tests::create_admin, utils::db::get_memory_sqlite_connection};
+
+    use super::ImportService;
+
+    const IMPORT: &str = "application_id;personal_id_number;email
+101101;0101010000;
+102102;0101010000;
+103103;0202020000;
+104104;0303030000;
+not a number;0404040000;
+103104;;
+";
+
+    #[tokio::test]
+    async fn test_import_candidates() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        let recipients = vec![admin.public_key];
+
+        let report = ImportService::import_candidates(&db, Some(admin.id), IMPORT.as_bytes(), &recipients).await.unwrap();
+        let statuses: Vec<ImportStatus> = report.results.iter().map(|r| r.status).collect();
+        assert_eq!(statuses, vec![
+            ImportStatus::Created,
+            ImportStatus::Linked,
+            ImportStatus::Created,
+            ImportStatus::Rejected,
+            ImportStatus::Rejected,
+            ImportStatus::Rejected,
+        ]);
+        assert_eq!(report.results[3].reason.as_deref(), Some("INVALID_APPLICATION_ID"));
+        assert_eq!(report.results[4].reason.as_deref(), Some("INVALID_IMPORT_ROW"));
+        assert_eq!(report.results[4].line, 6);
+
+        let response = ImportResponse::from(report);
+        let passwords = crypto::decrypt_password_with_private_key(&response.passwords, &private_key).await.unwrap();
+        assert_eq!(passwords.lines().count(), 4);
+        assert!(passwords.contains("102102,"));
+
+        // second run doesn't create anything, an application id taken by someone else is rejected
+        let rerun = format!("{}103103;0909090000;\n", IMPORT);
+        let report = ImportService::import_candidates(&db, Some(admin.id), rerun.as_bytes(), &recipients).await.unwrap();
+        assert_eq!(report.count(ImportStatus::Existing), 3);
+        assert_eq!(report.count(ImportStatus::Created) + report.count(ImportStatus::Linked), 0);
+        assert_eq!(report.results.last().unwrap().reason.as_deref(), Some("USER_ALREADY_EXISTS"));
+    }
+
+    #[tokio::test]
+    async fn test_import_without_audit_log() {
+        use sea_orm::{ConnectionTrait, Statement};
+
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        db.execute(Statement::from_string(db.get_database_backend(), "DROP TABLE audit_log".to_string())).await.unwrap();
+
+        // passwords of the created accounts are returned even though the import isn't audited
+        let report = ImportService::import_candidates(&db, Some(admin.id), IMPORT.as_bytes(), &vec![admin.public_key]).await.unwrap();
+        let response = ImportResponse::from(report);
+        let passwords = crypto::decrypt_password_with_private_key(&response.passwords, &private_key).await.unwrap();
+        assert_eq!(passwords.lines().count(), 4);
+    }
+}
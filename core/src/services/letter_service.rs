@@ -0,0 +1,174 @@
+use sea_orm::DbConn;
+
+use crate::{crypto, error::ServiceError, Query, models::{audit::AuditAction, field_of_study::FieldsOfStudy, throttle::ThrottleKey}, utils::{csv::parse_credentials, letter::{render_letters, CredentialLetter, LetterConfig}}};
+
+use super::{audit_service::AuditService, login_throttle_service::LoginThrottleService};
+
+pub struct LetterService;
+
+impl LetterService {
+    /// PDF with a credential letter for each application id and password, in the given order.
+    /// Passwords aren't stored anywhere, so they have to be the ones just returned by creating
+    /// the candidate, resetting the password or the import. Each password is checked against
+    /// the application, so a mistyped one fails instead of printing a wrong letter.
+    /// Wrong passwords count as failed logins of the application and the admin, every request is audited.
+    pub async fn credential_letters(
+        db: &DbConn,
+        admin_id: Option<i32>,
+        credentials: Vec<(i32, String)>,
+    ) -> Result<Vec<u8>, ServiceError> {
+        let applications = credentials.iter()
+            .map(|(application_id, _)| application_id.to_string())
+            .collect::<Vec<String>>()
+            .join(",");
+
+        let result = Self::render_credential_letters(db, admin_id, credentials).await;
+
+        let outcome = match &result {
+            Ok(_) => "PRINTED",
+            Err(e) => e.error_code(),
+        };
+        AuditService::log(db, admin_id, None, None, AuditAction::PrintLetters,
+            Some(format!("applications={} result={}", applications, outcome)),
+        ).await?;
+        result
+    }
+
+    async fn render_credential_letters(
+        db: &DbConn,
+        admin_id: Option<i32>,
+        credentials: Vec<(i32, String)>,
+    ) -> Result<Vec<u8>, ServiceError> {
+        let config = LetterConfig::from_env();
+        if credentials.is_empty() {
+            return Err(ServiceError::NoCredentials);
+        }
+        if credentials.len() > config.max_letters {
+            return Err(ServiceError::TooManyLetters);
+        }
+        let fields = FieldsOfStudy::from(Query::list_fields_of_study(db).await?);
+
+        let mut letters = vec![];
+        for (application_id, password) in credentials {
+            let mut throttle_keys = vec![ThrottleKey::Candidate(application_id)];
+            throttle_keys.extend(admin_id.map(ThrottleKey::Admin));
+            LoginThrottleService::check(db, &throttle_keys).await?;
+
+            let application = Query::find_application_by_id(db, application_id)
+                .await?
+                .ok_or(ServiceError::CandidateNotFound)?;
+            if !crypto::verify_password(password.to_owned(), application.password.to_owned()).await? {
+                LoginThrottleService::record_failure(db, &throttle_keys).await?;
+                return Err(ServiceError::InvalidLetterPassword);
+            }
+
+            letters.push(CredentialLetter {
+                application_id,
+                field_of_study: fields.by_application_id(application_id)
+                    .map(|f| f.name.to_owned())
+                    .unwrap_or(application.field_of_study),
+                password,
+            });
+        }
+
+        render_letters(&letters, &config)
+    }
+
+    pub async fn credential_letter(
+        db: &DbConn,
+        admin_id: Option<i32>,
+        application_id: i32,
+        password: String,
+    ) -> Result<Vec<u8>, ServiceError> {
+        Self::credential_letters(db, admin_id, vec![(application_id, password)]).await
+    }
+
+    /// Letters of the applications created by an import, `passwords` is the base64 encoded age file
+    /// returned by the import, decrypted with the admin's private key
+    pub async fn import_letters(
+        db: &DbConn,
+        admin_id: Option<i32>,
+        private_key: &String,
+        passwords: &str,
+    ) -> Result<Vec<u8>, ServiceError> {
+        let passwords = crypto::decrypt_password_with_private_key(passwords, private_key).await?;
+        let credentials = parse_credentials(passwords.as_bytes())?;
+
+        Self::credential_letters(db, admin_id, credentials).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crypto, error::ServiceError, models::{audit::AuditLogFilter, candidate::ImportResponse}, services::{admin_service::admin_tests::create_admin, application_service::ApplicationService, audit_service::AuditService, import_service::ImportService}, utils::{csv::parse_credentials, db::get_memory_sqlite_connection, filetype::filetype_is_pdf}};
+
+    use super::LetterService;
+
+    #[tokio::test]
+    async fn test_credential_letters() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        ApplicationService::create(&db, 101101, &"abcdefghijkl".to_string(), "0101010000".to_string(), None).await.unwrap();
+        ApplicationService::create(&db, 103101, &"mnopqrstuvwx".to_string(), "0202020000".to_string(), None).await.unwrap();
+
+        let letter = LetterService::credential_letter(&db, Some(admin.id), 101101, "abcdefghijkl".to_string()).await.unwrap();
+        assert!(filetype_is_pdf(&letter));
+
+        let credentials = parse_credentials(b"application_id,password\n101101,abcdefghijkl\n103101,mnopqrstuvwx\n").unwrap();
+        assert_eq!(credentials[1], (103101, "mnopqrstuvwx".to_string()));
+        let letters = LetterService::credential_letters(&db, Some(admin.id), credentials).await.unwrap();
+        assert!(filetype_is_pdf(&letters));
+
+        assert!(matches!(
+            LetterService::credential_letter(&db, Some(admin.id), 102101, "abcdefghijkl".to_string()).await,
+            Err(ServiceError::CandidateNotFound)
+        ));
+        assert!(matches!(
+            LetterService::credential_letters(&db, Some(admin.id), vec![]).await,
+            Err(ServiceError::NoCredentials)
+        ));
+        let too_many = (0..501).map(|i| (101101 + i, "abcdefghijkl".to_string())).collect();
+        assert!(matches!(
+            LetterService::credential_letters(&db, Some(admin.id), too_many).await,
+            Err(ServiceError::TooManyLetters)
+        ));
+
+        // every request is audited
+        let entries = AuditService::list(&db, &AuditLogFilter::default()).await.unwrap();
+        assert_eq!(entries.iter().filter(|e| e.action == "PRINT_LETTERS").count(), 5);
+        assert!(entries.iter().any(|e| e.detail.as_deref() == Some("applications=101101,103101 result=PRINTED")));
+    }
+
+    #[tokio::test]
+    async fn test_credential_letters_throttled() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        ApplicationService::create(&db, 101101, &"abcdefghijkl".to_string(), "0101010000".to_string(), None).await.unwrap();
+
+        for _ in 0..5 {
+            assert!(matches!(
+                LetterService::credential_letter(&db, Some(admin.id), 101101, "wrong password".to_string()).await,
+                Err(ServiceError::InvalidLetterPassword)
+            ));
+        }
+        // guessing is locked out like a login, even with the right password
+        assert!(matches!(
+            LetterService::credential_letter(&db, Some(admin.id), 101101, "abcdefghijkl".to_string()).await,
+            Err(ServiceError::TooManyLoginAttempts)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_import_letters() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = create_admin(&db).await;
+        let private_key = crypto::decrypt_password(admin.private_key, "admin".to_string()).await.unwrap();
+        let import = b"application_id;personal_id_number\n101101;0101010000\n103101;0202020000\n";
+
+        let report = ImportService::import_candidates(&db, Some(admin.id), import, &vec![admin.public_key]).await.unwrap();
+        let response = ImportResponse::from(report);
+
+        let letters = LetterService::import_letters(&db, Some(admin.id), &private_key, &response.passwords).await.unwrap();
+        assert!(filetype_is_pdf(&letters));
+    }
+}
@@ -0,0 +1,146 @@
+use std::future::Future;
+
+use sea_orm::DbConn;
+
+use crate::{error::ServiceError, Query, Mutation, models::{audit::AuditAction, throttle::{LockoutResponse, LoginThrottle, ThrottleKey}}};
+
+use super::audit_service::AuditService;
+
+pub struct LoginThrottleService;
+
+impl LoginThrottleService {
+    fn is_failure(e: &ServiceError) -> bool {
+        matches!(e,
+            ServiceError::InvalidCredentials
+            | ServiceError::InvalidSecondFactor
+            | ServiceError::CandidateNotFound
+        )
+    }
+
+    /// Fails with `ServiceError::TooManyLoginAttempts` when any of the keys is locked out
+    pub async fn check(db: &DbConn, keys: &[ThrottleKey]) -> Result<(), ServiceError> {
+        let now = chrono::Local::now().naive_local();
+        for key in keys {
+            let throttle = Query::find_login_throttle(db, &key.to_string()).await?;
+            if throttle.and_then(|t| t.locked_until).map_or(false, |until| until > now) {
+                return Err(ServiceError::TooManyLoginAttempts);
+            }
+        }
+        Ok(())
+    }
+
+    /// Failures are counted atomically in the database, so the limits hold across replicas
+    pub async fn record_failure(db: &DbConn, keys: &[ThrottleKey]) -> Result<(), ServiceError> {
+        let config = LoginThrottle::from_env();
+        let now = chrono::Local::now().naive_local();
+
+        for key in keys {
+            let policy = config.policy(key);
+            let throttle = Mutation::increment_login_throttle(db, key.to_string(), now, policy.window_start_limit(now)).await?;
+
+            if let Some(locked_until) = policy.locked_until(throttle.failures, now) {
+                Mutation::lock_login_throttle(db, throttle, locked_until).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Successful login forgets failures of the account, failures of the IP address are kept
+    pub async fn record_success(db: &DbConn, keys: &[ThrottleKey]) -> Result<(), ServiceError> {
+        for key in keys.iter().filter(|k| k.is_account()) {
+            if let Some(throttle) = Query::find_login_throttle(db, &key.to_string()).await? {
+                Mutation::delete_login_throttle(db, throttle).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the login unless one of the keys is locked out and records its outcome.
+    /// Locked out logins never reach password verification.
+    pub async fn attempt<T, F>(db: &DbConn, keys: &[ThrottleKey], login: F) -> Result<T, ServiceError>
+    where F: Future<Output = Result<T, ServiceError>>
+    {
+        Self::check(db, keys).await?;
+
+        let result = login.await;
+        match &result {
+            Ok(_) => Self::record_success(db, keys).await?,
+            Err(e) if Self::is_failure(e) => Self::record_failure(db, keys).await?,
+            Err(_) => {},
+        }
+        result
+    }
+
+    pub async fn list_lockouts(db: &DbConn) -> Result<Vec<LockoutResponse>, ServiceError> {
+        let now = chrono::Local::now().naive_local();
+
+        Ok(
+            Query::list_active_lockouts(db, now).await?
+                .into_iter()
+                .map(LockoutResponse::from)
+                .collect()
+        )
+    }
+
+    pub async fn clear_lockout(db: &DbConn, admin_id: i32, key: String) -> Result<(), ServiceError> {
+        let throttle = Query::find_login_throttle(db, &key)
+            .await?
+            .ok_or(ServiceError::LockoutNotFound)?;
+        Mutation::delete_login_throttle(db, throttle).await?;
+
+        AuditService::log(db, Some(admin_id), None, None, AuditAction::ClearLockout, Some(key)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::ServiceError, models::throttle::ThrottleKey, utils::db::get_memory_sqlite_connection, Query};
+
+    use super::LoginThrottleService;
+
+    #[tokio::test]
+    async fn test_lockout_and_clear() {
+        let db = get_memory_sqlite_connection().await;
+        let keys = [ThrottleKey::Ip("10.0.0.1".to_string()), ThrottleKey::Candidate(103151)];
+
+        for _ in 0..5 {
+            let result: Result<(), ServiceError> = LoginThrottleService::attempt(&db, &keys, async {
+                Err(ServiceError::InvalidCredentials)
+            }).await;
+            assert!(matches!(result, Err(ServiceError::InvalidCredentials)));
+        }
+
+        // correct password doesn't help while the account is locked out
+        let result = LoginThrottleService::attempt(&db, &keys, async { Ok(()) }).await;
+        assert!(matches!(result, Err(ServiceError::TooManyLoginAttempts)));
+
+        // other accounts from the same address can still log in
+        assert!(LoginThrottleService::check(&db, &[ThrottleKey::Ip("10.0.0.1".to_string()), ThrottleKey::Candidate(103152)]).await.is_ok());
+
+        let lockouts = LoginThrottleService::list_lockouts(&db).await.unwrap();
+        assert_eq!(lockouts.len(), 1);
+        assert_eq!(lockouts[0].key, "candidate:103151");
+
+        LoginThrottleService::clear_lockout(&db, 1, "candidate:103151".to_string()).await.unwrap();
+        assert!(LoginThrottleService::attempt(&db, &keys, async { Ok(()) }).await.is_ok());
+        assert!(LoginThrottleService::list_lockouts(&db).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_failures() {
+        let db = get_memory_sqlite_connection().await;
+        let keys = [ThrottleKey::Candidate(103153)];
+
+        let (first, second) = tokio::join!(
+            LoginThrottleService::record_failure(&db, &keys),
+            LoginThrottleService::record_failure(&db, &keys),
+        );
+        first.unwrap();
+        second.unwrap();
+
+        let throttle = Query::find_login_throttle(&db, "candidate:103153").await.unwrap().unwrap();
+        assert_eq!(throttle.failures, 2);
+        assert!(throttle.locked_until.is_none());
+    }
+}
@@ -0,0 +1,116 @@
+use chrono::NaiveDateTime;
+use entity::candidate;
+use log::{error, info, warn};
+use sea_orm::DbConn;
+
+use crate::{error::ServiceError, Query, models::candidate_details::EncryptedString, notification::{get_transport, Message, Template}};
+
+use super::portfolio_service::PortfolioService;
+
+pub struct NotificationService;
+
+impl NotificationService {
+    /// Sends templated message to all recipients, nothing is sent when there are no recipients
+    pub async fn send(to: Vec<String>, template: Template) -> Result<(), ServiceError> {
+        if to.is_empty() {
+            return Ok(());
+        }
+        let transport = get_transport()?;
+        transport.send(&Message::from_template(to, &template)).await
+    }
+
+    /// Same as `send`, but failure is only logged so that the operation which triggered the message does not fail
+    pub async fn notify(to: Vec<String>, template: Template) {
+        let subject = template.subject();
+        if let Err(e) = Self::send(to, template).await {
+            error!("NOTIFICATION \"{}\" FAILED: {} {:?}", subject, e, e.inner_trace());
+        }
+    }
+
+    /// Candidate's email, empty when details are not filled yet
+    pub async fn candidate_emails(candidate: &candidate::Model, private_key: &String) -> Vec<String> {
+        Self::decrypt_emails(vec![candidate.email.to_owned()], private_key).await
+    }
+
+    /// Emails of the candidate and all parents that filled them in
+    pub async fn family_emails(db: &DbConn, candidate: &candidate::Model, private_key: &String) -> Result<Vec<String>, ServiceError> {
+        let parents = Query::find_candidate_parents(db, candidate).await?;
+
+        let mut emails = vec![candidate.email.to_owned()];
+        emails.extend(parents.into_iter().map(|p| p.email));
+
+        Ok(
+            Self::decrypt_emails(emails, private_key).await
+        )
+    }
+
+    /// Reminds all candidates without submitted portfolio, returns number of reminded candidates
+    /// Failure of one candidate is logged and the remaining candidates are still reminded
+    pub async fn send_deadline_reminders(db: &DbConn, admin_private_key: &String, deadline: NaiveDateTime) -> Result<usize, ServiceError> {
+        let candidates = Query::list_candidates_full(db).await?;
+        let applications = Query::list_applications_compact(db).await?;
+
+        let mut reminded = 0;
+        for candidate in candidates {
+            if PortfolioService::is_portfolio_submitted(candidate.id).await {
+                continue;
+            }
+            let emails = match Self::family_emails(db, &candidate, admin_private_key).await {
+                Ok(emails) => emails,
+                Err(e) => {
+                    warn!("DEADLINE REMINDER TO CANDIDATE {} FAILED: {} {:?}", candidate.id, e, e.inner_trace());
+                    continue;
+                },
+            };
+            if emails.is_empty() {
+                continue;
+            }
+            let candidate_applications = applications.iter()
+                .filter(|a| a.candidate_id == candidate.id)
+                .map(|a| a.id)
+                .collect();
+
+            match Self::send(emails, Template::DeadlineReminder { applications: candidate_applications, deadline }).await {
+                Ok(()) => reminded += 1,
+                Err(e) => warn!("DEADLINE REMINDER TO CANDIDATE {} FAILED: {} {:?}", candidate.id, e, e.inner_trace()),
+            }
+        }
+
+        info!("DEADLINE REMINDER SENT TO {} CANDIDATES", reminded);
+        Ok(reminded)
+    }
+
+    async fn decrypt_emails(emails: Vec<Option<String>>, private_key: &String) -> Vec<String> {
+        let mut decrypted = vec![];
+        for email in emails.into_iter().flatten() {
+            // not decryptable email is treated as not filled
+            if let Ok(email) = EncryptedString::from(email).decrypt(private_key).await {
+                if !email.is_empty() && !decrypted.contains(&email) {
+                    decrypted.push(email);
+                }
+            }
+        }
+        decrypted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{crypto, services::candidate_service::tests::put_user_data, utils::db::get_memory_sqlite_connection};
+
+    use super::NotificationService;
+
+    #[tokio::test]
+    async fn test_family_emails() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, candidate, _) = put_user_data(&db).await;
+        let private_key = crypto::decrypt_password(application.private_key, "test".to_string()).await.unwrap();
+
+        assert_eq!(NotificationService::candidate_emails(&candidate, &private_key).await, vec!["email".to_string()]);
+        assert_eq!(
+            NotificationService::family_emails(&db, &candidate, &private_key).await.unwrap(),
+            vec!["email".to_string(), "parent_email".to_string()]
+        );
+        assert!(NotificationService::candidate_emails(&candidate, &"invalid key".to_string()).await.is_empty());
+    }
+}
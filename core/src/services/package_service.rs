@@ -0,0 +1,221 @@
+use std::path::Path;
+
+use async_compat::CompatExt;
+use entity::{application, candidate};
+use log::{info, warn};
+use sea_orm::DbConn;
+use tokio::io::AsyncWriteExt;
+
+use crate::{crypto, error::ServiceError, storage::{get_store, get_temp_path, PortfolioStore}, Query, models::{candidate_details::{EncryptedCandidateDetails, EncryptedString}, package::{FolderNameTemplate, PackageFailure, PackageReport, PackagedCandidate}}, utils::filetype::is_safe_entry_name};
+
+use super::portfolio_service::PortfolioService;
+
+pub struct PackageService;
+
+impl PackageService {
+    /// Decrypts all submitted portfolios from the portfolio store and unpacks them
+    /// into `<output>/<field of study>/<folder name>/`. Candidate applying to two fields is placed into both.
+    /// Candidates that fail are listed in the report and do not stop the export.
+    pub async fn package_all(
+        db: &DbConn,
+        private_key: &String,
+        output: &Path,
+        template: &FolderNameTemplate,
+    ) -> Result<PackageReport, ServiceError> {
+        let store = get_store()?;
+        let candidates = Query::list_candidates_full(db).await?;
+        let applications = Query::list_applications_compact(db).await?;
+
+        let mut report = PackageReport::default();
+        for candidate in candidates {
+            let mut candidate_applications: Vec<&application::Model> = applications.iter()
+                .filter(|a| a.candidate_id == candidate.id)
+                .collect();
+            candidate_applications.sort_by_key(|a| a.id);
+
+            if !store.exists(&PortfolioService::portfolio_key(candidate.id)).await {
+                report.not_submitted.push(candidate.id);
+                continue;
+            }
+
+            match Self::package_candidate(store.as_ref(), private_key, &candidate, &candidate_applications, output, template).await {
+                Ok(_) => report.packaged += 1,
+                Err(e) => {
+                    warn!("CANDIDATE {} PACKAGE FAILED: {} {:?}", candidate.id, e, e.inner_trace());
+                    report.failures.push(PackageFailure {
+                        candidate_id: candidate.id,
+                        applications: candidate_applications.iter().map(|a| a.id).collect(),
+                        reason: e.inner_trace().unwrap_or(e.to_string()),
+                    });
+                },
+            }
+        }
+
+        info!("PACKAGED {} PORTFOLIOS, {} FAILED", report.packaged, report.failures.len());
+        Ok(report)
+    }
+
+    async fn package_candidate(
+        store: &dyn PortfolioStore,
+        private_key: &String,
+        candidate: &candidate::Model,
+        applications: &[&application::Model],
+        output: &Path,
+        template: &FolderNameTemplate,
+    ) -> Result<(), ServiceError> {
+        let details = EncryptedCandidateDetails::from(candidate);
+        let (name, surname) = tokio::try_join!(
+            EncryptedString::decrypt_option(&details.name, private_key),
+            EncryptedString::decrypt_option(&details.surname, private_key),
+        )?;
+
+        let mut fields: Vec<String> = applications.iter().map(|a| a.field_of_study.to_owned()).collect();
+        fields.dedup();
+
+        let packaged = PackagedCandidate {
+            candidate_id: candidate.id,
+            name: name.unwrap_or_default(),
+            surname: surname.unwrap_or_default(),
+            applications: applications.iter().map(|a| a.id).collect(),
+            fields: fields.to_owned(),
+        };
+        let folder_name = template.render(&packaged);
+
+        // decrypted into local temporary file, the zip reader seeks and large portfolios are never held in memory
+        let temp_path = get_temp_path("package.zip");
+        let result: Result<(), ServiceError> = async {
+            Self::decrypt_portfolio(store, private_key, candidate.id, &temp_path).await?;
+            for field in fields.iter() {
+                Self::unpack(&temp_path, &output.join(field).join(&folder_name)).await?;
+            }
+            Ok(())
+        }.await;
+
+        tokio::fs::remove_file(&temp_path).await.ok();
+        result
+    }
+
+    async fn decrypt_portfolio(
+        store: &dyn PortfolioStore,
+        private_key: &String,
+        candidate_id: i32,
+        path: &Path,
+    ) -> Result<(), ServiceError> {
+        let reader = store.reader(&PortfolioService::portfolio_key(candidate_id)).await?;
+        let decrypt_reader = crypto::decrypt_reader_with_private_key(reader, private_key).await?;
+
+        let mut file = tokio::fs::File::create(path).await?;
+        futures::io::copy(decrypt_reader, &mut file.compat_mut()).await?;
+        file.shutdown().await?;
+
+        Ok(())
+    }
+
+    /// Extracts the submitted archive (cover letter, portfolio letter and portfolio zip) into the directory
+    async fn unpack(archive: &Path, dir: &Path) -> Result<(), ServiceError> {
+        tokio::fs::create_dir_all(dir).await?;
+
+        let file = tokio::fs::File::open(archive).await?;
+        let mut reader = async_zip::base::read::seek::ZipFileReader::new(file.compat()).await?;
+        let names: Vec<String> = reader.file()
+            .entries()
+            .iter()
+            .map(|e| e.entry().filename().as_str().map(|n| n.to_string()).unwrap_or_default())
+            .collect();
+
+        for (index, name) in names.into_iter().enumerate() {
+            if !is_safe_entry_name(&name) {
+                return Err(ServiceError::InvalidPortfolioZip(format!("invalid file name {:?}", name)));
+            }
+
+            let path = dir.join(&name);
+            if name.ends_with('/') || name.ends_with('\\') {
+                tokio::fs::create_dir_all(&path).await?;
+                continue;
+            }
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+
+            let entry_reader = reader.reader_without_entry(index).await?;
+            let mut output = tokio::fs::File::create(&path).await?;
+            futures::io::copy(entry_reader, &mut output.compat_mut()).await?;
+            output.shutdown().await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serial_test::serial;
+
+    use crate::{crypto, models::package::FolderNameTemplate, services::{candidate_service::tests::put_user_data, portfolio_service::PortfolioService}, utils::{db::get_memory_sqlite_connection, filetype::ZipManifest}};
+
+    use super::PackageService;
+
+    #[tokio::test]
+    #[serial]
+    async fn test_package_all() {
+        let db = get_memory_sqlite_connection().await;
+        let (application, candidate, _) = put_user_data(&db).await;
+        let private_key = crypto::decrypt_password(application.private_key.to_owned(), "test".to_string()).await.unwrap();
+
+        let random_number: u32 = rand::Rng::gen(&mut rand::thread_rng());
+        let temp_dir = std::env::temp_dir().join("portfolio_test_package").join(random_number.to_string());
+        let root_dir = temp_dir.join("data");
+        let output = temp_dir.join("named");
+        tokio::fs::create_dir_all(root_dir.join(candidate.id.to_string()).join("cache")).await.unwrap();
+        std::env::set_var("PORTFOLIO_STORE_PATH", root_dir.to_str().unwrap());
+
+        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0], &ZipManifest::default()).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &private_key).await.unwrap();
+
+        let report = PackageService::package_all(&db, &private_key, &output, &FolderNameTemplate::default())
+            .await
+            .unwrap();
+        assert_eq!(report.packaged, 1);
+        assert!(report.failures.is_empty());
+
+        let folder = output
+            .join(&application.field_of_study)
+            .join(format!("surname name ({})", application.id));
+        assert!(tokio::fs::metadata(folder.join("PORTFOLIO.zip")).await.is_ok());
+        assert!(tokio::fs::metadata(folder.join("MOTIVACNI_DOPIS.pdf")).await.is_ok());
+
+        let report = PackageService::package_all(&db, &"invalid key".to_string(), &output, &FolderNameTemplate::default())
+            .await
+            .unwrap();
+        assert_eq!(report.failures.len(), 1);
+
+        tokio::fs::remove_dir_all(temp_dir).await.unwrap();
+        std::env::remove_var("PORTFOLIO_STORE_PATH");
+    }
+
+    #[tokio::test]
+    async fn test_unpack_nested_entries() {
+        let random_number: u32 = rand::Rng::gen(&mut rand::thread_rng());
+        let temp_dir = std::env::temp_dir().join("portfolio_test_unpack").join(random_number.to_string());
+        tokio::fs::create_dir_all(&temp_dir).await.unwrap();
+
+        let mut zip = vec![];
+        let mut writer = async_zip::base::write::ZipFileWriter::new(&mut zip);
+        for (name, data) in [("PORTFOLIO.zip", &b"zip"[..]), ("dir/", &b""[..]), ("nested/dir/file.pdf", &b"pdf"[..])] {
+            let builder = async_zip::ZipEntryBuilder::new(name.to_string().into(), async_zip::Compression::Deflate);
+            writer.write_entry_whole(builder, data).await.unwrap();
+        }
+        writer.close().await.unwrap();
+        let archive = temp_dir.join("archive.zip");
+        tokio::fs::write(&archive, zip).await.unwrap();
+
+        let output = temp_dir.join("output");
+        PackageService::unpack(&archive, &output).await.unwrap();
+        assert_eq!(tokio::fs::read(output.join("PORTFOLIO.zip")).await.unwrap(), b"zip");
+        assert!(tokio::fs::metadata(output.join("dir")).await.unwrap().is_dir());
+        assert_eq!(tokio::fs::read(output.join("nested/dir/file.pdf")).await.unwrap(), b"pdf");
+
+        tokio::fs::remove_dir_all(temp_dir).await.unwrap();
+    }
+}
@@ -96,7 +96,7 @@ mod tests {
     #[tokio::test]
     async fn create_parent_test() {
         let db = get_memory_sqlite_connection().await;
-        let candidate = CandidateService::create(&db, "".to_string()).await.unwrap();
+        let candidate = CandidateService::create(&db, "".to_string(), "".to_string()).await.unwrap();
         super::ParentService::create(&db, candidate.id).await.unwrap();
         super::ParentService::create(&db, candidate.id).await.unwrap();
     }
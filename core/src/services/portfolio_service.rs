@@ -1,18 +1,23 @@
-use std::{path::{PathBuf, Path}};
+use std::{path::Path, pin::Pin};
 
+use async_compat::CompatExt;
 use entity::candidate;
+use futures::io::AsyncWriteExt as _;
 use log::{info, warn};
 use sea_orm::{DbConn};
 use serde::{Serialize, ser::{SerializeStruct}};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
 
-use crate::{error::ServiceError, Query, crypto};
+use crate::{error::ServiceError, Query, crypto, models::calendar::Phase, notification::Template, storage::{get_store, get_temp_path, PortfolioStore}, utils::filetype::ZipManifest};
 
+use super::{calendar_service::CalendarService, notification_service::NotificationService};
+
+/// Files in cache, together with manifest of the cached portfolio zip if there is one
 #[derive(Debug, PartialEq)]
 pub enum SubmissionProgress {
     NoneInCache,
-    SomeInCache(Vec<FileType>),
-    AllInCache,
+    SomeInCache(Vec<FileType>, Option<ZipManifest>),
+    AllInCache(Option<ZipManifest>),
     Submitted,
 }
 
@@ -20,8 +25,8 @@ impl SubmissionProgress {
     pub fn index(&self) -> usize {
         match self {
             SubmissionProgress::NoneInCache => 1,
-            SubmissionProgress::SomeInCache(_) => 2,
-            SubmissionProgress::AllInCache => 3,
+            SubmissionProgress::SomeInCache(..) => 2,
+            SubmissionProgress::AllInCache(_) => 3,
             SubmissionProgress::Submitted => 4,
         }
     }
@@ -33,11 +38,11 @@ impl Serialize for SubmissionProgress {
     where
         S: serde::Serializer,
     {
-        let mut progress = serializer.serialize_struct("SubmissionProgress", 2)?;
+        let mut progress = serializer.serialize_struct("SubmissionProgress", 3)?;
         progress.serialize_field("status", &self.index())?;
 
         match self {
-            SubmissionProgress::SomeInCache(files) => {
+            SubmissionProgress::SomeInCache(files, _) => {
                 progress.serialize_field("files", files)?;
             }
             _ => {
@@ -45,6 +50,15 @@ impl Serialize for SubmissionProgress {
             }
         };
 
+        match self {
+            SubmissionProgress::SomeInCache(_, manifest) | SubmissionProgress::AllInCache(manifest) => {
+                progress.serialize_field("manifest", manifest)?;
+            }
+            _ => {
+                progress.serialize_field("manifest", &None::<ZipManifest>)?;
+            }
+        };
+
         progress.end()
     }
 }
@@ -56,6 +70,7 @@ pub enum FileType {
     PortfolioLetterPdf = 2,
     PortfolioZip = 3,
     Age = 4,
+    PortfolioManifest = 5,
 }
 
 impl FileType {
@@ -69,6 +84,7 @@ impl FileType {
             FileType::PortfolioLetterPdf => "PORTFOLIO.pdf",
             FileType::PortfolioZip => "PORTFOLIO.zip",
             FileType::Age => "PORTFOLIO.age",
+            FileType::PortfolioManifest => "PORTFOLIO.json",
         }
     }
 
@@ -102,34 +118,42 @@ impl Serialize for FileType {
 pub struct PortfolioService;
 impl PortfolioService {
     pub async fn get_submission_progress(candidate_id: i32) -> Result<SubmissionProgress, ServiceError> {
-        let path = Self::get_file_store_path().join(&candidate_id.to_string());
-        if !path.exists() {
+        let store = get_store()?;
+        if !store.exists(&candidate_id.to_string()).await {
             return Err(ServiceError::CandidateNotFound);
         }
-        let cache_path = path.join("cache");
 
-        if path.join(FileType::Age.as_str()).exists() {
+        if store.exists(&Self::portfolio_key(candidate_id)).await {
             return Ok(SubmissionProgress::Submitted);
         }
 
         let mut files = Vec::new();
         for file in FileType::iter_cache() {
-            if cache_path.join(file.as_str()).exists() {
+            if store.exists(&Self::cache_key(candidate_id, file)).await {
                 files.push(file);
             }
         }
+        if files.is_empty() {
+            return Ok(SubmissionProgress::NoneInCache);
+        }
+
+        let manifest = Self::get_portfolio_zip_manifest(candidate_id).await;
         match files.len() {
-            0 => Ok(SubmissionProgress::NoneInCache),
-            3 => Ok(SubmissionProgress::AllInCache),
-            _ => Ok(SubmissionProgress::SomeInCache(files)),
+            3 => Ok(SubmissionProgress::AllInCache(manifest)),
+            _ => Ok(SubmissionProgress::SomeInCache(files, manifest)),
         }
     }
 
+    fn cache_dir_key(candidate_id: i32) -> String {
+        format!("{}/cache", candidate_id)
+    }
 
-    // Get root path or local directory
-    fn get_file_store_path() -> PathBuf {
-        dotenv::dotenv().ok();
-        Path::new(&std::env::var("PORTFOLIO_STORE_PATH").unwrap_or_else(|_| "".to_string())).to_path_buf()
+    fn cache_key(candidate_id: i32, filename: FileType) -> String {
+        format!("{}/cache/{}", candidate_id, filename.as_str())
+    }
+
+    pub(crate) fn portfolio_key(candidate_id: i32) -> String {
+        format!("{}/{}", candidate_id, FileType::Age.as_str())
     }
 
     /// Writes file to desired location
@@ -140,22 +164,14 @@ impl PortfolioService {
     ) -> Result<(), ServiceError> {
         info!("PORTFOLIO {} CACHE {} WRITE STARTED", candidate_id, filename.as_str());
 
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-
-        let mut file = tokio::fs::File::create(cache_path.join(filename.as_str())).await?;
-
-        file.write_all(&data).await?;
+        get_store()?.write(&Self::cache_key(candidate_id, filename), data).await?;
 
         info!("PORTFOLIO {} CACHE {} WRITE FINISHED", candidate_id, filename.as_str());
         Ok(())
     }
 
-    pub async fn create_user_dir(application_id: i32) -> tokio::io::Result<()> {
-        tokio::fs::create_dir_all(
-            Self::get_file_store_path()
-            .join(&application_id.to_string())
-            .join("cache"))
-            .await
+    pub async fn create_user_dir(candidate_id: i32) -> Result<(), ServiceError> {
+        get_store()?.create_dir(&Self::cache_dir_key(candidate_id)).await
     }
 
     
@@ -173,59 +189,60 @@ impl PortfolioService {
         Self::write_portfolio_file(candidate_id, letter, FileType::PortfolioLetterPdf).await
     }
 
+    /// Stores zip already checked by `inspect_zip` together with its manifest
     pub async fn add_portfolio_zip_to_cache(
         candidate_id: i32,
         zip: Vec<u8>,
+        manifest: &ZipManifest,
     ) -> Result<(), ServiceError> {
+        let manifest = serde_json::to_vec(manifest).map_err(|_| ServiceError::FormatError)?;
+        Self::write_portfolio_file(candidate_id, manifest, FileType::PortfolioManifest).await?;
         Self::write_portfolio_file(candidate_id, zip, FileType::PortfolioZip).await
     }
+
+    /// Manifest of the cached portfolio zip, None if there is no zip or it was uploaded without manifest
+    pub async fn get_portfolio_zip_manifest(candidate_id: i32) -> Option<ZipManifest> {
+        let store = get_store().ok()?;
+        let key = Self::cache_key(candidate_id, FileType::PortfolioManifest);
+        if !store.exists(&key).await {
+            return None;
+        }
+
+        let mut manifest = vec![];
+        store.reader(&key).await.ok()?
+            .read_to_end(&mut manifest).await.ok()?;
+
+        serde_json::from_slice(&manifest).ok()
+    }
     
-    
+    async fn is_in_cache(candidate_id: i32, file_type: FileType) -> bool {
+        match get_store() {
+            Ok(store) => store.exists(&Self::cache_key(candidate_id, file_type)).await,
+            Err(_) => false,
+        }
+    }
+
     pub async fn is_cover_letter(candidate_id: i32) -> bool {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-        
-        tokio::fs::metadata(cache_path.join(cache_path.join(FileType::CoverLetterPdf.as_str())))
-        .await
-        .is_ok()
+        Self::is_in_cache(candidate_id, FileType::CoverLetterPdf).await
     }
 
     pub async fn is_portfolio_letter(candidate_id: i32) -> bool {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-
-        tokio::fs::metadata(
-            cache_path.join(
-                cache_path.join(FileType::PortfolioLetterPdf.as_str())
-            )
-        )
-            .await
-            .is_ok()
+        Self::is_in_cache(candidate_id, FileType::PortfolioLetterPdf).await
     }
 
     pub async fn is_portfolio_zip(candidate_id: i32) -> bool {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-
-        tokio::fs::metadata(
-            cache_path.join(
-                cache_path.join(FileType::PortfolioZip.as_str())
-            )
-        )
-            .await
-            .is_ok()
+        Self::is_in_cache(candidate_id, FileType::PortfolioZip).await
     }
 
 
     /// Returns true if portfolio is ready to be moved to the final directory
     async fn is_portfolio_prepared(candidate_id: i32) -> bool {
-        Self::get_submission_progress(candidate_id).await.ok() == Some(SubmissionProgress::AllInCache)
+        matches!(Self::get_submission_progress(candidate_id).await, Ok(SubmissionProgress::AllInCache(_)))
     }
 
     // Delete single item from cache
     pub async fn delete_cache_item(candidate_id: i32, file_type: FileType) -> Result<(), ServiceError> {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-
-        tokio::fs::remove_file(cache_path.join(file_type.as_str())).await?;
-
-        Ok(())
+        get_store()?.delete(&Self::cache_key(candidate_id, file_type)).await
     }
 
     pub async fn delete_cover_letter_from_cache(
@@ -243,25 +260,30 @@ impl PortfolioService {
     pub async fn delete_portfolio_zip_from_cache(
         candidate_id: i32,
     ) -> Result<(), ServiceError> {
+        if Self::is_in_cache(candidate_id, FileType::PortfolioManifest).await {
+            Self::delete_cache_item(candidate_id, FileType::PortfolioManifest).await?;
+        }
         Self::delete_cache_item(candidate_id,  FileType::PortfolioZip).await
     }
 
     /// Removes all files from cache
     pub async fn delete_cache(candidate_id: i32) -> Result<(), ServiceError> {
-        let cache_path = Self::get_file_store_path().join(&candidate_id.to_string()).join("cache");
-        tokio::fs::remove_dir_all(&cache_path).await?;
+        let store = get_store()?;
+        store.delete_dir(&Self::cache_dir_key(candidate_id)).await?;
         // Recreate blank cache directory
-        tokio::fs::create_dir_all(&cache_path).await?;
+        store.create_dir(&Self::cache_dir_key(candidate_id)).await?;
 
         Ok(())
     }
 
 
-    /// Move files from cache to final directory and delete cache afterwards
-    pub async fn submit(candidate: &candidate::Model, db: &DbConn) -> Result<(), ServiceError> {
+    /// Stream files from cache through zip writer and age encryptor to the final directory and delete cache afterwards
+    /// Private key of the candidate (or admin) is used only to find out emails the confirmation is sent to
+    pub async fn submit(candidate: &candidate::Model, db: &DbConn, private_key: &String) -> Result<(), ServiceError> {
         let candidate_id = candidate.id;
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
-        let cache_path = path.join("cache");
+        let store = get_store()?;
+
+        CalendarService::check(candidate, Phase::Portfolio)?;
 
         if Self::is_portfolio_prepared(candidate_id).await == false {
             return Err(ServiceError::IncompletePortfolio);
@@ -269,58 +291,75 @@ impl PortfolioService {
         
         info!("PORTFOLIO {} SUBMIT STARTED", candidate.id);
 
-        let mut archive = tokio::fs::File::create(path.join(FileType::PortfolioZip.as_str())).await?;
-        let mut writer = async_zip::tokio::write::ZipFileWriter::with_tokio(&mut archive);
-        let mut buffer = vec![vec![], vec![], vec![]];
+        let applications = Query::find_applications_by_candidate_id(db, candidate_id).await?;
+        let applications_pubkeys: Vec<String> = applications
+            .iter()
+            .map(|a| a.public_key.to_owned()).collect();
+        let admin_public_keys = Query::get_all_admin_public_keys(db).await?;
 
-        let filenames = vec![FileType::CoverLetterPdf, FileType::PortfolioLetterPdf, FileType::PortfolioZip];
-        for (index, entry) in buffer.iter_mut().enumerate() {
-            let filename = filenames[index];
-            let mut entry_file = tokio::fs::File::open(cache_path.join(filename.as_str())).await?;
+        let mut recipients = vec![];
+        recipients.append(&mut admin_public_keys.iter().map(|s| &**s).collect());
+        recipients.append(&mut applications_pubkeys.iter().map(|s| &**s).collect());
 
-            entry_file.read_to_end(entry).await?;
+        // write into local temporary file first, so that half written portfolio is never considered submitted
+        let temp_path = get_temp_path(FileType::Age.as_str());
+        let result = Self::write_encrypted_portfolio(store.as_ref(), candidate_id, &temp_path, &recipients).await;
+        if let Err(e) = result {
+            tokio::fs::remove_file(&temp_path).await.ok();
+            return Err(e);
+        }
+
+        store.put_file(&Self::portfolio_key(candidate_id), &temp_path).await?;
+
+        if !Self::is_portfolio_submitted(candidate_id).await {
+            return Err(ServiceError::PortfolioWriteError)
         }
 
         Self::delete_cache(candidate_id).await?;
 
-        for (index, entry) in buffer.iter_mut().enumerate() {
-            let filename = filenames[index];
-            let builder = async_zip::ZipEntryBuilder::new(
-                filename.to_string().into(),
-                async_zip::Compression::Deflate,
-            );
+        info!("PORTFOLIO {} SUBMIT FINISHED", candidate_id);
 
-            writer.write_entry_whole(builder, &entry).await?;
+        // portfolio is already submitted, failed notification must not turn it into an error
+        match NotificationService::family_emails(db, candidate, private_key).await {
+            Ok(emails) => NotificationService::notify(
+                emails,
+                Template::PortfolioSubmitted {
+                    applications: applications.iter().map(|a| a.id).collect(),
+                }
+            ).await,
+            Err(e) => warn!("PORTFOLIO {} SUBMIT NOTIFICATION FAILED: {} {:?}", candidate_id, e, e.inner_trace()),
         }
 
-        writer.close().await?;
-        archive.shutdown().await?;
+        Ok(())
+    }
 
-        let applications_pubkeys: Vec<String> = Query::find_applications_by_candidate_id(db, candidate_id)
-            .await?
-            .iter()
-            .map(|a| a.public_key.to_owned()).collect();
-        let admin_public_keys = Query::get_all_admin_public_keys(db).await?;
+    async fn write_encrypted_portfolio(
+        store: &dyn PortfolioStore,
+        candidate_id: i32,
+        path: &Path,
+        recipients: &Vec<&str>,
+    ) -> Result<(), ServiceError> {
+        let mut archive = tokio::fs::File::create(path).await?;
+        let mut encrypt_writer = crypto::encrypt_writer_with_recipients(&mut archive, recipients).await?;
+        let mut writer = async_zip::base::write::ZipFileWriter::new(&mut encrypt_writer);
 
-        let mut recipients = vec![];
-        recipients.append(&mut admin_public_keys.iter().map(|s| &**s).collect());
-        recipients.append(&mut applications_pubkeys.iter().map(|s| &**s).collect());
+        for filename in FileType::iter_cache() {
+            let builder = async_zip::ZipEntryBuilder::new(
+                filename.to_string().into(),
+                async_zip::Compression::Deflate,
+            );
 
-        let final_path = path.join(FileType::PortfolioZip.as_str());
+            let entry_reader = store.reader(&Self::cache_key(candidate_id, filename)).await?;
+            let mut entry_writer = writer.write_entry_stream(builder).await?;
 
-        crypto::encrypt_file_with_recipients(
-            &final_path,
-            &final_path.with_extension("age"),
-            recipients,
-        ).await?;
-        tokio::fs::remove_file(final_path).await?;
+            futures::io::copy(entry_reader.compat(), &mut entry_writer).await?;
 
-        
-        if !Self::is_portfolio_submitted(candidate_id).await {
-            return Err(ServiceError::PortfolioWriteError)
+            entry_writer.close().await?;
         }
 
-        info!("PORTFOLIO {} SUBMIT FINISHED", candidate_id);
+        writer.close().await?;
+        encrypt_writer.close().await?;
+        archive.shutdown().await?;
 
         Ok(())
     }
@@ -328,17 +367,17 @@ impl PortfolioService {
     /// Delete PORTFOLIO.age file
     pub async fn delete_portfolio(candidate_id: i32) -> Result<(), ServiceError> {
         info!("PORTFOLIO {} DELETE STARTED", candidate_id);
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
+        let store = get_store()?;
 
-        let portfolio_path = path.join(FileType::PortfolioZip.as_str());
-        let portfolio_age_path = portfolio_path.with_extension("age");
+        let portfolio_key = format!("{}/{}", candidate_id, FileType::PortfolioZip.as_str());
+        let portfolio_age_key = Self::portfolio_key(candidate_id);
 
-        if tokio::fs::metadata(&portfolio_path).await.is_ok() {
-            tokio::fs::remove_file(&portfolio_path).await?;
+        if store.exists(&portfolio_key).await {
+            store.delete(&portfolio_key).await?;
         }
 
-        if tokio::fs::metadata(&portfolio_age_path).await.is_ok() {
-            tokio::fs::remove_file(&portfolio_age_path).await?;
+        if store.exists(&portfolio_age_key).await {
+            store.delete(&portfolio_age_key).await?;
         }
 
         info!("PORTFOLIO {} DELETE FINISHED", candidate_id);
@@ -350,8 +389,7 @@ impl PortfolioService {
     pub async fn delete_candidate_root(candidate_id: i32) -> Result<(), ServiceError> {
         warn!("CANDIDATE {} ROOT DIRECTORY DELETE STARTED", candidate_id);
 
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
-        tokio::fs::remove_dir_all(path).await?;
+        get_store()?.delete_dir(&candidate_id.to_string()).await?;
 
         warn!("CANDIDATE {} ROOT DIRECTORY DELETE FINISHED", candidate_id);
 
@@ -360,23 +398,20 @@ impl PortfolioService {
 
     /// Returns true if portfolio is submitted
     pub async fn is_portfolio_submitted(candidate_id: i32) -> bool {
-        let path = Self::get_file_store_path().join(&candidate_id.to_string()).to_path_buf();
-
-        tokio::fs::metadata(path.join(FileType::Age.as_str())).await.is_ok()
+        match get_store() {
+            Ok(store) => store.exists(&Self::portfolio_key(candidate_id)).await,
+            Err(_) => false,
+        }
     }
 
-    /// Returns decrypted portfolio zip as Vec of bytes
-    pub async fn get_portfolio(candidate_id: i32, private_key: String) -> Result<Vec<u8>, ServiceError> {
+    /// Returns reader of decrypted portfolio zip, the portfolio is decrypted while being read
+    pub async fn get_portfolio(candidate_id: i32, private_key: String) -> Result<Pin<Box<dyn AsyncRead + Send>>, ServiceError> {
         info!("PORTFOLIO {} DECRYPT STARTED", candidate_id);
-        let path = Self::get_file_store_path()
-            .join(&candidate_id.to_string())
-            .join(FileType::Age.as_str())
-            .to_path_buf();
 
-        let buffer = crypto::decrypt_file_with_private_key_as_buffer(path, &private_key).await?;
+        let reader = get_store()?.reader(&Self::portfolio_key(candidate_id)).await?;
+        let decrypt_reader = crypto::decrypt_reader_with_private_key(reader, &private_key).await?;
 
-        info!("PORTFOLIO {} DECRYPT FINISHED", candidate_id);
-        Ok(buffer)
+        Ok(Box::pin(decrypt_reader.compat()))
     }
 
     pub async fn reencrypt_portfolio(candidate_id: i32,
@@ -384,25 +419,20 @@ impl PortfolioService {
         recipients: &Vec<String>
     ) -> Result<(), ServiceError> {
         info!("PORTFOLIO {} REENCRYPT STARTED", candidate_id);
-        let path = Self::get_file_store_path()
-            .join(&candidate_id.to_string())
-            .join(FileType::Age.as_str())
-            .to_path_buf();
-
-        let plain_portfolio = crypto::decrypt_file_with_private_key_as_buffer(
-            path.to_owned(),
-            &private_key
+        let store = get_store()?;
+        let temp_path = get_temp_path(FileType::Age.as_str());
+
+        let reader = store.reader(&Self::portfolio_key(candidate_id)).await?;
+        let mut temp_file = tokio::fs::File::create(&temp_path).await?;
+        crypto::reencrypt_with_private_key(
+            reader,
+            &mut temp_file,
+            &private_key,
+            recipients.iter().map(|s| s.as_str()).collect(),
         ).await?;
+        drop(temp_file);
 
-        
-        let enc_portfolio= crypto::encrypt_buffer_with_recipients(
-            &plain_portfolio, 
-            recipients
-        ).await?;
-        
-        tokio::fs::remove_file(path.to_owned()).await?;
-        
-        tokio::fs::write(path, enc_portfolio).await?;
+        store.put_file(&Self::portfolio_key(candidate_id), &temp_path).await?;
 
         info!("PORTFOLIO {} REENCRYPT FINISHED", candidate_id);
 
@@ -414,7 +444,7 @@ impl PortfolioService {
 mod tests {
     use serial_test::serial;
 
-    use crate::{services::{portfolio_service::{PortfolioService, FileType}, candidate_service::{CandidateService, tests::put_user_data}}, utils::db::get_memory_sqlite_connection, crypto};
+    use crate::{services::{portfolio_service::{PortfolioService, FileType}, candidate_service::{CandidateService, tests::put_user_data}}, utils::{db::get_memory_sqlite_connection, filetype::ZipManifest}, crypto};
     use std::path::PathBuf;
 
     const APPLICATION_ID: i32 = 103151;
@@ -449,7 +479,7 @@ mod tests {
         let temp_dir = std::env::temp_dir().join("portfolio_test_tempdir").join("create_folder");
         std::env::set_var("PORTFOLIO_STORE_PATH", temp_dir.to_str().unwrap());
 
-        let candidate = CandidateService::create(&db, "".to_string())
+        let candidate = CandidateService::create(&db, "".to_string(), "".to_string())
             .await
             .ok()
             .unwrap();
@@ -568,9 +598,10 @@ mod tests {
     async fn test_add_portfolio_zip_to_cache() {
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0], &ZipManifest::default()).await.unwrap();
         
         assert!(tokio::fs::metadata(application_cache_dir.join("PORTFOLIO.zip")).await.is_ok());
+        assert_eq!(PortfolioService::get_portfolio_zip_manifest(APPLICATION_ID).await, Some(ZipManifest::default()));
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -580,11 +611,12 @@ mod tests {
     async fn test_delete_portfolio_zip_from_cache() {
         let (temp_dir, _, application_cache_dir) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0], &ZipManifest::default()).await.unwrap();
         
         PortfolioService::delete_portfolio_zip_from_cache(APPLICATION_ID).await.unwrap();
 
         assert!(tokio::fs::metadata(application_cache_dir.join("PORTFOLIO.zip")).await.is_err());
+        assert!(tokio::fs::metadata(application_cache_dir.join("PORTFOLIO.json")).await.is_err());
 
         clear_data_store_temp_dir(temp_dir).await;
     }
@@ -594,7 +626,7 @@ mod tests {
     async fn test_is_portfolio_zip() {
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0], &ZipManifest::default()).await.unwrap();
         
         assert!(PortfolioService::is_portfolio_zip(APPLICATION_ID).await);
 
@@ -608,7 +640,7 @@ mod tests {
 
         PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
         PortfolioService::add_portfolio_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0], &ZipManifest::default()).await.unwrap();
 
         assert!(PortfolioService::is_portfolio_prepared(APPLICATION_ID).await);
 
@@ -618,7 +650,7 @@ mod tests {
 
         PortfolioService::add_cover_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
         //PortfolioService::add_portfolio_letter_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0], &ZipManifest::default()).await.unwrap();
 
         assert!(!PortfolioService::is_portfolio_prepared(APPLICATION_ID).await);
 
@@ -630,7 +662,7 @@ mod tests {
     async fn test_delete_cache() {
         let (temp_dir, _, _) = create_data_store_temp_dir(APPLICATION_ID).await;
 
-        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(APPLICATION_ID, vec![0], &ZipManifest::default()).await.unwrap();
         
         assert!(PortfolioService::is_portfolio_zip(APPLICATION_ID).await);
 
@@ -651,9 +683,9 @@ mod tests {
 
         PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
         PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0], &ZipManifest::default()).await.unwrap();
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &"".to_string()).await.unwrap();
         
         assert!(tokio::fs::metadata(application_dir.join("PORTFOLIO.age")).await.is_ok());
 
@@ -670,9 +702,9 @@ mod tests {
 
         PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
         PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0], &ZipManifest::default()).await.unwrap();
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &"".to_string()).await.unwrap();
         
         assert!(tokio::fs::metadata(application_dir.join("PORTFOLIO.age")).await.is_ok());
 
@@ -693,9 +725,9 @@ mod tests {
 
         PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
         PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0], &ZipManifest::default()).await.unwrap();
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &"".to_string()).await.unwrap();
         
         assert!(PortfolioService::is_portfolio_submitted(candidate.id).await);
 
@@ -705,9 +737,9 @@ mod tests {
 
         PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0]).await.unwrap();
         PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0]).await.unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0]).await.unwrap();
+        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0], &ZipManifest::default()).await.unwrap();
 
-        PortfolioService::submit(&candidate, &db).await.unwrap();
+        PortfolioService::submit(&candidate, &db, &"".to_string()).await.unwrap();
 
         tokio::fs::remove_file(application_dir.join("PORTFOLIO.age")).await.unwrap();
         
@@ -728,23 +760,39 @@ mod tests {
             .await
             .unwrap();
 
-        PortfolioService::add_cover_letter_to_cache(candidate.id, vec![0])
+        let files = [vec![1], vec![2, 2], vec![3, 3, 3]];
+        PortfolioService::add_cover_letter_to_cache(candidate.id, files[0].to_owned())
             .await
             .unwrap();
-        PortfolioService::add_portfolio_letter_to_cache(candidate.id, vec![0])
+        PortfolioService::add_portfolio_letter_to_cache(candidate.id, files[1].to_owned())
             .await
             .unwrap();
-        PortfolioService::add_portfolio_zip_to_cache(candidate.id, vec![0])
+        PortfolioService::add_portfolio_zip_to_cache(candidate.id, files[2].to_owned(), &ZipManifest::default())
             .await
             .unwrap();
 
-        PortfolioService::submit(&candidate, &db)
+        PortfolioService::submit(&candidate, &db, &"".to_string())
             .await
             .unwrap();
 
-        PortfolioService::get_portfolio(candidate.id, private_key)
+        let mut reader = PortfolioService::get_portfolio(candidate.id, private_key.to_owned())
             .await
             .unwrap();
+        let mut portfolio = vec![];
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut portfolio).await.unwrap();
+
+        let submitted = crypto::decrypt_file_with_private_key_as_buffer(temp_dir.join(PortfolioService::portfolio_key(candidate.id)), &private_key)
+            .await
+            .unwrap();
+        assert_eq!(portfolio, submitted);
+
+        let mut zip = async_zip::base::read::seek::ZipFileReader::new(futures::io::Cursor::new(portfolio)).await.unwrap();
+        for (index, (file_type, data)) in FileType::iter_cache().zip(files.iter()).enumerate() {
+            assert_eq!(zip.file().entries()[index].entry().filename().as_str().unwrap(), file_type.as_str());
+            let mut entry = vec![];
+            futures::io::AsyncReadExt::read_to_end(&mut zip.reader_without_entry(index).await.unwrap(), &mut entry).await.unwrap();
+            assert_eq!(&entry, data);
+        }
 
         clear_data_store_temp_dir(temp_dir).await;
     }
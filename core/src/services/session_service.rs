@@ -3,6 +3,7 @@ use entity::{session_trait::UserSession};
 use sea_orm::{DbConn, ActiveModelTrait, ActiveModelBehavior};
 
 use crate::{
+    crypto,
     error::ServiceError,
     Mutation,
 };
@@ -20,6 +21,30 @@ impl SessionService {
         }
     }
 
+    /// Encrypts the private key under a new random secret.
+    /// Returns the key slot stored with the session and the secret handed to the client.
+    pub async fn seal_private_key(private_key: String) -> Result<(String, String), ServiceError> {
+        let secret = crypto::random_secret();
+        let key_slot = crypto::encrypt_with_secret(private_key, secret.to_owned()).await?;
+
+        Ok((key_slot, secret))
+    }
+
+    /// Decrypts the private key from the key slot of a session, fails when the secret doesn't match
+    pub async fn open_private_key(key_slot: Option<String>, secret: String) -> Result<String, ServiceError> {
+        let key_slot = key_slot.ok_or(ServiceError::Unauthorized)?;
+
+        crypto::decrypt_with_secret(key_slot, secret)
+            .await
+            .map_err(|_| ServiceError::Unauthorized)
+    }
+
+    /// Last seen time is stored at most once a minute to spare the database
+    pub fn should_update_last_seen(last_seen_at: Option<chrono::NaiveDateTime>) -> bool {
+        let now = chrono::Utc::now().naive_local();
+        last_seen_at.map_or(true, |last_seen| now - last_seen >= chrono::Duration::minutes(1))
+    }
+
     /// Delete list of sessions
     pub async fn delete_sessions<T>(db: &DbConn, sessions: Vec<T>, keep_n_recent: usize) -> Result<(), ServiceError> where T: ActiveModelTrait + std::marker::Send + ActiveModelBehavior {
         for session in sessions
@@ -43,8 +68,10 @@ mod tests {
     use crate::{
         crypto,
         services::{application_service::ApplicationService},
-        utils::db::get_memory_sqlite_connection, models::auth::AuthenticableTrait,
+        utils::db::get_memory_sqlite_connection, models::auth::AuthenticableTrait, Query,
     };
+
+    use super::SessionService;
     const SECRET: &str = "Tajny_kod";
 
     #[tokio::test]
@@ -52,7 +79,7 @@ mod tests {
 
         let db = get_memory_sqlite_connection().await;
 
-        let application = ApplicationService::create(&"".to_string(), &db, 103151, &SECRET.to_string(), "".to_string()).await.unwrap().0;
+        let application = ApplicationService::create(&db, 103151, &SECRET.to_string(), "".to_string(), None).await.unwrap().0;
 
         assert_eq!(application.id.to_owned(), 103151);
         assert_ne!(application.password.to_owned(), SECRET.to_string());
@@ -66,7 +93,7 @@ mod tests {
     async fn test_candidate_session_correct_password() {
         let db = &get_memory_sqlite_connection().await;
 
-        let application = ApplicationService::create(&"".to_string(), &db, 103151, &SECRET.to_string(), "".to_string()).await.unwrap().0;
+        let application = ApplicationService::create(&db, 103151, &SECRET.to_string(), "".to_string(), None).await.unwrap().0;
 
         // correct password
         let session = ApplicationService::new_session(
@@ -74,6 +101,7 @@ mod tests {
             &application,
             SECRET.to_string(),
             "127.0.0.1".to_string(),
+            None,
         )
         .await
         .unwrap();
@@ -88,16 +116,43 @@ mod tests {
     async fn test_candidate_session_incorrect_password() {
         let db = &get_memory_sqlite_connection().await;
 
-        let application = ApplicationService::create(&"".to_string(), &db, 103151, &SECRET.to_string(), "".to_string()).await.unwrap().0;
+        let application = ApplicationService::create(&db, 103151, &SECRET.to_string(), "".to_string(), None).await.unwrap().0;
 
         // incorrect password
         assert!(ApplicationService::new_session(
             db,
             &application,
             "Spatny_kod".to_string(),
-            "127.0.0.1".to_string()
+            "127.0.0.1".to_string(),
+            None,
         )
         .await
         .is_err());
     }
+
+    #[tokio::test]
+    async fn test_key_slot() {
+        let (key_slot, secret) = SessionService::seal_private_key("AGE-SECRET-KEY-1".to_string()).await.unwrap();
+
+        assert_ne!(key_slot, "AGE-SECRET-KEY-1");
+        assert_eq!(SessionService::open_private_key(Some(key_slot.to_owned()), secret).await.unwrap(), "AGE-SECRET-KEY-1");
+        assert!(SessionService::open_private_key(Some(key_slot), crypto::random_secret()).await.is_err());
+        assert!(SessionService::open_private_key(None, crypto::random_secret()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_logout_destroys_key_slot() {
+        let db = &get_memory_sqlite_connection().await;
+
+        let application = ApplicationService::create(&db, 103151, &SECRET.to_string(), "".to_string(), None).await.unwrap().0;
+        let (session, secret) = ApplicationService::login(db, application.id, SECRET.to_string(), "127.0.0.1".to_string(), None).await.unwrap();
+        let session = Uuid::parse_str(&session).unwrap();
+
+        assert!(ApplicationService::auth_with_private_key(db, session, secret.to_owned()).await.is_ok());
+
+        let session_model = Query::find_session_by_uuid(db, session).await.unwrap().unwrap();
+        ApplicationService::logout(db, session_model).await.unwrap();
+
+        assert!(ApplicationService::auth_with_private_key(db, session, secret).await.is_err());
+    }
 }
@@ -0,0 +1,261 @@
+use entity::admin;
+use sea_orm::DbConn;
+use sha2::{Digest, Sha256};
+
+use crate::{crypto, error::ServiceError, Query, Mutation, utils::totp, models::{audit::AuditAction, auth::{RecoveryCodesResponse, TotpEnrolmentResponse}}};
+
+use super::audit_service::AuditService;
+
+const TOTP_ISSUER: &str = "SSPS Portfolio";
+const RECOVERY_CODES_COUNT: usize = 10;
+
+pub struct TotpService;
+
+impl TotpService {
+    pub fn is_enabled(admin: &admin::Model) -> bool {
+        admin.totp_secret.is_some() && admin.totp_enabled_at.is_some()
+    }
+
+    fn hash_recovery_code(code: &str) -> String {
+        format!("{:x}", Sha256::digest(code.trim().to_uppercase().as_bytes()))
+    }
+
+    async fn find_admin(db: &DbConn, admin_id: i32) -> Result<admin::Model, ServiceError> {
+        Query::find_admin_by_id(db, admin_id)
+            .await?
+            .ok_or(ServiceError::InvalidCredentials)
+    }
+
+    /// Secret is encrypted to the admin's public key, so it stays readable when the password changes
+    async fn encrypt_secret(admin: &admin::Model, secret: &str) -> Result<String, ServiceError> {
+        crypto::encrypt_password_with_recipients(secret, &vec![admin.public_key.as_str()]).await
+    }
+
+    /// Secret can be read only with the private key unlocked by the admin password
+    async fn decrypt_secret(admin: &admin::Model, password: &str) -> Result<String, ServiceError> {
+        let enc_secret = admin.totp_secret.to_owned().ok_or(ServiceError::SecondFactorNotEnrolled)?;
+        let private_key = crypto::decrypt_password(admin.private_key.to_owned(), password.to_string())
+            .await
+            .map_err(|_| ServiceError::InvalidCredentials)?;
+
+        crypto::decrypt_password_with_private_key(&enc_secret, &private_key).await
+    }
+
+    /// Generates a new secret, the second factor is enabled only after `confirm`
+    pub async fn enrol(
+        db: &DbConn,
+        admin_id: i32,
+        password: String,
+    ) -> Result<TotpEnrolmentResponse, ServiceError> {
+        let admin = Self::find_admin(db, admin_id).await?;
+        if !crypto::verify_password(password.to_owned(), admin.password.to_owned()).await? {
+            return Err(ServiceError::InvalidCredentials);
+        }
+        // an enabled second factor can only be replaced after a reset
+        if Self::is_enabled(&admin) {
+            return Err(ServiceError::Forbidden);
+        }
+
+        let secret = totp::generate_secret();
+        let uri = totp::provisioning_uri(&secret, &admin.name, TOTP_ISSUER);
+
+        let enc_secret = Self::encrypt_secret(&admin, &secret).await?;
+        Mutation::update_admin_totp_secret(db, admin, enc_secret).await?;
+
+        Ok(TotpEnrolmentResponse { secret, uri })
+    }
+
+    /// Enables the enrolled second factor and returns new recovery codes
+    pub async fn confirm(
+        db: &DbConn,
+        admin_id: i32,
+        password: String,
+        code: String,
+    ) -> Result<RecoveryCodesResponse, ServiceError> {
+        let admin = Self::find_admin(db, admin_id).await?;
+        if Self::is_enabled(&admin) {
+            return Err(ServiceError::Forbidden);
+        }
+        let secret = Self::decrypt_secret(&admin, &password).await?;
+
+        let now = chrono::Utc::now();
+        let step = totp::verify(&secret, &code, now.timestamp(), None)
+            .ok_or(ServiceError::InvalidSecondFactor)?;
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODES_COUNT)
+            .map(|_| crypto::random_12_char_string())
+            .collect();
+        let hashes: Vec<String> = recovery_codes.iter()
+            .map(|c| Self::hash_recovery_code(c))
+            .collect();
+
+        Mutation::enable_admin_totp(db,
+            admin,
+            serde_json::to_string(&hashes).map_err(|_| ServiceError::FormatError)?,
+            step,
+            now.naive_utc(),
+        ).await?;
+
+        AuditService::log(db, Some(admin_id), None, None, AuditAction::EnableSecondFactor, None).await?;
+        Ok(RecoveryCodesResponse { recovery_codes })
+    }
+
+    /// Second login step, accepts either a current code or an unused recovery code.
+    /// Does nothing for admins without a second factor.
+    pub(in crate::services) async fn verify_login(
+        db: &DbConn,
+        admin: &admin::Model,
+        password: &str,
+        code: Option<String>,
+    ) -> Result<(), ServiceError> {
+        if !Self::is_enabled(admin) {
+            return Ok(());
+        }
+        // password is checked first, so the second step is revealed only to someone who knows it
+        let secret = Self::decrypt_secret(admin, password).await?;
+        let Some(code) = code.filter(|c| !c.trim().is_empty()) else {
+            return Err(ServiceError::SecondFactorRequired);
+        };
+
+        if let Some(step) = totp::verify(&secret, &code, chrono::Utc::now().timestamp(), admin.totp_last_step) {
+            Mutation::update_admin_totp_usage(db,
+                admin.to_owned(),
+                admin.totp_recovery_codes.to_owned(),
+                Some(step),
+            ).await?;
+            return Ok(());
+        }
+
+        let mut hashes: Vec<String> = admin.totp_recovery_codes.as_deref()
+            .map(serde_json::from_str)
+            .transpose()
+            .map_err(|_| ServiceError::FormatError)?
+            .unwrap_or_default();
+        let hash = Self::hash_recovery_code(&code);
+        let Some(position) = hashes.iter().position(|h| *h == hash) else {
+            return Err(ServiceError::InvalidSecondFactor);
+        };
+        hashes.remove(position);
+
+        Mutation::update_admin_totp_usage(db,
+            admin.to_owned(),
+            Some(serde_json::to_string(&hashes).map_err(|_| ServiceError::FormatError)?),
+            admin.totp_last_step,
+        ).await?;
+        Ok(())
+    }
+
+    /// Removes the second factor of an admin who lost access to it (cli)
+    pub async fn reset(db: &DbConn, admin_id: i32) -> Result<(), ServiceError> {
+        let admin = Self::find_admin(db, admin_id).await?;
+        Mutation::reset_admin_totp(db, admin).await?;
+
+        AuditService::log(db, None, None, None, AuditAction::ResetSecondFactor, Some(format!("admin={}", admin_id))).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use entity::admin;
+    use sea_orm::{ActiveModelTrait, DbConn, Set};
+
+    use crate::{crypto, error::ServiceError, Query, utils::{db::get_memory_sqlite_connection, totp}};
+
+    use super::TotpService;
+
+    async fn insert_admin(db: &DbConn) -> admin::Model {
+        let (public_key, private_key) = crypto::create_identity();
+        admin::ActiveModel {
+            name: Set("admin".to_string()),
+            public_key: Set(public_key),
+            private_key: Set(crypto::encrypt_password(private_key, "test".to_string()).await.unwrap()),
+            password: Set(crypto::hash_password("test".to_string()).await.unwrap()),
+            role: Set("superadmin".to_string()),
+            created_at: Set(Utc::now().naive_utc()),
+            updated_at: Set(Utc::now().naive_utc()),
+            ..Default::default()
+        }
+            .insert(db)
+            .await
+            .unwrap()
+    }
+
+    fn current_code(secret: &str, offset: i64) -> String {
+        let step = totp::step_at(Utc::now().timestamp()) + offset;
+        totp::code_at_step(secret, step).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_enrol_and_login() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = insert_admin(&db).await;
+
+        assert!(TotpService::enrol(&db, admin.id, "wrong".to_string()).await.is_err());
+        let enrolment = TotpService::enrol(&db, admin.id, "test".to_string()).await.unwrap();
+        assert!(enrolment.uri.contains(&enrolment.secret));
+
+        // not enabled until confirmed
+        let admin = Query::find_admin_by_id(&db, admin.id).await.unwrap().unwrap();
+        assert!(!TotpService::is_enabled(&admin));
+        assert!(TotpService::verify_login(&db, &admin, "test", None).await.is_ok());
+
+        let codes = TotpService::confirm(&db,
+            admin.id,
+            "test".to_string(),
+            current_code(&enrolment.secret, 0),
+        ).await.unwrap();
+        assert_eq!(codes.recovery_codes.len(), 10);
+
+        let admin = Query::find_admin_by_id(&db, admin.id).await.unwrap().unwrap();
+        assert!(TotpService::is_enabled(&admin));
+        assert!(matches!(
+            TotpService::verify_login(&db, &admin, "test", None).await,
+            Err(ServiceError::SecondFactorRequired)
+        ));
+        assert!(matches!(
+            TotpService::verify_login(&db, &admin, "wrong", None).await,
+            Err(ServiceError::InvalidCredentials)
+        ));
+        assert!(matches!(
+            TotpService::verify_login(&db, &admin, "test", Some("not a code".to_string())).await,
+            Err(ServiceError::InvalidSecondFactor)
+        ));
+
+        // step used for the confirmation can't be used again, the next one is still accepted
+        let code = current_code(&enrolment.secret, 1);
+        assert!(TotpService::verify_login(&db, &admin, "test", Some(code.to_owned())).await.is_ok());
+
+        // the same code can't be used twice
+        let admin = Query::find_admin_by_id(&db, admin.id).await.unwrap().unwrap();
+        assert!(TotpService::verify_login(&db, &admin, "test", Some(code)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_recovery_codes_and_reset() {
+        let db = get_memory_sqlite_connection().await;
+        let admin = insert_admin(&db).await;
+
+        let enrolment = TotpService::enrol(&db, admin.id, "test".to_string()).await.unwrap();
+        let codes = TotpService::confirm(&db,
+            admin.id,
+            "test".to_string(),
+            current_code(&enrolment.secret, 0),
+        ).await.unwrap();
+        let recovery_code = codes.recovery_codes[0].to_owned();
+
+        let admin = Query::find_admin_by_id(&db, admin.id).await.unwrap().unwrap();
+        assert!(TotpService::verify_login(&db, &admin, "test", Some(recovery_code.to_lowercase())).await.is_ok());
+
+        let admin = Query::find_admin_by_id(&db, admin.id).await.unwrap().unwrap();
+        assert!(TotpService::verify_login(&db, &admin, "test", Some(recovery_code)).await.is_err());
+        assert!(TotpService::verify_login(&db, &admin, "test", Some(codes.recovery_codes[1].to_owned())).await.is_ok());
+
+        TotpService::reset(&db, admin.id).await.unwrap();
+        let admin = Query::find_admin_by_id(&db, admin.id).await.unwrap().unwrap();
+        assert!(!TotpService::is_enabled(&admin));
+        assert!(admin.totp_secret.is_none());
+        assert!(TotpService::verify_login(&db, &admin, "test", None).await.is_ok());
+    }
+}
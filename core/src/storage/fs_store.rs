@@ -0,0 +1,147 @@
+use std::{path::{Path, PathBuf}, pin::Pin};
+
+use async_trait::async_trait;
+use tokio::io::AsyncRead;
+
+use crate::error::ServiceError;
+
+use super::PortfolioStore;
+
+/// Files stored in local directory `PORTFOLIO_STORE_PATH`
+pub struct FsStore {
+    root: PathBuf,
+}
+
+impl FsStore {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    pub fn from_env() -> Self {
+        dotenv::dotenv().ok();
+        Self::new(Path::new(&std::env::var("PORTFOLIO_STORE_PATH").unwrap_or_else(|_| "".to_string())).to_path_buf())
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.root.join(key)
+    }
+}
+
+#[async_trait]
+impl PortfolioStore for FsStore {
+    async fn create_dir(&self, key: &str) -> Result<(), ServiceError> {
+        tokio::fs::create_dir_all(self.path(key)).await?;
+        Ok(())
+    }
+
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        tokio::fs::write(self.path(key), data).await?;
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, local_path: &Path) -> Result<(), ServiceError> {
+        let path = self.path(key);
+        // rename fails when temp directory is on another filesystem
+        if tokio::fs::rename(local_path, &path).await.is_err() {
+            let part_path = path.with_extension("part");
+            tokio::fs::copy(local_path, &part_path).await?;
+            tokio::fs::rename(&part_path, &path).await?;
+            tokio::fs::remove_file(local_path).await?;
+        }
+        Ok(())
+    }
+
+    async fn reader(&self, key: &str) -> Result<Pin<Box<dyn AsyncRead + Send>>, ServiceError> {
+        let file = tokio::fs::File::open(self.path(key)).await?;
+        Ok(Box::pin(file))
+    }
+
+    async fn exists(&self, key: &str) -> bool {
+        tokio::fs::metadata(self.path(key)).await.is_ok()
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ServiceError> {
+        tokio::fs::remove_file(self.path(key)).await?;
+        Ok(())
+    }
+
+    async fn delete_dir(&self, key: &str) -> Result<(), ServiceError> {
+        tokio::fs::remove_dir_all(self.path(key)).await?;
+        Ok(())
+    }
+
+    async fn list(&self, key: &str) -> Result<(Vec<String>, Vec<String>), ServiceError> {
+        let relative = |path: &Path| -> Result<String, ServiceError> {
+            let relative = path.strip_prefix(&self.root)
+                .map_err(|e| ServiceError::StorageError(e.to_string()))?;
+            Ok(
+                relative.components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+                    .join("/")
+            )
+        };
+
+        let mut directories = vec![];
+        let mut files = vec![];
+        let mut pending: Vec<PathBuf> = vec![self.path(key)];
+        while let Some(dir) = pending.pop() {
+            let mut read_dir = tokio::fs::read_dir(&dir).await?;
+            while let Some(entry) = read_dir.next_entry().await? {
+                let path = entry.path();
+                if entry.file_type().await?.is_dir() {
+                    directories.push(relative(&path)?);
+                    pending.push(path);
+                } else {
+                    files.push(relative(&path)?);
+                }
+            }
+        }
+        directories.sort();
+        files.sort();
+
+        Ok((directories, files))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fs_store_write_read_delete() {
+        let root = std::env::temp_dir().join(format!("portfolio_test_store_{}", uuid::Uuid::new_v4()));
+        let store = FsStore::new(root.to_owned());
+
+        store.create_dir("103/cache").await.unwrap();
+        assert!(store.exists("103").await);
+        assert!(!store.exists("104").await);
+
+        store.write("103/cache/PORTFOLIO.pdf", vec![1, 2, 3]).await.unwrap();
+        assert!(store.exists("103/cache/PORTFOLIO.pdf").await);
+        assert_eq!(
+            store.list("").await.unwrap(),
+            (vec!["103".to_string(), "103/cache".to_string()], vec!["103/cache/PORTFOLIO.pdf".to_string()])
+        );
+
+        let mut buffer = vec![];
+        store.reader("103/cache/PORTFOLIO.pdf").await.unwrap().read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![1, 2, 3]);
+
+        let local_path = crate::storage::get_temp_path("PORTFOLIO.age");
+        tokio::fs::write(&local_path, vec![4, 5]).await.unwrap();
+        store.put_file("103/PORTFOLIO.age", &local_path).await.unwrap();
+        assert!(!local_path.exists());
+        assert_eq!(tokio::fs::read(root.join("103/PORTFOLIO.age")).await.unwrap(), vec![4, 5]);
+
+        store.delete("103/PORTFOLIO.age").await.unwrap();
+        assert!(!store.exists("103/PORTFOLIO.age").await);
+
+        store.delete_dir("103").await.unwrap();
+        assert!(!store.exists("103/cache/PORTFOLIO.pdf").await);
+
+        tokio::fs::remove_dir_all(root).await.unwrap();
+    }
+}
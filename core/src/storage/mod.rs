@@ -0,0 +1,55 @@
+use std::{path::Path, pin::Pin};
+
+use async_trait::async_trait;
+use tokio::io::AsyncRead;
+
+use crate::error::ServiceError;
+
+pub mod fs_store;
+pub mod s3_store;
+
+pub use self::fs_store::FsStore;
+pub use self::s3_store::S3Store;
+
+/// Storage of portfolio files. Files are addressed by keys relative to the store root,
+/// e.g. `103/cache/PORTFOLIO.pdf` or `103/PORTFOLIO.age`
+#[async_trait]
+pub trait PortfolioStore: Send + Sync {
+    /// Makes sure directory under the key exists
+    async fn create_dir(&self, key: &str) -> Result<(), ServiceError>;
+
+    /// Writes (or overwrites) whole file
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError>;
+
+    /// Moves local file into the store, local file is removed afterwards
+    async fn put_file(&self, key: &str, local_path: &Path) -> Result<(), ServiceError>;
+
+    /// Returns reader of the file, the file is never loaded into memory as a whole
+    async fn reader(&self, key: &str) -> Result<Pin<Box<dyn AsyncRead + Send>>, ServiceError>;
+
+    /// Returns true if file or directory under the key exists
+    async fn exists(&self, key: &str) -> bool;
+
+    async fn delete(&self, key: &str) -> Result<(), ServiceError>;
+
+    /// Deletes directory and all files in it
+    async fn delete_dir(&self, key: &str) -> Result<(), ServiceError>;
+
+    /// Sorted keys of all directories and files under the key, `""` lists the whole store
+    async fn list(&self, key: &str) -> Result<(Vec<String>, Vec<String>), ServiceError>;
+}
+
+/// Returns store selected by `PORTFOLIO_STORE` (`fs` by default or `s3`)
+pub fn get_store() -> Result<Box<dyn PortfolioStore>, ServiceError> {
+    dotenv::dotenv().ok();
+    match std::env::var("PORTFOLIO_STORE").unwrap_or_else(|_| "fs".to_string()).as_str() {
+        "fs" => Ok(Box::new(FsStore::from_env())),
+        "s3" => Ok(Box::new(S3Store::from_env()?)),
+        other => Err(ServiceError::StorageError(format!("Unknown store {}", other))),
+    }
+}
+
+/// Unique path of a temporary local file
+pub(crate) fn get_temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("portfolio_{}_{}", uuid::Uuid::new_v4(), name))
+}
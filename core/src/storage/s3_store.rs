@@ -0,0 +1,188 @@
+use std::{path::Path, pin::Pin};
+
+use async_trait::async_trait;
+use s3::{creds::Credentials, Bucket, Region};
+use tokio::io::AsyncRead;
+
+use crate::error::ServiceError;
+
+use super::{get_temp_path, PortfolioStore};
+
+/// Files stored in S3 compatible object store (AWS, MinIO, ...)
+pub struct S3Store {
+    bucket: Bucket,
+}
+
+fn s3_error<E: ToString>(e: E) -> ServiceError {
+    ServiceError::StorageError(e.to_string())
+}
+
+fn env_var(name: &str) -> Result<String, ServiceError> {
+    std::env::var(name).map_err(|_| ServiceError::StorageError(format!("{} not set", name)))
+}
+
+impl S3Store {
+    pub fn new(
+        bucket: &str,
+        region: &str,
+        endpoint: &str,
+        access_key: &str,
+        secret_key: &str,
+    ) -> Result<Self, ServiceError> {
+        let region = Region::Custom {
+            region: region.to_string(),
+            endpoint: endpoint.to_string(),
+        };
+        let credentials = Credentials::new(Some(access_key), Some(secret_key), None, None, None)
+            .map_err(s3_error)?;
+
+        // path style addressing is required by MinIO
+        let bucket = Bucket::new(bucket, region, credentials)
+            .map_err(s3_error)?
+            .with_path_style();
+
+        Ok(Self { bucket })
+    }
+
+    /// Configured by `PORTFOLIO_S3_BUCKET`, `PORTFOLIO_S3_REGION`, `PORTFOLIO_S3_ENDPOINT`,
+    /// `PORTFOLIO_S3_ACCESS_KEY` and `PORTFOLIO_S3_SECRET_KEY`
+    pub fn from_env() -> Result<Self, ServiceError> {
+        dotenv::dotenv().ok();
+        Self::new(
+            &env_var("PORTFOLIO_S3_BUCKET")?,
+            &std::env::var("PORTFOLIO_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            &env_var("PORTFOLIO_S3_ENDPOINT")?,
+            &env_var("PORTFOLIO_S3_ACCESS_KEY")?,
+            &env_var("PORTFOLIO_S3_SECRET_KEY")?,
+        )
+    }
+
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>, ServiceError> {
+        let prefix = match prefix.trim_end_matches('/') {
+            "" => String::new(),
+            prefix => format!("{}/", prefix),
+        };
+        let results = self.bucket
+            .list(prefix, None)
+            .await
+            .map_err(s3_error)?;
+
+        let mut keys: Vec<String> = results.into_iter()
+            .flat_map(|r| r.contents)
+            .map(|o| o.key)
+            .collect();
+        keys.sort();
+        Ok(keys)
+    }
+}
+
+#[async_trait]
+impl PortfolioStore for S3Store {
+    /// Object stores have no directories, an empty marker object is created instead
+    async fn create_dir(&self, key: &str) -> Result<(), ServiceError> {
+        self.bucket
+            .put_object(format!("{}/.keep", key.trim_end_matches('/')), &[])
+            .await
+            .map_err(s3_error)?;
+        Ok(())
+    }
+
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<(), ServiceError> {
+        self.bucket.put_object(key, &data).await.map_err(s3_error)?;
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, local_path: &Path) -> Result<(), ServiceError> {
+        let mut file = tokio::fs::File::open(local_path).await?;
+        self.bucket.put_object_stream(&mut file, key).await.map_err(s3_error)?;
+        tokio::fs::remove_file(local_path).await?;
+        Ok(())
+    }
+
+    /// Object is downloaded into unlinked temporary file, so it can be read without holding it in memory
+    async fn reader(&self, key: &str) -> Result<Pin<Box<dyn AsyncRead + Send>>, ServiceError> {
+        let temp_path = get_temp_path("download");
+        let mut file = tokio::fs::File::create(&temp_path).await?;
+        let status = self.bucket
+            .get_object_to_writer(key, &mut file)
+            .await
+            .map_err(s3_error);
+        drop(file);
+
+        let file = match status {
+            Ok(_) => tokio::fs::File::open(&temp_path).await,
+            Err(e) => {
+                tokio::fs::remove_file(&temp_path).await?;
+                return Err(e);
+            }
+        }?;
+        tokio::fs::remove_file(&temp_path).await?;
+
+        Ok(Box::pin(file))
+    }
+
+    async fn exists(&self, key: &str) -> bool {
+        if self.bucket.head_object(key).await.is_ok() {
+            return true;
+        }
+        self.list_keys(key).await.map(|keys| !keys.is_empty()).unwrap_or(false)
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), ServiceError> {
+        self.bucket.delete_object(key).await.map_err(s3_error)?;
+        Ok(())
+    }
+
+    async fn delete_dir(&self, key: &str) -> Result<(), ServiceError> {
+        for object_key in self.list_keys(key).await? {
+            self.bucket.delete_object(object_key).await.map_err(s3_error)?;
+        }
+        Ok(())
+    }
+
+    /// Directories exist only as marker objects, so only files are listed
+    async fn list(&self, key: &str) -> Result<(Vec<String>, Vec<String>), ServiceError> {
+        Ok((vec![], self.list_keys(key).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+
+    fn test_store() -> S3Store {
+        let endpoint = std::env::var("PORTFOLIO_S3_TEST_ENDPOINT")
+            .expect("PORTFOLIO_S3_TEST_ENDPOINT must be set");
+        S3Store::new(
+            "portfolio-test",
+            "us-east-1",
+            &endpoint,
+            &std::env::var("PORTFOLIO_S3_TEST_ACCESS_KEY").unwrap_or_else(|_| "minioadmin".to_string()),
+            &std::env::var("PORTFOLIO_S3_TEST_SECRET_KEY").unwrap_or_else(|_| "minioadmin".to_string()),
+        ).unwrap()
+    }
+
+    /// Needs a MinIO (or other S3 compatible) instance with bucket `portfolio-test`, run with
+    /// `docker run -p 9000:9000 minio/minio server /data` and
+    /// `PORTFOLIO_S3_TEST_ENDPOINT=http://127.0.0.1:9000 cargo test -p portfolio-core -- --ignored s3_store`
+    #[tokio::test]
+    #[ignore]
+    async fn test_s3_store_write_read_delete() {
+        let store = test_store();
+
+        store.create_dir("103/cache").await.unwrap();
+        assert!(store.exists("103").await);
+
+        store.write("103/cache/PORTFOLIO.pdf", vec![1, 2, 3]).await.unwrap();
+        assert!(store.exists("103/cache/PORTFOLIO.pdf").await);
+
+        let mut buffer = vec![];
+        store.reader("103/cache/PORTFOLIO.pdf").await.unwrap().read_to_end(&mut buffer).await.unwrap();
+        assert_eq!(buffer, vec![1, 2, 3]);
+
+        store.delete_dir("103").await.unwrap();
+        assert!(!store.exists("103/cache/PORTFOLIO.pdf").await);
+    }
+}
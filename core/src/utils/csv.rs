@@ -6,9 +6,12 @@ use crate::{
 };
 use sea_orm::DbConn;
 use async_trait::async_trait;
-use crate::models::candidate::{CandidateRow, FieldOfStudy, FieldsCombination};
+use crate::models::candidate::{CandidateImportRow, CandidateRoundRow, CandidateRow};
 use crate::models::candidate_details::EncryptedCandidateDetails;
+use crate::models::admin::FieldScope;
+use crate::models::field_of_study::{our_school_name, FieldsCombination, FieldsOfStudy};
 use crate::models::school::School;
+use entity::{admission_round, field_of_study};
 
 impl TryFrom<(i32, ApplicationDetails)> for ApplicationRow {
     type Error = ServiceError;
@@ -61,18 +64,19 @@ impl TryFrom<(i32, ApplicationDetails)> for ApplicationRow {
 
 #[async_trait]
 pub trait CsvExporter {
-    async fn export(db: &DbConn, private_key: String) -> Result<Vec<u8>, ServiceError>;
+    /// Only candidates applying to a field of study within `scope` are exported
+    async fn export(db: &DbConn, private_key: String, scope: &FieldScope) -> Result<Vec<u8>, ServiceError>;
 }
 
 pub struct ApplicationCsv;
 
 #[async_trait]
 impl CsvExporter for ApplicationCsv {
-    async fn export(db: &DbConn, private_key: String) -> Result<Vec<u8>, ServiceError> {
+    async fn export(db: &DbConn, private_key: String, scope: &FieldScope) -> Result<Vec<u8>, ServiceError> {
         let mut wtr = csv::Writer::from_writer(vec![]);
 
         let applications = Query::list_applications_compact(&db).await?;
-        for application in applications {
+        for application in applications.into_iter().filter(|a| scope.allows(&a.field_of_study)) {
             let candidate = ApplicationService::find_related_candidate(db, &application).await?;
             let parents = Query::find_candidate_parents(db, &candidate).await?;
 
@@ -104,14 +108,22 @@ pub struct CandidateCsv;
 
 #[async_trait]
 impl CsvExporter for CandidateCsv {
-    async fn export(db: &DbConn, private_key: String) -> Result<Vec<u8>, ServiceError> {
+    async fn export(db: &DbConn, private_key: String, scope: &FieldScope) -> Result<Vec<u8>, ServiceError> {
         let mut wtr = csv::Writer::from_writer(vec![]);
 
         let candidates = Query::list_candidates_full(&db).await?;
         let applications = Query::list_applications_compact(&db).await?;
         let parents = Query::list_all_parents(&db).await?;
+        let fields = FieldsOfStudy::from(Query::list_fields_of_study(&db).await?);
+        let rounds = Query::list_admission_rounds(&db).await?;
+        let school_name = our_school_name();
+
+        wtr.write_record(CandidateRow::headers(&rounds))?;
 
         for model in candidates {
+            if !applications.iter().any(|a| a.candidate_id == model.id && scope.allows(&a.field_of_study)) {
+                continue;
+            }
             let (id, c) = (
                 model.id,
                 EncryptedCandidateDetails::from(&model).decrypt(&private_key).await?
@@ -127,31 +139,39 @@ impl CsvExporter for CandidateCsv {
                 .map(|p| p.id)
                 .collect::<Vec<i32>>();
 
+            let schools = [&c.first_school, &c.second_school];
+            let mut round_rows = vec![];
+            let mut round_fields = vec![];
+            for (i, round) in rounds.iter().enumerate() {
+                let school = get_school_for_round(&schools, round, i);
+                let field = match school {
+                    Some(school) => get_our_school_field(school, &school_name, &fields)?,
+                    None => None,
+                }
+                    // rounds linked to a field of study are only for candidates of that field
+                    .filter(|f| round.field_of_study_id.map_or(true, |id| id == f.id));
 
-            let (first_field, second_field) = (
-                get_our_school_field(&c.first_school).map_err(|_| ServiceError::InvalidFieldOfStudy)?,
-                get_our_school_field(&c.second_school).map_err(|_| ServiceError::InvalidFieldOfStudy)?,
-            );
+                round_rows.push(CandidateRoundRow {
+                    admissions: field.is_some(),
+                    field: field.map(|f| f.code.to_owned()),
+                    school: school.map(|s| s.name().to_string()).unwrap_or_default(),
+                    school_field: school.map(|s| s.field().to_string()).unwrap_or_default(),
+                });
+                round_fields.push(field);
+            }
 
-            let applications_fields_comb = get_applications_fields_comb(&related_applications);
+            let applications_fields_comb = get_applications_fields_comb(&related_applications, &fields);
 
-            let fields_combination = FieldsCombination::from_fields(&first_field, &second_field);
+            let fields_combination = FieldsCombination::from_fields(round_fields);
             let fields_match = applications_fields_comb == fields_combination;
 
             let row = CandidateRow {
                 id,
                 first_application: *related_applications.first().ok_or(ServiceError::CandidateNotFound)?,
                 second_application: related_applications.get(1).map(|id| *id).to_owned(),
-                first_school: c.first_school.name().to_string(),
-                first_school_field: c.first_school.field().to_string(),
-                second_school: c.second_school.name().to_string(),
-                second_school_field: c.second_school.field().to_string(),
-                first_day_admissions: first_field.is_some(),
-                second_day_admissions: second_field.is_some(),
-                first_day_field: first_field.to_owned(),
-                second_day_field: second_field.to_owned(),
-                fields_combination,
                 personal_id_number: c.personal_id_number.to_string(),
+                rounds: round_rows,
+                fields_combination,
                 fields_match,
                 name: c.name.to_owned(),
                 surname: c.surname.to_owned(),
@@ -160,32 +180,87 @@ impl CsvExporter for CandidateCsv {
                 parent_email: parents.first().map(|id| id.to_string()),
                 parent_telephone: parents.first().map(|id| id.to_string()),
             };
-            wtr.serialize(row)?;
+            wtr.write_record(row.into_record())?;
         }
         wtr.into_inner()
             .map_err(|_| ServiceError::CsvIntoInnerError)
     }
 }
 
+/// Rows of the candidate import with their line numbers, `;` separated files
+/// exported from spreadsheets with the Czech locale are accepted as well
+pub fn parse_candidate_import(data: &[u8]) -> Result<Vec<(u64, Result<CandidateImportRow, ServiceError>)>, ServiceError> {
+    let header = data.split(|b| *b == b'\n').next().unwrap_or_default();
+    let delimiter = if header.contains(&b';') && !header.contains(&b',') { b';' } else { b',' };
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(data);
+    let headers = rdr.headers()?.clone();
+
+    Ok(
+        rdr.records()
+            .map(|record| match record {
+                Ok(record) => (
+                    record.position().map_or(0, |p| p.line()),
+                    record.deserialize(Some(&headers)).map_err(|_| ServiceError::InvalidImportRow),
+                ),
+                Err(e) => (
+                    e.position().map_or(0, |p| p.line()),
+                    Err(ServiceError::InvalidImportRow),
+                ),
+            })
+            .collect()
+    )
+}
+
+/// Application ids and passwords from the `application_id,password` CSV written by the import
+pub fn parse_credentials(data: &[u8]) -> Result<Vec<(i32, String)>, ServiceError> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(data);
+
+    Ok(
+        rdr.deserialize()
+            .collect::<Result<Vec<(i32, String)>, csv::Error>>()?
+    )
+}
+
 fn get_applications_fields_comb(
     related_applications: &[i32],
+    fields: &FieldsOfStudy,
 ) -> FieldsCombination {
-    let fields_vec = related_applications.iter().map(|id| FieldOfStudy::from(*id)).collect::<Vec<_>>();
     FieldsCombination::from_fields(
-        &fields_vec.first().map(|f| f.to_owned()),
-        &fields_vec.get(1).map(|f| f.to_owned()),
+        related_applications.iter().map(|id| fields.by_application_id(*id))
     )
 }
 
-fn get_our_school_field(school: &School) -> Result<Option<FieldOfStudy>, ServiceError> {
-    if school.name() == "Smíchovská střední průmyslová škola a gymnázium" {
+/// School explicitly linked to the round, otherwise school on the same position as the round
+fn get_school_for_round<'a>(
+    schools: &[&'a School],
+    round: &admission_round::Model,
+    position: usize,
+) -> Option<&'a School> {
+    schools.iter()
+        .find(|s| s.admission_round() == Some(round.id))
+        .or_else(|| schools.get(position).filter(|s| s.admission_round().is_none()))
+        .copied()
+}
+
+fn get_our_school_field<'a>(
+    school: &School,
+    school_name: &str,
+    fields: &'a FieldsOfStudy,
+) -> Result<Option<&'a field_of_study::Model>, ServiceError> {
+    if school.name() == school_name {
         Ok(
             Some(
-                FieldOfStudy::try_from(school.field().to_owned())?
+                fields.by_name(school.field()).ok_or(ServiceError::InvalidFieldOfStudy)?
             )
         )
     } else {
         Ok(None)
     }
 }
-
@@ -1,4 +1,4 @@
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 
 use crate::error::ServiceError;
 
@@ -10,4 +10,13 @@ pub fn parse_naive_date_from_opt_str(date: Option<String>, fmt: &str) -> Result<
                 .ok_or(ServiceError::InvalidDate)?
             )
     )
-}
\ No newline at end of file
+}
+
+/// Parses `2023-04-13 08:00:00`, `2023-04-13T08:00:00` or just `2023-04-13` (midnight)
+pub fn parse_naive_datetime(datetime: &str) -> Result<NaiveDateTime, ServiceError> {
+    NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(datetime, "%Y-%m-%d").ok()?.and_hms_opt(0, 0, 0))
+        .ok_or(ServiceError::InvalidDate)
+}
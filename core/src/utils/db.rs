@@ -12,7 +12,8 @@ pub async fn get_recipients(db: &DbConn, candidate_pubkey: &str) -> Result<Vec<S
 }
 
 pub async fn get_memory_sqlite_connection() -> sea_orm::DbConn {
-    use entity::{admin, candidate, parent, session};
+    use entity::{admin, candidate, parent, session, audit_log, field_of_study, admission_round, login_throttle};
+    use sea_orm::{ActiveModelTrait, Set};
     use sea_orm::{Schema, Database};
     use sea_orm::{sea_query::TableCreateStatement, ConnectionTrait, DbBackend};
 
@@ -45,12 +46,38 @@ pub async fn get_memory_sqlite_connection() -> sea_orm::DbConn {
     let stmt4: TableCreateStatement = schema.create_table_from_entity(admin::Entity);
     let stmt5: TableCreateStatement = schema.create_table_from_entity(admin_session::Entity);
     let stmt6: TableCreateStatement = schema.create_table_from_entity(parent::Entity);
+    let stmt7: TableCreateStatement = schema.create_table_from_entity(audit_log::Entity);
+    let stmt8: TableCreateStatement = schema.create_table_from_entity(field_of_study::Entity);
+    let stmt9: TableCreateStatement = schema.create_table_from_entity(admission_round::Entity);
+    let stmt10: TableCreateStatement = schema.create_table_from_entity(login_throttle::Entity);
     db.execute(db.get_database_backend().build(&stmt)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt2)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt3)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt4)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt5)).await.unwrap();
     db.execute(db.get_database_backend().build(&stmt6)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt7)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt8)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt9)).await.unwrap();
+    db.execute(db.get_database_backend().build(&stmt10)).await.unwrap();
+
+    // same fields of study as seeded by the migration
+    for (prefix, code, name) in [
+        ("101", "G", "7941K41-Gymnázium"),
+        ("102", "IT", "1820M01-Informační technologie"),
+        ("103", "KB", "1820M01-Informační technologie - Kybernetická bezpečnost"),
+    ] {
+        field_of_study::ActiveModel {
+            prefix: Set(prefix.to_string()),
+            code: Set(code.to_string()),
+            name: Set(name.to_string()),
+            capacity: Set(30),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+    }
 
     // switch everything from varchars to text
     let query = "ALTER TABLE candidate MODIFY COLUMN name text; ALTER TABLE candidate MODIFY COLUMN surname text; ALTER TABLE candidate MODIFY COLUMN birth_surname text; ALTER TABLE candidate MODIFY COLUMN birthplace text; ALTER TABLE candidate MODIFY COLUMN address text; ALTER TABLE candidate MODIFY COLUMN letter_address text; ALTER TABLE candidate MODIFY COLUMN telephone text; ALTER TABLE candidate MODIFY COLUMN citizenship text; ALTER TABLE candidate MODIFY COLUMN email text; ALTER TABLE candidate MODIFY COLUMN sex text; ALTER TABLE candidate MODIFY COLUMN school_name text; ALTER TABLE candidate MODIFY COLUMN personal_identification_number text; ALTER TABLE candidate MODIFY COLUMN health_insurance text; ALTER TABLE candidate MODIFY COLUMN grades_json text; ALTER TABLE candidate MODIFY COLUMN first_school text; ALTER TABLE candidate MODIFY COLUMN second_school text; ALTER TABLE candidate MODIFY COLUMN test_language text; ALTER TABLE parent MODIFY COLUMN name text; ALTER TABLE parent MODIFY COLUMN surname text; ALTER TABLE parent MODIFY COLUMN telephone text; ALTER TABLE parent MODIFY COLUMN email text; ALTER TABLE application MODIFY COLUMN personal_id_number text; ALTER TABLE candidate MODIFY COLUMN birthdate text;".to_string();
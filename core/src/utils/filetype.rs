@@ -1,3 +1,8 @@
+use futures::io::{AsyncReadExt, Cursor};
+use serde::{Serialize, Deserialize};
+
+use crate::error::ServiceError;
+
 pub fn filetype_is_pdf(buffer: &[u8]) -> bool {
     infer::archive::is_pdf(buffer)
 }
@@ -6,6 +11,160 @@ pub fn filetype_is_zip(buffer: &[u8]) -> bool {
     infer::archive::is_zip(buffer)
 }
 
+/// Number of bytes from the start of an entry used to detect its type
+const SNIFF_LEN: usize = 8192;
+const DEFAULT_MAX_ENTRIES: usize = 100;
+const DEFAULT_MAX_UNCOMPRESSED_SIZE: u64 = 500 * 1024 * 1024;
+
+/// Limits of the portfolio zip, configurable with `PORTFOLIO_ZIP_MAX_ENTRIES` and `PORTFOLIO_ZIP_MAX_SIZE` (bytes)
+#[derive(Debug, Clone, Copy)]
+pub struct ZipLimits {
+    pub max_entries: usize,
+    pub max_uncompressed_size: u64,
+}
+
+impl Default for ZipLimits {
+    fn default() -> Self {
+        Self {
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_uncompressed_size: DEFAULT_MAX_UNCOMPRESSED_SIZE,
+        }
+    }
+}
+
+impl ZipLimits {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+        Self {
+            max_entries: std::env::var("PORTFOLIO_ZIP_MAX_ENTRIES").ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_entries),
+            max_uncompressed_size: std::env::var("PORTFOLIO_ZIP_MAX_SIZE").ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.max_uncompressed_size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ZipEntryKind {
+    Pdf,
+    Image,
+    Video,
+}
+
+impl ZipEntryKind {
+    /// Detects kind of the file from its first bytes, None for file types not allowed in portfolio
+    pub fn detect(head: &[u8]) -> Option<Self> {
+        if filetype_is_pdf(head) {
+            return Some(ZipEntryKind::Pdf);
+        }
+        match infer::get(head)?.matcher_type() {
+            infer::MatcherType::Image => Some(ZipEntryKind::Image),
+            infer::MatcherType::Video => Some(ZipEntryKind::Video),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipManifestEntry {
+    pub name: String,
+    pub kind: ZipEntryKind,
+    pub size: u64,
+}
+
+/// Files found in the portfolio zip, stored next to the cached zip and shown to the candidate
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ZipManifest {
+    pub entries: Vec<ZipManifestEntry>,
+    pub total_size: u64,
+}
+
+/// Relative name without `..` components, safe to be joined to a directory path
+pub fn is_safe_entry_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('/')
+        && !name.starts_with('\\')
+        && !name.contains(':')
+        && !name.split(|c| c == '/' || c == '\\').any(|part| part == "..")
+}
+
+/// Metadata added by macOS Finder (`__MACOSX/` resource forks and `.DS_Store`), left out of the manifest
+fn is_junk_entry_name(name: &str) -> bool {
+    name.starts_with("__MACOSX/")
+        || name.rsplit(|c| c == '/' || c == '\\').next() == Some(".DS_Store")
+}
+
+/// Reads all entries of the zip and checks that it contains only PDFs, images and videos
+/// within the limits. Entry sizes are counted from the decompressed data, sizes declared
+/// in the zip headers are not trusted.
+pub async fn inspect_zip(data: &[u8], limits: &ZipLimits) -> Result<ZipManifest, ServiceError> {
+    let invalid = |reason: String| ServiceError::InvalidPortfolioZip(reason);
+
+    // malformed zip is the candidate's fault, not an internal error
+    let mut reader = async_zip::base::read::seek::ZipFileReader::new(Cursor::new(data))
+        .await
+        .map_err(|e| invalid(e.to_string()))?;
+    let entries: Vec<(String, bool)> = reader.file()
+        .entries()
+        .iter()
+        .map(|e| {
+            let entry = e.entry();
+            let name = entry.filename().as_str().map(|n| n.to_string()).unwrap_or_default();
+            (name, entry.dir().unwrap_or(false))
+        })
+        .collect();
+
+    if entries.iter().filter(|(name, _)| !is_junk_entry_name(name)).count() > limits.max_entries {
+        return Err(invalid(format!("more than {} files", limits.max_entries)));
+    }
+
+    let mut manifest = ZipManifest::default();
+    let mut buffer = vec![0u8; 64 * 1024];
+    for (index, (name, is_dir)) in entries.into_iter().enumerate() {
+        if !is_safe_entry_name(&name) {
+            return Err(invalid(format!("invalid file name {:?}", name)));
+        }
+        if is_dir || is_junk_entry_name(&name) {
+            continue;
+        }
+
+        let mut entry_reader = reader.reader_without_entry(index)
+            .await
+            .map_err(|e| invalid(e.to_string()))?;
+        let mut head = Vec::with_capacity(SNIFF_LEN);
+        let mut size = 0u64;
+        loop {
+            let read = entry_reader.read(&mut buffer)
+                .await
+                .map_err(|e| invalid(e.to_string()))?;
+            if read == 0 {
+                break;
+            }
+            size += read as u64;
+            if manifest.total_size + size > limits.max_uncompressed_size {
+                return Err(invalid(format!("larger than {} bytes", limits.max_uncompressed_size)));
+            }
+            if head.len() < SNIFF_LEN {
+                let missing = (SNIFF_LEN - head.len()).min(read);
+                head.extend_from_slice(&buffer[..missing]);
+            }
+        }
+
+        let kind = ZipEntryKind::detect(&head)
+            .ok_or_else(|| invalid(format!("file {:?} is not a PDF, image or video", name)))?;
+
+        manifest.total_size += size;
+        manifest.entries.push(ZipManifestEntry { name, kind, size });
+    }
+
+    Ok(manifest)
+}
+
 mod tests {
 
     #[test]
@@ -36,5 +195,78 @@ mod tests {
         assert!(!super::filetype_is_zip(EMPTY));
     }
 
-}
 
+    #[cfg(test)]
+    async fn create_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = vec![];
+        let mut writer = async_zip::base::write::ZipFileWriter::new(&mut zip);
+        for (name, data) in files {
+            let builder = async_zip::ZipEntryBuilder::new(
+                name.to_string().into(),
+                async_zip::Compression::Deflate,
+            );
+            writer.write_entry_whole(builder, data).await.unwrap();
+        }
+        writer.close().await.unwrap();
+        zip
+    }
+
+    #[tokio::test]
+    async fn test_inspect_zip() {
+        const PDF: &[u8] = b"%PDF-1.4\n%%EOF\n";
+        const PNG: &[u8] = &[0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D, 0x49, 0x48, 0x44, 0x52];
+
+        let zip = create_zip(&[("dopis.pdf", PDF), ("foto/obrazek.png", PNG)]).await;
+        let manifest = super::inspect_zip(&zip, &super::ZipLimits::default()).await.unwrap();
+
+        assert_eq!(manifest.entries.len(), 2);
+        assert_eq!(manifest.entries[0].kind, super::ZipEntryKind::Pdf);
+        assert_eq!(manifest.entries[1].kind, super::ZipEntryKind::Image);
+        assert_eq!(manifest.total_size, (PDF.len() + PNG.len()) as u64);
+
+        let limits = super::ZipLimits { max_entries: 1, ..Default::default() };
+        assert!(super::inspect_zip(&zip, &limits).await.is_err());
+
+        let limits = super::ZipLimits { max_uncompressed_size: 10, ..Default::default() };
+        assert!(super::inspect_zip(&zip, &limits).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_inspect_zip_macos_junk() {
+        const PDF: &[u8] = b"%PDF-1.4\n%%EOF\n";
+        const APPLE_DOUBLE: &[u8] = &[0x00, 0x05, 0x16, 0x07, 0, 0x02, 0, 0, 0x4D, 0x61, 0x63, 0x20, 0x4F, 0x53, 0x20, 0x58];
+
+        // zip compressed by Finder
+        let zip = create_zip(&[
+            ("dopis.pdf", PDF),
+            ("__MACOSX/._dopis.pdf", APPLE_DOUBLE),
+            (".DS_Store", APPLE_DOUBLE),
+            ("foto/.DS_Store", APPLE_DOUBLE),
+        ]).await;
+        let manifest = super::inspect_zip(&zip, &super::ZipLimits { max_entries: 1, ..Default::default() }).await.unwrap();
+
+        assert_eq!(manifest.entries.iter().map(|e| e.name.as_str()).collect::<Vec<_>>(), vec!["dopis.pdf"]);
+        assert_eq!(manifest.total_size, PDF.len() as u64);
+    }
+
+    #[tokio::test]
+    async fn test_inspect_zip_rejected() {
+        const PDF: &[u8] = b"%PDF-1.4\n%%EOF\n";
+        const EXE: &[u8] = &[0x4D, 0x5A, 0x90, 0, 3, 0, 0, 0, 4, 0, 0, 0, 0xFF, 0xFF, 0, 0];
+
+        let limits = super::ZipLimits::default();
+        for files in [
+            vec![("program.exe", EXE)],
+            vec![("../dopis.pdf", PDF)],
+            vec![("/etc/dopis.pdf", PDF)],
+        ] {
+            let zip = create_zip(&files).await;
+            assert!(super::inspect_zip(&zip, &limits).await.is_err());
+        }
+
+        let nested = create_zip(&[("dopis.pdf", PDF)]).await;
+        let zip = create_zip(&[("portfolio.zip", nested.as_slice())]).await;
+        assert!(super::inspect_zip(&zip, &limits).await.is_err());
+    }
+
+}
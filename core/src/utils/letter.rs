@@ -0,0 +1,193 @@
+use printpdf::{BuiltinFont, Color, IndirectFontRef, Line, Mm, PdfDocument, PdfDocumentReference, PdfLayerReference, Point, Rgb};
+use qrcode::QrCode;
+
+use crate::{error::ServiceError, models::field_of_study::our_school_name};
+
+const DEFAULT_LOGIN_URL: &str = "https://portfolio.ssps.cz";
+const PAGE_WIDTH: Mm = Mm(210.0);
+const PAGE_HEIGHT: Mm = Mm(297.0);
+const MARGIN: f64 = 20.0;
+const QR_CODE_SIZE: f64 = 40.0;
+const DEFAULT_MAX_LETTERS: usize = 500;
+
+/// Credentials of one application printed on its own page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CredentialLetter {
+    pub application_id: i32,
+    pub field_of_study: String,
+    pub password: String,
+}
+
+/// School header and login page printed on the letters, configurable with `PORTFOLIO_SCHOOL_NAME`,
+/// `PORTFOLIO_LOGIN_URL` and `PORTFOLIO_LETTER_FONT` (path to a TTF font with Czech characters).
+/// Every password is verified before printing, `PORTFOLIO_MAX_LETTERS` limits letters of one request.
+#[derive(Debug, Clone)]
+pub struct LetterConfig {
+    pub school_name: String,
+    pub login_url: String,
+    pub font: Option<String>,
+    pub max_letters: usize,
+}
+
+impl LetterConfig {
+    pub fn from_env() -> Self {
+        Self {
+            school_name: our_school_name(),
+            login_url: std::env::var("PORTFOLIO_LOGIN_URL").unwrap_or(DEFAULT_LOGIN_URL.to_string()),
+            font: std::env::var("PORTFOLIO_LETTER_FONT").ok(),
+            max_letters: std::env::var("PORTFOLIO_MAX_LETTERS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_MAX_LETTERS),
+        }
+    }
+}
+
+fn pdf_error(e: impl ToString) -> ServiceError {
+    ServiceError::PdfError(e.to_string())
+}
+
+/// Built-in PDF fonts can't encode Czech characters, text printed with them loses diacritics
+fn strip_diacritics(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            'á' => 'a', 'č' => 'c', 'ď' => 'd', 'é' | 'ě' => 'e', 'í' => 'i', 'ň' => 'n', 'ó' => 'o',
+            'ř' => 'r', 'š' => 's', 'ť' => 't', 'ú' | 'ů' => 'u', 'ý' => 'y', 'ž' => 'z',
+            'Á' => 'A', 'Č' => 'C', 'Ď' => 'D', 'É' | 'Ě' => 'E', 'Í' => 'I', 'Ň' => 'N', 'Ó' => 'O',
+            'Ř' => 'R', 'Š' => 'S', 'Ť' => 'T', 'Ú' | 'Ů' => 'U', 'Ý' => 'Y', 'Ž' => 'Z',
+            c if c.is_ascii() => c,
+            _ => '?',
+        })
+        .collect()
+}
+
+struct Fonts {
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    mono: IndirectFontRef,
+    unicode: bool,
+}
+
+impl Fonts {
+    fn load(doc: &PdfDocumentReference, config: &LetterConfig) -> Result<Self, ServiceError> {
+        if let Some(path) = &config.font {
+            let font = doc.add_external_font(std::fs::File::open(path)?).map_err(pdf_error)?;
+            return Ok(Self { regular: font.clone(), bold: font.clone(), mono: font, unicode: true });
+        }
+
+        Ok(Self {
+            regular: doc.add_builtin_font(BuiltinFont::Helvetica).map_err(pdf_error)?,
+            bold: doc.add_builtin_font(BuiltinFont::HelveticaBold).map_err(pdf_error)?,
+            mono: doc.add_builtin_font(BuiltinFont::CourierBold).map_err(pdf_error)?,
+            unicode: false,
+        })
+    }
+
+    fn text(&self, layer: &PdfLayerReference, text: &str, size: f64, y: f64, font: &IndirectFontRef) {
+        let text = if self.unicode { text.to_string() } else { strip_diacritics(text) };
+        layer.use_text(text, size, Mm(MARGIN), Mm(y), font);
+    }
+}
+
+/// Dark modules of the QR code as filled squares, `x` and `y` is the bottom left corner
+fn draw_qr_code(layer: &PdfLayerReference, data: &str, x: f64, y: f64, size: f64) -> Result<(), ServiceError> {
+    let code = QrCode::new(data.as_bytes()).map_err(pdf_error)?;
+    let width = code.width();
+    let module = size / width as f64;
+
+    layer.set_fill_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        if color != qrcode::Color::Dark {
+            continue;
+        }
+        let left = x + (i % width) as f64 * module;
+        let bottom = y + size - (i / width + 1) as f64 * module;
+        layer.add_shape(Line {
+            points: vec![
+                (Point::new(Mm(left), Mm(bottom)), false),
+                (Point::new(Mm(left + module), Mm(bottom)), false),
+                (Point::new(Mm(left + module), Mm(bottom + module)), false),
+                (Point::new(Mm(left), Mm(bottom + module)), false),
+            ],
+            is_closed: true,
+            has_fill: true,
+            has_stroke: false,
+            is_clipping_path: false,
+        });
+    }
+    Ok(())
+}
+
+fn draw_letter(layer: &PdfLayerReference, fonts: &Fonts, config: &LetterConfig, letter: &CredentialLetter) -> Result<(), ServiceError> {
+    fonts.text(layer, &config.school_name, 16.0, 272.0, &fonts.bold);
+    fonts.text(layer, "Přístupové údaje do přihláškového systému", 14.0, 250.0, &fonts.bold);
+
+    fonts.text(layer, &format!("Evidenční číslo přihlášky: {}", letter.application_id), 12.0, 235.0, &fonts.regular);
+    fonts.text(layer, &format!("Obor: {}", letter.field_of_study), 12.0, 227.0, &fonts.regular);
+    fonts.text(layer, "Heslo:", 12.0, 219.0, &fonts.regular);
+    layer.use_text(letter.password.to_owned(), 14.0, Mm(MARGIN + 15.0), Mm(219.0), &fonts.mono);
+    fonts.text(layer, &format!("Přihlásit se můžete na adrese {}", config.login_url), 12.0, 205.0, &fonts.regular);
+
+    draw_qr_code(layer, &config.login_url, MARGIN, 155.0, QR_CODE_SIZE)?;
+
+    fonts.text(layer, "Heslo si po prvním přihlášení můžete změnit.", 10.0, 140.0, &fonts.regular);
+    fonts.text(layer, "Dopis si uschovejte, heslo není možné znovu zobrazit, pouze obnovit ve škole.", 10.0, 134.0, &fonts.regular);
+    Ok(())
+}
+
+/// One PDF with a page per letter
+pub fn render_letters(letters: &[CredentialLetter], config: &LetterConfig) -> Result<Vec<u8>, ServiceError> {
+    let Some((first, rest)) = letters.split_first() else {
+        return Err(ServiceError::NoCredentials);
+    };
+
+    let (doc, page, layer) = PdfDocument::new("Přístupové údaje", PAGE_WIDTH, PAGE_HEIGHT, "letter");
+    let fonts = Fonts::load(&doc, config)?;
+
+    draw_letter(&doc.get_page(page).get_layer(layer), &fonts, config, first)?;
+    for letter in rest {
+        let (page, layer) = doc.add_page(PAGE_WIDTH, PAGE_HEIGHT, "letter");
+        draw_letter(&doc.get_page(page).get_layer(layer), &fonts, config, letter)?;
+    }
+
+    doc.save_to_bytes().map_err(pdf_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{error::ServiceError, utils::filetype::filetype_is_pdf};
+
+    use super::{render_letters, strip_diacritics, CredentialLetter, LetterConfig};
+
+    fn config() -> LetterConfig {
+        LetterConfig {
+            school_name: "SSPŠ".to_string(),
+            login_url: "https://portfolio.example.com".to_string(),
+            font: None,
+            max_letters: 10,
+        }
+    }
+
+    #[test]
+    fn test_render_letters() {
+        let letter = |application_id| CredentialLetter {
+            application_id,
+            field_of_study: "Informační technologie".to_string(),
+            password: "abcdefghijkl".to_string(),
+        };
+
+        let single = render_letters(&[letter(102151)], &config()).unwrap();
+        assert!(filetype_is_pdf(&single));
+
+        let batch = render_letters(&[letter(102151), letter(102152), letter(102153)], &config()).unwrap();
+        assert!(filetype_is_pdf(&batch));
+        assert!(batch.len() > single.len());
+
+        assert!(matches!(render_letters(&[], &config()), Err(ServiceError::NoCredentials)));
+    }
+
+    #[test]
+    fn test_strip_diacritics() {
+        assert_eq!(strip_diacritics("Přihláška Žluťoučký kůň"), "Prihlaska Zlutoucky kun");
+    }
+}
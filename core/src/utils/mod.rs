@@ -1,4 +1,6 @@
 pub mod csv;
 pub mod filetype;
 pub mod db;
-pub mod date;
\ No newline at end of file
+pub mod date;
+pub mod totp;
+pub mod letter;
\ No newline at end of file
@@ -0,0 +1,107 @@
+//! Time-based one-time passwords (RFC 6238) compatible with common authenticator apps
+
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+/// Length of one time step in seconds
+pub const TOTP_STEP: i64 = 30;
+/// Number of digits of a code
+pub const TOTP_DIGITS: u32 = 6;
+/// Number of neighbouring steps accepted to tolerate clock drift
+pub const TOTP_SKEW: i64 = 1;
+
+const SECRET_BYTES: usize = 20;
+const BASE32: base32::Alphabet = base32::Alphabet::RFC4648 { padding: false };
+
+/// New random secret, base32 encoded as expected by authenticator apps
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_BYTES];
+    rand::thread_rng().fill_bytes(&mut secret);
+    base32::encode(BASE32, &secret)
+}
+
+/// `otpauth://` uri, usually shown to the user as a QR code
+pub fn provisioning_uri(secret: &str, account: &str, issuer: &str) -> String {
+    format!(
+        "otpauth://totp/{issuer}:{account}?secret={secret}&issuer={issuer}&algorithm=SHA1&digits={TOTP_DIGITS}&period={TOTP_STEP}",
+        issuer = urlencode(issuer),
+        account = urlencode(account),
+    )
+}
+
+fn urlencode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn step_at(unix_time: i64) -> i64 {
+    unix_time.div_euclid(TOTP_STEP)
+}
+
+/// HOTP value (RFC 4226) of the raw secret for the given counter
+fn hotp(secret: &[u8], counter: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&counter.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([hash[offset], hash[offset + 1], hash[offset + 2], hash[offset + 3]]) & 0x7fff_ffff;
+
+    format!("{:0width$}", binary % 10u32.pow(TOTP_DIGITS), width = TOTP_DIGITS as usize)
+}
+
+/// Code for the given step, None if the secret is not valid base32
+pub fn code_at_step(secret: &str, step: i64) -> Option<String> {
+    let secret = base32::decode(BASE32, secret)?;
+    Some(hotp(&secret, step as u64))
+}
+
+/// Returns the matched step if the code is valid at `unix_time`.
+/// Steps up to `last_step` are rejected, so every code can be used only once.
+pub fn verify(secret: &str, code: &str, unix_time: i64, last_step: Option<i64>) -> Option<i64> {
+    let code = code.trim();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let current = step_at(unix_time);
+    (current - TOTP_SKEW..=current + TOTP_SKEW)
+        .filter(|step| last_step.map_or(true, |last| *step > last))
+        .find(|step| code_at_step(secret, *step).as_deref() == Some(code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 6238 test secret "12345678901234567890"
+    const RFC_SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn test_rfc_6238_vectors() {
+        assert_eq!(code_at_step(RFC_SECRET, step_at(59)).unwrap(), "287082");
+        assert_eq!(code_at_step(RFC_SECRET, step_at(1111111109)).unwrap(), "081804");
+        assert_eq!(code_at_step(RFC_SECRET, step_at(1234567890)).unwrap(), "005924");
+        assert_eq!(code_at_step(RFC_SECRET, step_at(2000000000)).unwrap(), "279037");
+    }
+
+    #[test]
+    fn test_verify() {
+        let secret = generate_secret();
+        let now = 1_700_000_000;
+        let code = code_at_step(&secret, step_at(now)).unwrap();
+
+        assert_eq!(verify(&secret, &code, now, None), Some(step_at(now)));
+        // clock drift of one step is tolerated
+        assert_eq!(verify(&secret, &code, now + TOTP_STEP, None), Some(step_at(now)));
+        assert_eq!(verify(&secret, &code, now + 3 * TOTP_STEP, None), None);
+        // replay of an already used step
+        assert_eq!(verify(&secret, &code, now, Some(step_at(now))), None);
+        assert_eq!(verify(&secret, "abcdef", now, None), None);
+    }
+}
@@ -1,8 +1,9 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.3
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "admin")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -12,6 +13,24 @@ pub struct Model {
     #[sea_orm(column_type = "Text")]
     pub private_key: String,
     pub password: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub totp_secret: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub totp_recovery_codes: Option<String>,
+    pub totp_enabled_at: Option<DateTime>,
+    pub totp_last_step: Option<i64>,
+    /// Disabled admins can't log in, their key stays among the recipients
+    pub disabled_at: Option<DateTime>,
+    /// Key dropped by a key rotation, it's no longer a recipient of new data and the admin can't log in
+    pub key_retired_at: Option<DateTime>,
+    /// New admin whose key is a recipient of new data, but existing candidates aren't re-encrypted to it yet,
+    /// the admin can't log in until `cli admin activate` re-encrypts them
+    pub key_pending_at: Option<DateTime>,
+    /// `superadmin`, `clerk`, `staff` or `reviewer`
+    pub role: String,
+    /// JSON list of field of study codes a reviewer is assigned to
+    #[sea_orm(column_type = "Text", nullable)]
+    pub fields_of_study: Option<String>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
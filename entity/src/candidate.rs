@@ -1,8 +1,9 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.3
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "candidate")]
 pub struct Model {
     #[sea_orm(primary_key)]
@@ -19,6 +20,9 @@ pub struct Model {
     pub email: Option<String>,
     pub sex: Option<String>,
     pub personal_identification_number: String,
+    /// Keyed hash of the personal identification number for lookups without decryption
+    #[sea_orm(unique)]
+    pub personal_id_index: Option<String>,
     pub school_name: Option<String>,
     pub health_insurance: Option<String>,
     pub grades_json: Option<String>,
@@ -26,6 +30,7 @@ pub struct Model {
     pub second_school: Option<String>,
     pub test_language: Option<String>,
     pub encrypted_by_id: Option<i32>,
+    pub deadline_extension: Option<DateTime>,
     pub created_at: DateTime,
     pub updated_at: DateTime,
 }
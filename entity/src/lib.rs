@@ -6,4 +6,8 @@ pub mod parent;
 pub mod session;
 pub mod admin_session;
 pub mod session_trait;
-pub mod application;
\ No newline at end of file
+pub mod application;
+pub mod audit_log;
+pub mod field_of_study;
+pub mod admission_round;
+pub mod login_throttle;
\ No newline at end of file
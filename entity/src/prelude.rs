@@ -2,7 +2,11 @@
 
 pub use super::admin::Entity as Admin;
 pub use super::admin_session::Entity as AdminSession;
+pub use super::admission_round::Entity as AdmissionRound;
 pub use super::application::Entity as Application;
+pub use super::audit_log::Entity as AuditLog;
 pub use super::candidate::Entity as Candidate;
+pub use super::field_of_study::Entity as FieldOfStudy;
+pub use super::login_throttle::Entity as LoginThrottle;
 pub use super::parent::Entity as Parent;
 pub use super::session::Entity as Session;
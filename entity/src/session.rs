@@ -1,17 +1,24 @@
 //! SeaORM Entity. Generated by sea-orm-codegen 0.9.3
 
 use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
 
 use crate::session_trait::UserSession;
 
-#[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "session")]
 pub struct Model {
     #[sea_orm(primary_key, auto_increment = false)]
     pub id: Uuid,
     pub candidate_id: i32,
     pub ip_address: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub user_agent: Option<String>,
+    /// Candidate private key encrypted with a secret known only to the client
+    #[sea_orm(column_type = "Text", nullable)]
+    pub key_slot: Option<String>,
     pub created_at: DateTime,
+    pub last_seen_at: Option<DateTime>,
     pub expires_at: DateTime,
     pub updated_at: DateTime,
 }
@@ -4,11 +4,25 @@ export type Fetch = (input: RequestInfo, init?: RequestInit) => Promise<Response
 
 export const API_URL = '/api';
 
+// JSON body of api errors, `code` is stable, `message` is localized by Accept-Language
+export interface ErrorResponse {
+	code: string;
+	status: number;
+	message: string;
+	details: Record<string, string[]> | null;
+}
+
 export interface ApiError {
 	error: AxiosError | unknown;
 	msg: string;
+	code?: string;
+	details?: Record<string, string[]> | null;
 }
 
 export const errorHandler = (error: AxiosError | unknown, msg: string): ApiError => {
+	const data = (error as AxiosError<ErrorResponse>)?.response?.data;
+	if (data && typeof data === 'object' && 'code' in data) {
+		return { error, msg: data.message, code: data.code, details: data.details };
+	}
 	return { error, msg };
 };
@@ -1,4 +1,5 @@
 export interface AdminLogin {
 	adminId: number;
 	password: string;
+	code?: string;
 }
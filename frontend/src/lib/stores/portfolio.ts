@@ -10,9 +10,21 @@ export enum UploadStatus {
 	Submitted = 4
 }
 
+export interface ZipManifestEntry {
+	name: string;
+	kind: 'PDF' | 'IMAGE' | 'VIDEO';
+	size: number;
+}
+
+export interface ZipManifest {
+	entries: Array<ZipManifestEntry>;
+	totalSize: number;
+}
+
 export interface SubmissionProgress {
 	status?: UploadStatus;
 	files?: [number];
+	manifest?: ZipManifest | null;
 }
 export const submissionProgress = writable<SubmissionProgress>({});
 
@@ -11,6 +11,21 @@ mod m20221112_112212_create_parent_candidate_fk;
 mod m20221221_162232_create_admin_session;
 mod m20230114_114628_create_application;
 mod m20230114_114826_create_application_candidate_fk;
+mod m20230601_101500_create_audit_log;
+mod m20230605_080000_create_field_of_study;
+mod m20230610_090000_create_admission_round;
+mod m20230610_091000_create_admission_round_field_of_study_fk;
+mod m20230620_100000_add_candidate_deadline_extension;
+mod m20230625_120000_add_admin_totp;
+mod m20230701_090000_add_admin_session_key_slot;
+mod m20230702_090000_add_session_key_slot;
+mod m20230705_080000_create_login_throttle;
+mod m20230708_100000_add_session_client_info;
+mod m20230712_090000_add_admin_disabled_at;
+mod m20230715_090000_add_admin_role;
+mod m20230718_090000_add_candidate_personal_id_index;
+mod m20230720_090000_add_admin_key_retired_at;
+mod m20230722_090000_add_admin_key_pending_at;
 pub struct Migrator;
 
 #[async_trait::async_trait]
@@ -23,6 +38,20 @@ impl MigratorTrait for Migrator {
             Box::new(m20221025_154422_create_session::Migration),
             Box::new(m20221221_162232_create_admin_session::Migration),
             Box::new(m20230114_114628_create_application::Migration),
+            Box::new(m20230601_101500_create_audit_log::Migration),
+            Box::new(m20230605_080000_create_field_of_study::Migration),
+            Box::new(m20230610_090000_create_admission_round::Migration),
+            Box::new(m20230620_100000_add_candidate_deadline_extension::Migration),
+            Box::new(m20230625_120000_add_admin_totp::Migration),
+            Box::new(m20230701_090000_add_admin_session_key_slot::Migration),
+            Box::new(m20230702_090000_add_session_key_slot::Migration),
+            Box::new(m20230705_080000_create_login_throttle::Migration),
+            Box::new(m20230708_100000_add_session_client_info::Migration),
+            Box::new(m20230712_090000_add_admin_disabled_at::Migration),
+            Box::new(m20230715_090000_add_admin_role::Migration),
+            Box::new(m20230718_090000_add_candidate_personal_id_index::Migration),
+            Box::new(m20230720_090000_add_admin_key_retired_at::Migration),
+            Box::new(m20230722_090000_add_admin_key_pending_at::Migration),
         ];
 
         if cfg!(debug_assertions) || cfg!(test) {
@@ -40,6 +69,7 @@ impl MigratorTrait for Migrator {
                 m20221028_194728_session_create_admin_fk::Migration,
             ));
             migrations.push(Box::new(m20230114_114826_create_application_candidate_fk::Migration));
+            migrations.push(Box::new(m20230610_091000_create_admission_round_field_of_study_fk::Migration));
         }
 
         migrations
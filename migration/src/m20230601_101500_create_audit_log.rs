@@ -0,0 +1,82 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLog::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuditLog::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AuditLog::AdminId).integer())
+                    .col(ColumnDef::new(AuditLog::CandidateId).integer())
+                    .col(ColumnDef::new(AuditLog::ApplicationId).integer())
+                    .col(ColumnDef::new(AuditLog::Action).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Detail).text())
+                    .col(ColumnDef::new(AuditLog::CreatedAt).date_time().not_null())
+                    .col(ColumnDef::new(AuditLog::PrevHash).string().not_null())
+                    .col(ColumnDef::new(AuditLog::Hash).string().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_audit_log_candidate_id")
+                .table(AuditLog::Table)
+                .col(AuditLog::CandidateId)
+                .to_owned(),
+        ).await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_audit_log_admin_id")
+                .table(AuditLog::Table)
+                .col(AuditLog::AdminId)
+                .to_owned(),
+        ).await?;
+
+        // every entry has exactly one successor, concurrent writers can't fork the chain
+        manager.create_index(
+            Index::create()
+                .name("idx_audit_log_prev_hash")
+                .table(AuditLog::Table)
+                .col(AuditLog::PrevHash)
+                .unique()
+                .to_owned(),
+        ).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLog::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum AuditLog {
+    Table,
+    Id,
+    AdminId,
+    CandidateId,
+    ApplicationId,
+    Action,
+    Detail,
+    CreatedAt,
+    PrevHash,
+    Hash,
+}
@@ -0,0 +1,72 @@
+use entity::field_of_study;
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ActiveModelTrait, Set},
+};
+
+/// Fields of study offered before they were configurable, prefix is the start of application id
+const INITIAL_FIELDS: [(&str, &str, &str, i32); 3] = [
+    ("101", "G", "7941K41-Gymnázium", 30),
+    ("102", "IT", "1820M01-Informační technologie", 60),
+    ("103", "KB", "1820M01-Informační technologie - Kybernetická bezpečnost", 30),
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(FieldOfStudy::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(FieldOfStudy::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(FieldOfStudy::Prefix).string().not_null().unique_key())
+                    .col(ColumnDef::new(FieldOfStudy::Code).string().not_null())
+                    .col(ColumnDef::new(FieldOfStudy::Name).string().not_null())
+                    .col(ColumnDef::new(FieldOfStudy::Capacity).integer().not_null())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        for (prefix, code, name, capacity) in INITIAL_FIELDS {
+            field_of_study::ActiveModel {
+                prefix: Set(prefix.to_owned()),
+                code: Set(code.to_owned()),
+                name: Set(name.to_owned()),
+                capacity: Set(capacity),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(FieldOfStudy::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum FieldOfStudy {
+    Table,
+    Id,
+    Prefix,
+    Code,
+    Name,
+    Capacity,
+}
@@ -0,0 +1,69 @@
+use chrono::NaiveDate;
+use entity::admission_round;
+use sea_orm_migration::{
+    prelude::*,
+    sea_orm::{ActiveModelTrait, Set},
+};
+
+/// Exam days that used to be hard-coded in the candidate CSV
+const INITIAL_ROUNDS: [(i32, u32, u32, &str); 2] = [
+    (2023, 4, 13, "13. 4."),
+    (2023, 4, 14, "14. 4."),
+];
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AdmissionRound::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AdmissionRound::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(AdmissionRound::Date).date().not_null())
+                    .col(ColumnDef::new(AdmissionRound::Label).string().not_null())
+                    .col(ColumnDef::new(AdmissionRound::FieldOfStudyId).integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        let db = manager.get_connection();
+        for (year, month, day, label) in INITIAL_ROUNDS {
+            admission_round::ActiveModel {
+                date: Set(NaiveDate::from_ymd_opt(year, month, day).unwrap()),
+                label: Set(label.to_owned()),
+                field_of_study_id: Set(None),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AdmissionRound::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum AdmissionRound {
+    Table,
+    Id,
+    Date,
+    Label,
+    FieldOfStudyId,
+}
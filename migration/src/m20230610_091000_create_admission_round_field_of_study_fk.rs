@@ -0,0 +1,26 @@
+use sea_orm_migration::prelude::*;
+
+use crate::{m20230605_080000_create_field_of_study::FieldOfStudy, m20230610_090000_create_admission_round::AdmissionRound};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.create_foreign_key(ForeignKey::create()
+            .name("admission_round_field_of_study_fk")
+            .from(AdmissionRound::Table, AdmissionRound::FieldOfStudyId)
+            .to(FieldOfStudy::Table, FieldOfStudy::Id)
+            .on_delete(ForeignKeyAction::SetNull)
+            .on_update(ForeignKeyAction::Cascade)
+            .to_owned()).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_foreign_key(ForeignKey::drop()
+            .name("admission_round_field_of_study_fk")
+            .table(AdmissionRound::Table)
+            .to_owned()).await
+    }
+}
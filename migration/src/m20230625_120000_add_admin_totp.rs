@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admin::Table)
+                    .add_column(ColumnDef::new(Admin::TotpSecret).text())
+                    .add_column(ColumnDef::new(Admin::TotpRecoveryCodes).text())
+                    .add_column(ColumnDef::new(Admin::TotpEnabledAt).date_time())
+                    .add_column(ColumnDef::new(Admin::TotpLastStep).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admin::Table)
+                    .drop_column(Admin::TotpSecret)
+                    .drop_column(Admin::TotpRecoveryCodes)
+                    .drop_column(Admin::TotpEnabledAt)
+                    .drop_column(Admin::TotpLastStep)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Admin {
+    Table,
+    TotpSecret,
+    TotpRecoveryCodes,
+    TotpEnabledAt,
+    TotpLastStep,
+}
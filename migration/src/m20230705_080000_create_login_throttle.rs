@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(LoginThrottle::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(LoginThrottle::Key)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(LoginThrottle::Failures).integer().not_null())
+                    .col(ColumnDef::new(LoginThrottle::WindowStart).date_time().not_null())
+                    .col(ColumnDef::new(LoginThrottle::LockedUntil).date_time())
+                    .col(ColumnDef::new(LoginThrottle::UpdatedAt).date_time().not_null())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(LoginThrottle::Table).to_owned())
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+pub enum LoginThrottle {
+    Table,
+    Key,
+    Failures,
+    WindowStart,
+    LockedUntil,
+    UpdatedAt,
+}
@@ -0,0 +1,66 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Session::Table)
+                    .add_column(ColumnDef::new(Session::UserAgent).text())
+                    .add_column(ColumnDef::new(Session::LastSeenAt).date_time())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AdminSession::Table)
+                    .add_column(ColumnDef::new(AdminSession::UserAgent).text())
+                    .add_column(ColumnDef::new(AdminSession::LastSeenAt).date_time())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Session::Table)
+                    .drop_column(Session::UserAgent)
+                    .drop_column(Session::LastSeenAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AdminSession::Table)
+                    .drop_column(AdminSession::UserAgent)
+                    .drop_column(AdminSession::LastSeenAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Session {
+    Table,
+    UserAgent,
+    LastSeenAt,
+}
+
+#[derive(Iden)]
+enum AdminSession {
+    Table,
+    UserAgent,
+    LastSeenAt,
+}
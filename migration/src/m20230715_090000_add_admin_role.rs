@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing admins keep full access
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admin::Table)
+                    .add_column(
+                        ColumnDef::new(Admin::Role)
+                            .string()
+                            .not_null()
+                            .default("superadmin"),
+                    )
+                    .add_column(ColumnDef::new(Admin::FieldsOfStudy).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Admin::Table)
+                    .drop_column(Admin::Role)
+                    .drop_column(Admin::FieldsOfStudy)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Admin {
+    Table,
+    Role,
+    FieldsOfStudy,
+}
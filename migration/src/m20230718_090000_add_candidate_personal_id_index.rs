@@ -0,0 +1,54 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // filled in for existing candidates by `cli blind_index`,
+        // unique so that concurrent imports can't create the same candidate twice
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candidate::Table)
+                    .add_column(ColumnDef::new(Candidate::PersonalIdIndex).string_len(64))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager.create_index(
+            Index::create()
+                .name("idx_candidate_personal_id_index")
+                .table(Candidate::Table)
+                .col(Candidate::PersonalIdIndex)
+                .unique()
+                .to_owned(),
+        ).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.drop_index(
+            Index::drop()
+                .name("idx_candidate_personal_id_index")
+                .table(Candidate::Table)
+                .to_owned(),
+        ).await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Candidate::Table)
+                    .drop_column(Candidate::PersonalIdIndex)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+/// Learn more at https://docs.rs/sea-query#iden
+#[derive(Iden)]
+enum Candidate {
+    Table,
+    PersonalIdIndex,
+}